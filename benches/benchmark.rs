@@ -1,7 +1,7 @@
 use criterion::{black_box, criterion_group, criterion_main, Bencher, Criterion};
 use dbgen::{
     eval::{CompileContext, State},
-    format::Options,
+    format::{CsvFormat, Format, Options, SqlFormat},
     parser::Template,
     span::Registry,
 };
@@ -12,26 +12,39 @@ use std::{
     io::{sink, Write},
 };
 
-fn run_benchmark(b: &mut Bencher<'_>, path: &str) {
+fn run_benchmark(b: &mut Bencher<'_>, path: &str, format: &dyn Format) {
     let mut registry = Registry::default();
-    let mut template = Template::parse(&read_to_string(path).unwrap(), &[], None, &mut registry).unwrap();
+    let mut template = Template::parse(
+        &read_to_string(path).unwrap(),
+        Some(path.as_ref()),
+        &[],
+        None,
+        &mut registry,
+    )
+    .unwrap();
     let ctx = CompileContext::new(template.variables_count);
     let row = ctx.compile_row(template.tables.swap_remove(0).exprs).unwrap();
     let mut state = State::new(1, Box::new(Hc128Rng::from_seed([0x41; 32])), ctx);
-    let options = Options::default();
     let mut sink: Box<dyn Write> = Box::new(sink());
+    let mut values = Vec::new();
 
     b.iter(move || {
-        let values = black_box(&row).eval(black_box(&mut state)).unwrap();
-        for value in values {
-            options.write_sql_value(black_box(&mut *sink), &value).unwrap();
+        black_box(&row).eval_into(black_box(&mut state), &mut values).unwrap();
+        for (i, value) in values.iter().enumerate() {
+            if i != 0 {
+                format.write_value_separator(black_box(&mut *sink)).unwrap();
+            }
+            format.write_value(black_box(&mut *sink), value).unwrap();
         }
     });
 }
 
 fn bench_templates(c: &mut Criterion) {
     c.bench_function("sysbench_oltp_uniform", |b| {
-        run_benchmark(b, "res/sysbench/oltp_uniform_mysql.sql");
+        run_benchmark(b, "res/sysbench/oltp_uniform_mysql.sql", &SqlFormat(Options::default()));
+    });
+    c.bench_function("sysbench_oltp_uniform_csv", |b| {
+        run_benchmark(b, "res/sysbench/oltp_uniform_mysql.sql", &CsvFormat(Options::default()));
     });
 }
 
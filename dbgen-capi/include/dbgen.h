@@ -0,0 +1,177 @@
+#ifndef DBGEN_H
+#define DBGEN_H
+
+/* This file is generated by cbindgen. Do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Formats the rows as SQL INSERT statements.
+#define DBGEN_FORMAT_SQL 0
+
+// Formats the rows as CSV.
+#define DBGEN_FORMAT_CSV 1
+
+// Formats the rows as SQL `INSERT … SET` statements.
+#define DBGEN_FORMAT_SQL_INSERT_SET 2
+
+// The type of a cell.
+typedef enum DbgenCellKind {
+  // NULL.
+  DBGEN_CELL_KIND_NULL,
+  // A boolean stored in `integer` as 0 or 1.
+  DBGEN_CELL_KIND_BOOL,
+  // A signed integer stored in `integer`.
+  DBGEN_CELL_KIND_INT,
+  // An unsigned integer larger than `INT64_MAX`, stored in `integer` which should be
+  // reinterpreted as `uint64_t`.
+  DBGEN_CELL_KIND_UINT,
+  // A floating point number stored in `real`. Integers outside the range of both `int64_t`
+  // and `uint64_t` are also stored as `Float`, and may lose precision.
+  DBGEN_CELL_KIND_FLOAT,
+  // A UTF-8 string stored in `data`.
+  DBGEN_CELL_KIND_STRING,
+  // A binary string stored in `data`.
+  DBGEN_CELL_KIND_BYTES,
+  // A timestamp stored in `integer` as microseconds since 1970-01-01 00:00:00 UTC. `data`
+  // contains the timestamp in the local time zone, formatted as `YYYY-MM-DD hh:mm:ss.ffffff`.
+  DBGEN_CELL_KIND_TIMESTAMP,
+  // An interval stored in `integer` as microseconds.
+  DBGEN_CELL_KIND_INTERVAL,
+  // An array formatted as an SQL `ARRAY[…]` literal in `data`.
+  DBGEN_CELL_KIND_ARRAY,
+  // A JSON value formatted as JSON text in `data`.
+  DBGEN_CELL_KIND_JSON,
+  // An exact decimal number formatted as text in `data`, e.g. `12.50`. `real` contains the
+  // nearest floating point number.
+  DBGEN_CELL_KIND_DECIMAL,
+} DbgenCellKind;
+
+// Typed values of some rows.
+typedef struct DbgenBatch DbgenBatch;
+
+// A template compiled together with its seed.
+typedef struct DbgenGenerator DbgenGenerator;
+
+// A cursor of the rows of a table.
+typedef struct DbgenRows DbgenRows;
+
+// A byte buffer owned by the caller, which must be released by `dbgen_bytes_free()`.
+typedef struct DbgenBytes {
+  // The content.
+  uint8_t *data;
+  // The length of the content.
+  size_t len;
+} DbgenBytes;
+
+// Options of creating a generator. Every field can be NULL to use the default.
+typedef struct DbgenOptions {
+  // The seed as a 64-digit hex string. The default is a random seed.
+  const char *seed;
+  // The random number generator, same as the `--rng` flag of the CLI. The default is
+  // `"hc128"`.
+  const char *rng;
+  // The current timestamp in UTC, in the form `YYYY-MM-DD hh:mm:ss.ffffff`. The default is the
+  // time when the generator is created.
+  const char *now;
+  // The time zone used to parse and format timestamps. The default is `"UTC"`.
+  const char *time_zone;
+  // The path to the tz database. The default is `"/usr/share/zoneinfo"`.
+  const char *zoneinfo;
+} DbgenOptions;
+
+// A typed value, borrowed from a `DbgenBatch`.
+typedef struct DbgenCell {
+  // The type of this cell, which determines which of the other fields are used.
+  DbgenCellKind kind;
+  // The integer content.
+  int64_t integer;
+  // The floating point content.
+  double real;
+  // The string content, which is not NUL-terminated. This is NULL if unused.
+  const uint8_t *data;
+  // The length of the string content.
+  size_t len;
+} DbgenCell;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the version of this library as a static string.
+const char *dbgen_version(void);
+
+// Releases a string returned by this library. Does nothing if `s` is NULL.
+void dbgen_string_free(char *s);
+
+// Releases a byte buffer returned by this library.
+void dbgen_bytes_free(DbgenBytes bytes);
+
+// Compiles a template from its source and evaluates its global expressions.
+//
+// `options` can be NULL to use the default options. Returns NULL on error, and stores the error
+// message into `*error` if `error` is not NULL.
+DbgenGenerator *dbgen_generator_new(const char *source, const DbgenOptions *options, char **error);
+
+// Releases a generator. Does nothing if `generator` is NULL.
+void dbgen_generator_free(DbgenGenerator *generator);
+
+// Creates a cursor of the rows of a table, starting from the first row.
+//
+// Every cursor of the same table produces the same rows. Returns NULL on error (including when
+// `generator` is NULL), and stores the error message into `*error` if `error` is not NULL.
+DbgenRows *dbgen_rows_new(const DbgenGenerator *generator, const char *table, char **error);
+
+// Releases a cursor. Does nothing if `rows` is NULL.
+void dbgen_rows_free(DbgenRows *rows);
+
+// Returns the number of columns of the table, or 0 if `rows` is NULL.
+size_t dbgen_rows_columns_count(const DbgenRows *rows);
+
+// Returns the name of a column without quotation marks, or NULL if `rows` is NULL or the index
+// is out of range.
+//
+// The string is valid until the cursor is freed.
+const char *dbgen_rows_column_name(const DbgenRows *rows, size_t index);
+
+// Generates the next `n` rows, formatted as a single INSERT statement or a part of CSV.
+//
+// `format` is one of the `DBGEN_FORMAT_*` constants. The first call on a cursor also includes
+// the file header, e.g. the column names of CSV. Returns false on error (including when `rows` or
+// `output` is NULL), and stores the error message into `*error` if `error` is not NULL. On
+// success, `*output` must be released by `dbgen_bytes_free()`.
+bool dbgen_rows_next_formatted(DbgenRows *rows,
+                               size_t n,
+                               uint32_t format,
+                               DbgenBytes *output,
+                               char **error);
+
+// Generates the next `n` rows as typed cells.
+//
+// Returns NULL on error (including when `rows` is NULL), and stores the error message into
+// `*error` if `error` is not NULL.
+DbgenBatch *dbgen_rows_next_batch(DbgenRows *rows, size_t n, char **error);
+
+// Releases a batch. Does nothing if `batch` is NULL.
+void dbgen_batch_free(DbgenBatch *batch);
+
+// Returns the number of rows in the batch, or 0 if `batch` is NULL.
+size_t dbgen_batch_rows_count(const DbgenBatch *batch);
+
+// Returns the number of columns in the batch, or 0 if `batch` is NULL.
+size_t dbgen_batch_columns_count(const DbgenBatch *batch);
+
+// Returns a cell of the batch. Returns a NULL cell if `batch` is NULL, or the row or column is
+// out of range.
+//
+// The `data` of the cell is valid until the batch is freed.
+DbgenCell dbgen_batch_cell(const DbgenBatch *batch, size_t row, size_t column);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* DBGEN_H */
@@ -0,0 +1,828 @@
+//! C API of `dbgen`.
+//!
+//! The header `include/dbgen.h` is generated from this file by `cbindgen`.
+//!
+//! Panics never unwind into the caller. Functions reporting errors through an `error` argument
+//! report a panic as an error, while other functions return NULL, zero or a NULL cell instead.
+//! Passing NULL as an object is reported the same way. Catching panics requires
+//! `panic = "unwind"`, so the library should be built with `--profile release-capi`; with the
+//! workspace's `release` profile a panic aborts the process instead.
+//!
+//! # Ownership rules
+//!
+//! * Every object returned by a `dbgen_*_new` or `dbgen_*_next_*` function is owned by the
+//!   caller, and must be released by the matching `dbgen_*_free` function exactly once.
+//! * Error strings are allocated by this library and must be released by
+//!   `dbgen_string_free()`. Strings and pointers returned by accessors (such as
+//!   `dbgen_rows_column_name()` and the `data` of `DbgenCell`) are borrowed from their parent
+//!   object, and remain valid until the parent is freed.
+//! * A `DbgenGenerator` is immutable, so it can be used by multiple threads at the same time.
+//!   A `DbgenRows` cursor keeps a reference to the generator's data, so the generator can be
+//!   freed before its cursors.
+//! * A `DbgenRows` cursor or a `DbgenBatch` may be moved to another thread, but must not be
+//!   used by multiple threads at the same time.
+
+#![allow(clippy::missing_safety_doc)]
+
+use chrono::{NaiveDateTime, TimeZone};
+use dbgen::{
+    eval::Schema,
+    format::{CsvFormat, Format, Options, SqlFormat, SqlInsertSetFormat},
+    generator::{Generator, Rows},
+    rng::{RngName, Seed},
+    span::{Registry, S},
+    value::{Value, TIMESTAMP_FORMAT},
+};
+use serde::de::{Deserializer, Error as DeError, Visitor};
+use std::{
+    any::Any,
+    ffi::{CStr, CString},
+    fmt, mem,
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::Arc,
+};
+
+/// Formats the rows as SQL INSERT statements.
+pub const DBGEN_FORMAT_SQL: u32 = 0;
+/// Formats the rows as CSV.
+pub const DBGEN_FORMAT_CSV: u32 = 1;
+/// Formats the rows as SQL `INSERT … SET` statements.
+pub const DBGEN_FORMAT_SQL_INSERT_SET: u32 = 2;
+
+/// A template compiled together with its seed.
+pub struct DbgenGenerator {
+    generator: Generator,
+    registry: Arc<Registry>,
+}
+
+/// A cursor of the rows of a table.
+pub struct DbgenRows {
+    rows: Rows,
+    schema: Schema,
+    column_names: Vec<CString>,
+    registry: Arc<Registry>,
+    wrote_file_header: bool,
+}
+
+/// Typed values of some rows.
+pub struct DbgenBatch {
+    /// The values of every row. These are kept alive because the cells point to them.
+    #[allow(dead_code)]
+    rows: Vec<Vec<Value>>,
+    /// Content of cells which do not exist in the values, e.g. formatted timestamps.
+    #[allow(dead_code)]
+    texts: Vec<Vec<u8>>,
+    cells: Vec<DbgenCell>,
+    columns_count: usize,
+}
+
+/// Options of creating a generator. Every field can be NULL to use the default.
+#[repr(C)]
+pub struct DbgenOptions {
+    /// The seed as a 64-digit hex string. The default is a random seed.
+    pub seed: *const c_char,
+    /// The random number generator, same as the `--rng` flag of the CLI. The default is
+    /// `"hc128"`.
+    pub rng: *const c_char,
+    /// The current timestamp in UTC, in the form `YYYY-MM-DD hh:mm:ss.ffffff`. The default is the
+    /// time when the generator is created.
+    pub now: *const c_char,
+    /// The time zone used to parse and format timestamps. The default is `"UTC"`.
+    pub time_zone: *const c_char,
+    /// The path to the tz database. The default is `"/usr/share/zoneinfo"`.
+    pub zoneinfo: *const c_char,
+}
+
+/// A byte buffer owned by the caller, which must be released by `dbgen_bytes_free()`.
+#[repr(C)]
+pub struct DbgenBytes {
+    /// The content.
+    pub data: *mut u8,
+    /// The length of the content.
+    pub len: usize,
+}
+
+/// The type of a cell.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DbgenCellKind {
+    /// NULL.
+    Null,
+    /// A boolean stored in `integer` as 0 or 1.
+    Bool,
+    /// A signed integer stored in `integer`.
+    Int,
+    /// An unsigned integer larger than `INT64_MAX`, stored in `integer` which should be
+    /// reinterpreted as `uint64_t`.
+    Uint,
+    /// A floating point number stored in `real`. Integers outside the range of both `int64_t`
+    /// and `uint64_t` are also stored as `Float`, and may lose precision.
+    Float,
+    /// A UTF-8 string stored in `data`.
+    String,
+    /// A binary string stored in `data`.
+    Bytes,
+    /// A timestamp stored in `integer` as microseconds since 1970-01-01 00:00:00 UTC. `data`
+    /// contains the timestamp in the local time zone, formatted as `YYYY-MM-DD hh:mm:ss.ffffff`.
+    Timestamp,
+    /// An interval stored in `integer` as microseconds.
+    Interval,
+    /// An array formatted as an SQL `ARRAY[…]` literal in `data`.
+    Array,
+    /// A JSON value formatted as JSON text in `data`.
+    Json,
+    /// An exact decimal number formatted as text in `data`, e.g. `12.50`. `real` contains the
+    /// nearest floating point number.
+    Decimal,
+}
+
+/// A typed value, borrowed from a `DbgenBatch`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct DbgenCell {
+    /// The type of this cell, which determines which of the other fields are used.
+    pub kind: DbgenCellKind,
+    /// The integer content.
+    pub integer: i64,
+    /// The floating point content.
+    pub real: f64,
+    /// The string content, which is not NUL-terminated. This is NULL if unused.
+    pub data: *const u8,
+    /// The length of the string content.
+    pub len: usize,
+}
+
+impl DbgenCell {
+    const NULL: Self = Self {
+        kind: DbgenCellKind::Null,
+        integer: 0,
+        real: 0.0,
+        data: ptr::null(),
+        len: 0,
+    };
+
+    fn with_text(kind: DbgenCellKind, integer: i64, text: &[u8]) -> Self {
+        Self {
+            kind,
+            integer,
+            data: text.as_ptr(),
+            len: text.len(),
+            ..Self::NULL
+        }
+    }
+}
+
+/// Converts a NUL-terminated string argument, where NULL becomes `None`.
+unsafe fn optional_str<'a>(s: *const c_char, name: &str) -> Result<Option<&'a str>, String> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map(Some)
+        .map_err(|e| format!("Error: {} is not valid UTF-8: {}", name, e))
+}
+
+/// Converts a required NUL-terminated string argument.
+unsafe fn required_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, String> {
+    optional_str(s, name)?.ok_or_else(|| format!("Error: {} must not be NULL", name))
+}
+
+/// Converts a required pointer argument.
+unsafe fn required_ref<'a, T>(p: *const T, name: &str) -> Result<&'a T, String> {
+    p.as_ref().ok_or_else(|| format!("Error: {} must not be NULL", name))
+}
+
+/// Converts a required mutable pointer argument.
+unsafe fn required_mut<'a, T>(p: *mut T, name: &str) -> Result<&'a mut T, String> {
+    p.as_mut().ok_or_else(|| format!("Error: {} must not be NULL", name))
+}
+
+/// Converts a string into a C string, replacing interior NULs.
+fn to_c_string(s: String) -> CString {
+    CString::new(s).unwrap_or_else(|e| {
+        let s = String::from_utf8_lossy(&e.into_vec()).replace('\0', "\u{fffd}");
+        CString::new(s).expect("all NULs are replaced")
+    })
+}
+
+/// Describes the payload of a panic.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("Error: internal panic: {}", message)
+}
+
+/// Runs `f` and reports its result to the caller, treating a panic as an error. On error, stores
+/// the message into `error` if it is not NULL, and returns `None`.
+unsafe fn report<T>(error: *mut *mut c_char, f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(panic_message(&*payload)));
+    match result {
+        Ok(value) => Some(value),
+        Err(message) => {
+            if !error.is_null() {
+                *error = to_c_string(message).into_raw();
+            }
+            None
+        }
+    }
+}
+
+/// Runs `f` for a function which cannot report errors, returning `default` if it panics.
+fn catch<T>(default: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
+
+/// Returns the version of this library as a static string.
+#[no_mangle]
+pub extern "C" fn dbgen_version() -> *const c_char {
+    catch(ptr::null(), || concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast())
+}
+
+/// Releases a string returned by this library. Does nothing if `s` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_string_free(s: *mut c_char) {
+    catch((), || {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    })
+}
+
+/// Releases a byte buffer returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_bytes_free(bytes: DbgenBytes) {
+    catch((), || {
+        if !bytes.data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes.data, bytes.len)));
+        }
+    })
+}
+
+/// Compiles a template from its source and evaluates its global expressions.
+///
+/// `options` can be NULL to use the default options. Returns NULL on error, and stores the error
+/// message into `*error` if `error` is not NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_generator_new(
+    source: *const c_char,
+    options: *const DbgenOptions,
+    error: *mut *mut c_char,
+) -> *mut DbgenGenerator {
+    report(error, || generator_new(source, options.as_ref())).map_or(ptr::null_mut(), |g| Box::into_raw(Box::new(g)))
+}
+
+unsafe fn generator_new(source: *const c_char, options: Option<&DbgenOptions>) -> Result<DbgenGenerator, String> {
+    let mut builder = Generator::builder(required_str(source, "template")?);
+    if let Some(options) = options {
+        if let Some(seed) = optional_str(options.seed, "seed")? {
+            let seed = seed
+                .parse::<Seed>()
+                .map_err(|e| format!("Error: invalid seed: {}", e))?;
+            builder = builder.seed(seed);
+        }
+        if let Some(rng) = optional_str(options.rng, "rng")? {
+            builder = builder.rng(rng.parse::<RngName>().map_err(|e| format!("Error: {}", e))?);
+        }
+        if let Some(now) = optional_str(options.now, "now")? {
+            let now = NaiveDateTime::parse_from_str(now, TIMESTAMP_FORMAT)
+                .map_err(|e| format!("Error: invalid timestamp {}: {}", now, e))?;
+            builder = builder.now(now);
+        }
+        if let Some(time_zone) = optional_str(options.time_zone, "time_zone")? {
+            builder = builder.time_zone(time_zone);
+        }
+        if let Some(zoneinfo) = optional_str(options.zoneinfo, "zoneinfo")? {
+            builder = builder.zoneinfo(zoneinfo);
+        }
+    }
+    let mut registry = Registry::default();
+    let generator = builder.build(&mut registry).map_err(|e| registry.describe(&e))?;
+    Ok(DbgenGenerator {
+        generator,
+        registry: Arc::new(registry),
+    })
+}
+
+/// Releases a generator. Does nothing if `generator` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_generator_free(generator: *mut DbgenGenerator) {
+    catch((), || {
+        if !generator.is_null() {
+            drop(Box::from_raw(generator));
+        }
+    })
+}
+
+/// Creates a cursor of the rows of a table, starting from the first row.
+///
+/// Every cursor of the same table produces the same rows. Returns NULL on error (including when
+/// `generator` is NULL), and stores the error message into `*error` if `error` is not NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_rows_new(
+    generator: *const DbgenGenerator,
+    table: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut DbgenRows {
+    report(error, || rows_new(required_ref(generator, "generator")?, table))
+        .map_or(ptr::null_mut(), |r| Box::into_raw(Box::new(r)))
+}
+
+unsafe fn rows_new(generator: &DbgenGenerator, table: *const c_char) -> Result<DbgenRows, String> {
+    let table = required_str(table, "table")?;
+    let schema = generator
+        .generator
+        .schema(table)
+        .ok_or_else(|| format!("Error: unknown table {}", table))?;
+    let column_names = schema.unescaped_column_names().map(to_c_string).collect();
+    Ok(DbgenRows {
+        rows: generator.generator.rows(table),
+        schema,
+        column_names,
+        registry: generator.registry.clone(),
+        wrote_file_header: false,
+    })
+}
+
+/// Releases a cursor. Does nothing if `rows` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_rows_free(rows: *mut DbgenRows) {
+    catch((), || {
+        if !rows.is_null() {
+            drop(Box::from_raw(rows));
+        }
+    })
+}
+
+/// Returns the number of columns of the table, or 0 if `rows` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_rows_columns_count(rows: *const DbgenRows) -> usize {
+    catch(0, || rows.as_ref().map_or(0, |rows| rows.column_names.len()))
+}
+
+/// Returns the name of a column without quotation marks, or NULL if `rows` is NULL or the index
+/// is out of range.
+///
+/// The string is valid until the cursor is freed.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_rows_column_name(rows: *const DbgenRows, index: usize) -> *const c_char {
+    catch(ptr::null(), || {
+        rows.as_ref()
+            .and_then(|rows| rows.column_names.get(index))
+            .map_or(ptr::null(), |name| name.as_ptr())
+    })
+}
+
+impl DbgenRows {
+    /// Generates the next `n` rows.
+    fn next_rows(&mut self, n: usize) -> Result<Vec<Vec<Value>>, String> {
+        let registry = &self.registry;
+        self.rows
+            .by_ref()
+            .take(n)
+            .collect::<Result<_, S<_>>>()
+            .map_err(|e| registry.describe(&e))
+    }
+}
+
+/// Generates the next `n` rows, formatted as a single INSERT statement or a part of CSV.
+///
+/// `format` is one of the `DBGEN_FORMAT_*` constants. The first call on a cursor also includes
+/// the file header, e.g. the column names of CSV. Returns false on error (including when `rows` or
+/// `output` is NULL), and stores the error message into `*error` if `error` is not NULL. On
+/// success, `*output` must be released by `dbgen_bytes_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_rows_next_formatted(
+    rows: *mut DbgenRows,
+    n: usize,
+    format: u32,
+    output: *mut DbgenBytes,
+    error: *mut *mut c_char,
+) -> bool {
+    report(error, || {
+        let rows = required_mut(rows, "rows")?;
+        let output = required_mut(output, "output")?;
+        let bytes = rows.next_formatted(n, format)?;
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()).cast();
+        *output = DbgenBytes { data, len };
+        Ok(())
+    })
+    .is_some()
+}
+
+impl DbgenRows {
+    fn next_formatted(&mut self, n: usize, format: u32) -> Result<Vec<u8>, String> {
+        let options = Options::default();
+        let format: Box<dyn Format> = match format {
+            DBGEN_FORMAT_SQL => Box::new(SqlFormat(options)),
+            DBGEN_FORMAT_CSV => Box::new(CsvFormat(options)),
+            DBGEN_FORMAT_SQL_INSERT_SET => Box::new(SqlInsertSetFormat(options)),
+            _ => return Err(format!("Error: unknown format {}", format)),
+        };
+        let rows = self.next_rows(n)?;
+
+        // writing into a Vec never fails.
+        let mut output = Vec::new();
+        if !mem::replace(&mut self.wrote_file_header, true) {
+            format.write_file_header(&mut output, &self.schema).unwrap();
+        }
+        for (row_index, row) in rows.iter().enumerate() {
+            if row_index == 0 {
+                format.write_header(&mut output, &self.schema).unwrap();
+            } else {
+                format.write_row_separator(&mut output).unwrap();
+            }
+            for (col_index, (column, value)) in self.schema.column_names().zip(row).enumerate() {
+                if col_index != 0 {
+                    format.write_value_separator(&mut output).unwrap();
+                }
+                format.write_value_header(&mut output, column).unwrap();
+                format.write_value(&mut output, value).unwrap();
+            }
+        }
+        if !rows.is_empty() {
+            format.write_trailer(&mut output).unwrap();
+        }
+        Ok(output)
+    }
+}
+
+/// Generates the next `n` rows as typed cells.
+///
+/// Returns NULL on error (including when `rows` is NULL), and stores the error message into
+/// `*error` if `error` is not NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_rows_next_batch(
+    rows: *mut DbgenRows,
+    n: usize,
+    error: *mut *mut c_char,
+) -> *mut DbgenBatch {
+    report(error, || {
+        let rows = required_mut(rows, "rows")?;
+        let columns_count = rows.column_names.len();
+        Ok(DbgenBatch::new(rows.next_rows(n)?, columns_count))
+    })
+    .map_or(ptr::null_mut(), |batch| Box::into_raw(Box::new(batch)))
+}
+
+impl DbgenBatch {
+    fn new(rows: Vec<Vec<Value>>, columns_count: usize) -> Self {
+        let mut texts = Vec::new();
+        let mut cells = Vec::with_capacity(rows.len() * columns_count);
+        for value in rows.iter().flatten() {
+            cells.push(to_cell(value, &mut texts));
+        }
+        Self {
+            rows,
+            texts,
+            cells,
+            columns_count,
+        }
+    }
+}
+
+/// Converts a value into a cell. If the cell needs some text which does not exist in the value,
+/// the text is stored into `texts`.
+fn to_cell(value: &Value, texts: &mut Vec<Vec<u8>>) -> DbgenCell {
+    let mut with_text = |kind, integer, text: Vec<u8>| {
+        let cell = DbgenCell::with_text(kind, integer, &text);
+        texts.push(text);
+        cell
+    };
+    match value {
+        Value::Null => DbgenCell::NULL,
+        Value::Number(_) => value.deserialize_any(NumberVisitor(texts)).unwrap_or(DbgenCell::NULL),
+        Value::Bytes(b) => {
+            let kind = if b.as_str().is_some() {
+                DbgenCellKind::String
+            } else {
+                DbgenCellKind::Bytes
+            };
+            DbgenCell::with_text(kind, 0, b.as_bytes())
+        }
+        Value::Timestamp(timestamp, tz) => {
+            let micros = timestamp.timestamp() * 1_000_000 + i64::from(timestamp.timestamp_subsec_micros());
+            let text = tz.from_utc_datetime(timestamp).format(TIMESTAMP_FORMAT).to_string();
+            with_text(DbgenCellKind::Timestamp, micros, text.into_bytes())
+        }
+        Value::Interval(micros) => DbgenCell {
+            kind: DbgenCellKind::Interval,
+            integer: *micros,
+            ..DbgenCell::NULL
+        },
+        Value::Array(_) => with_text(DbgenCellKind::Array, 0, value.to_string().into_bytes()),
+        Value::Json(json) => with_text(DbgenCellKind::Json, 0, serde_json::to_vec(&**json).unwrap_or_default()),
+    }
+}
+
+/// Visitor classifying a number through its serde representation. The text of decimals is
+/// stored into the vector.
+struct NumberVisitor<'a>(&'a mut Vec<Vec<u8>>);
+
+impl<'de> Visitor<'de> for NumberVisitor<'_> {
+    type Value = DbgenCell;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a number")
+    }
+
+    fn visit_bool<E: DeError>(self, v: bool) -> Result<DbgenCell, E> {
+        Ok(DbgenCell {
+            kind: DbgenCellKind::Bool,
+            integer: v.into(),
+            ..DbgenCell::NULL
+        })
+    }
+
+    fn visit_i64<E: DeError>(self, v: i64) -> Result<DbgenCell, E> {
+        Ok(DbgenCell {
+            kind: DbgenCellKind::Int,
+            integer: v,
+            ..DbgenCell::NULL
+        })
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn visit_u64<E: DeError>(self, v: u64) -> Result<DbgenCell, E> {
+        Ok(DbgenCell {
+            kind: DbgenCellKind::Uint,
+            integer: v as i64,
+            ..DbgenCell::NULL
+        })
+    }
+
+    /// Integers which fit neither `i64` nor `u64` cannot be represented exactly by a cell, so
+    /// they are converted to `Float`.
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_i128<E: DeError>(self, v: i128) -> Result<DbgenCell, E> {
+        self.visit_f64(v as f64)
+    }
+
+    fn visit_f64<E: DeError>(self, v: f64) -> Result<DbgenCell, E> {
+        Ok(DbgenCell {
+            kind: DbgenCellKind::Float,
+            real: v,
+            ..DbgenCell::NULL
+        })
+    }
+
+    fn visit_str<E: DeError>(self, v: &str) -> Result<DbgenCell, E> {
+        let text = v.as_bytes().to_owned();
+        let cell = DbgenCell {
+            real: v.parse().map_err(E::custom)?,
+            ..DbgenCell::with_text(DbgenCellKind::Decimal, 0, &text)
+        };
+        self.0.push(text);
+        Ok(cell)
+    }
+}
+
+/// Releases a batch. Does nothing if `batch` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_batch_free(batch: *mut DbgenBatch) {
+    catch((), || {
+        if !batch.is_null() {
+            drop(Box::from_raw(batch));
+        }
+    })
+}
+
+/// Returns the number of rows in the batch, or 0 if `batch` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_batch_rows_count(batch: *const DbgenBatch) -> usize {
+    catch(0, || {
+        batch
+            .as_ref()
+            .and_then(|batch| batch.cells.len().checked_div(batch.columns_count))
+            .unwrap_or(0)
+    })
+}
+
+/// Returns the number of columns in the batch, or 0 if `batch` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_batch_columns_count(batch: *const DbgenBatch) -> usize {
+    catch(0, || batch.as_ref().map_or(0, |batch| batch.columns_count))
+}
+
+/// Returns a cell of the batch. Returns a NULL cell if `batch` is NULL, or the row or column is
+/// out of range.
+///
+/// The `data` of the cell is valid until the batch is freed.
+#[no_mangle]
+pub unsafe extern "C" fn dbgen_batch_cell(batch: *const DbgenBatch, row: usize, column: usize) -> DbgenCell {
+    catch(DbgenCell::NULL, || {
+        let batch = match batch.as_ref() {
+            Some(batch) if column < batch.columns_count => batch,
+            _ => return DbgenCell::NULL,
+        };
+        row.checked_mul(batch.columns_count)
+            .and_then(|start| start.checked_add(column))
+            .and_then(|index| batch.cells.get(index))
+            .copied()
+            .unwrap_or(DbgenCell::NULL)
+    })
+}
+
+#[test]
+fn test_c_api() {
+    use std::slice;
+
+    fn assert_thread_safe<T: Send + Sync>() {}
+    assert_thread_safe::<DbgenGenerator>();
+    fn assert_send<T: Send>() {}
+    assert_send::<DbgenRows>();
+
+    unsafe {
+        let mut error = ptr::null_mut();
+        let generator = dbgen_generator_new(
+            "CREATE TABLE t (a INT {{ rownum\0".as_ptr().cast(),
+            ptr::null(),
+            &mut error,
+        );
+        assert!(generator.is_null());
+        let message = CStr::from_ptr(error).to_str().unwrap().to_owned();
+        dbgen_string_free(error);
+        error = ptr::null_mut();
+        assert!(message.starts_with("Error: failed to parse template\n"), "{}", message);
+
+        let options = DbgenOptions {
+            seed: "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef\0"
+                .as_ptr()
+                .cast(),
+            rng: ptr::null(),
+            now: "2020-01-02 03:04:05\0".as_ptr().cast(),
+            time_zone: ptr::null(),
+            zoneinfo: ptr::null(),
+        };
+        let generator = dbgen_generator_new(
+            "CREATE TABLE t (
+                a INT {{ rownum }},
+                `B` TEXT {{ CASE WHEN rownum = 3 THEN debug.panic(rownum) ELSE 'b' || rownum END }},
+                c TIMESTAMP {{ current_timestamp }},
+                d DECIMAL {{ CAST(12.5 AS DECIMAL(10, 2)) }}
+            );\0"
+                .as_ptr()
+                .cast(),
+            &options,
+            &mut error,
+        );
+        assert!(error.is_null(), "{:?}", CStr::from_ptr(error));
+        assert!(!generator.is_null());
+
+        let rows = dbgen_rows_new(generator, "u\0".as_ptr().cast(), &mut error);
+        assert!(rows.is_null());
+        assert_eq!(CStr::from_ptr(error).to_str().unwrap(), "Error: unknown table u");
+        dbgen_string_free(error);
+        error = ptr::null_mut();
+
+        let rows = dbgen_rows_new(generator, "T\0".as_ptr().cast(), &mut error);
+        dbgen_generator_free(generator);
+        assert_eq!(dbgen_rows_columns_count(rows), 4);
+        assert_eq!(CStr::from_ptr(dbgen_rows_column_name(rows, 1)).to_str().unwrap(), "B");
+        assert!(dbgen_rows_column_name(rows, 4).is_null());
+
+        let mut output = DbgenBytes {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert!(dbgen_rows_next_formatted(
+            rows,
+            1,
+            DBGEN_FORMAT_SQL,
+            &mut output,
+            &mut error
+        ));
+        assert_eq!(
+            slice::from_raw_parts(output.data, output.len),
+            &b"INSERT INTO t VALUES\n(1, 'b1', '2020-01-02 03:04:05', 12.50);\n"[..]
+        );
+        dbgen_bytes_free(output);
+
+        let batch = dbgen_rows_next_batch(rows, 1, &mut error);
+        assert_eq!(dbgen_batch_rows_count(batch), 1);
+        assert_eq!(dbgen_batch_columns_count(batch), 4);
+        let cell = dbgen_batch_cell(batch, 0, 0);
+        assert_eq!((cell.kind, cell.integer), (DbgenCellKind::Int, 2));
+        let cell = dbgen_batch_cell(batch, 0, 1);
+        assert_eq!(cell.kind, DbgenCellKind::String);
+        assert_eq!(slice::from_raw_parts(cell.data, cell.len), b"b2");
+        let cell = dbgen_batch_cell(batch, 0, 2);
+        assert_eq!(
+            (cell.kind, cell.integer),
+            (DbgenCellKind::Timestamp, 1_577_934_245_000_000)
+        );
+        assert_eq!(slice::from_raw_parts(cell.data, cell.len), b"2020-01-02 03:04:05");
+        let cell = dbgen_batch_cell(batch, 0, 3);
+        assert_eq!((cell.kind, cell.real), (DbgenCellKind::Decimal, 12.5));
+        assert_eq!(slice::from_raw_parts(cell.data, cell.len), b"12.50");
+        assert_eq!(dbgen_batch_cell(batch, 1, 0).kind, DbgenCellKind::Null);
+        dbgen_batch_free(batch);
+
+        let batch = dbgen_rows_next_batch(rows, 1, &mut error);
+        assert!(batch.is_null());
+        let message = CStr::from_ptr(error).to_str().unwrap().to_owned();
+        dbgen_string_free(error);
+        assert!(message.starts_with("Error: runtime panic:"), "{}", message);
+        assert!(message.contains("debug.panic(rownum)"), "{}", message);
+
+        dbgen_rows_free(rows);
+    }
+}
+
+#[cfg(test)]
+unsafe fn take_error(error: &mut *mut c_char) -> String {
+    let message = CStr::from_ptr(*error).to_str().unwrap().to_owned();
+    dbgen_string_free(*error);
+    *error = ptr::null_mut();
+    message
+}
+
+/// CI runs this test with `--profile release-capi`, the profile the library is built with.
+#[test]
+fn test_panic_is_reported() {
+    unsafe {
+        let mut error = ptr::null_mut();
+        let result = report(&mut error, || -> Result<(), String> { panic!("boom {}", 1) });
+        assert!(result.is_none());
+        assert_eq!(take_error(&mut error), "Error: internal panic: boom 1");
+        assert_eq!(catch(7, || panic!("boom")), 7);
+    }
+}
+
+#[test]
+fn test_c_api_errors() {
+    unsafe {
+        let mut error = ptr::null_mut();
+        let rows = dbgen_rows_new(ptr::null(), "t\0".as_ptr().cast(), &mut error);
+        assert!(rows.is_null());
+        assert_eq!(take_error(&mut error), "Error: generator must not be NULL");
+
+        let mut output = DbgenBytes {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert!(!dbgen_rows_next_formatted(
+            ptr::null_mut(),
+            1,
+            DBGEN_FORMAT_SQL,
+            &mut output,
+            &mut error
+        ));
+        assert_eq!(take_error(&mut error), "Error: rows must not be NULL");
+        assert!(dbgen_rows_next_batch(ptr::null_mut(), 1, &mut error).is_null());
+        assert_eq!(take_error(&mut error), "Error: rows must not be NULL");
+        assert_eq!(dbgen_rows_columns_count(ptr::null()), 0);
+        assert!(dbgen_rows_column_name(ptr::null(), 0).is_null());
+        assert_eq!(dbgen_batch_rows_count(ptr::null()), 0);
+        assert_eq!(dbgen_batch_columns_count(ptr::null()), 0);
+        assert_eq!(dbgen_batch_cell(ptr::null(), 0, 0).kind, DbgenCellKind::Null);
+
+        let generator = dbgen_generator_new(
+            "CREATE TABLE t (a INT {{ rownum }});\0".as_ptr().cast(),
+            ptr::null(),
+            &mut error,
+        );
+        let rows = dbgen_rows_new(generator, "t\0".as_ptr().cast(), &mut error);
+        dbgen_generator_free(generator);
+        assert!(!dbgen_rows_next_formatted(
+            rows,
+            1,
+            DBGEN_FORMAT_SQL,
+            ptr::null_mut(),
+            &mut error
+        ));
+        assert_eq!(take_error(&mut error), "Error: output must not be NULL");
+        // the failed call does not consume any rows.
+        let batch = dbgen_rows_next_batch(rows, 1, &mut error);
+        assert_eq!(dbgen_batch_cell(batch, 0, 0).integer, 1);
+        assert_eq!(dbgen_batch_cell(batch, usize::MAX, 0).kind, DbgenCellKind::Null);
+        dbgen_batch_free(batch);
+        dbgen_rows_free(rows);
+    }
+}
+
+#[test]
+fn test_header_is_up_to_date() {
+    use std::{io::ErrorKind, process::Command};
+
+    let status = Command::new("cbindgen")
+        .args(&["--config", "cbindgen.toml", "--output", "include/dbgen.h", "--verify"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status();
+    match status {
+        // cbindgen is always installed on CI.
+        Err(e) if e.kind() == ErrorKind::NotFound && std::env::var_os("CI").is_none() => {
+            eprintln!("cbindgen is not installed, skipped checking include/dbgen.h");
+        }
+        status => assert!(
+            status.unwrap().success(),
+            "include/dbgen.h is outdated, regenerate it with `cbindgen --config cbindgen.toml --output include/dbgen.h`"
+        ),
+    }
+}
@@ -81,7 +81,7 @@ fn try_generate_rows(
         .map_err(|e| Error::InvalidArguments(format!("invalid seed: {}", e)))
         .no_span_err()?;
 
-    let template = Template::parse(template, &[], None, span_registry)?;
+    let template = Template::parse(template, None, &[], None, span_registry)?;
     let mut ctx = CompileContext::new(template.variables_count);
     ctx.current_timestamp = now;
     let tables = template
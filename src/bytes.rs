@@ -1,24 +1,65 @@
 //! Byte string.
 
 use crate::number::Number;
+use memchr::memmem;
 use rand_regex::{EncodedString, Encoding};
 use std::{
     cmp::Ordering,
     convert::TryFrom,
-    fmt, io,
-    ops::Range,
+    fmt, io, mem,
+    ops::{Deref, Range},
     str::{from_utf8, from_utf8_unchecked},
+    sync::Arc,
 };
 
 /// Describes how the input byte-string failed the UTF-8 encoding.
 #[derive(Debug)]
 pub struct TryIntoStringError(pub ByteString);
 
+/// The storage of the raw bytes of a [`ByteString`].
+#[derive(Clone, Debug)]
+enum Bytes {
+    /// Bytes owned by a single byte string. Cloning copies the bytes.
+    Owned(Vec<u8>),
+    /// Reference-counted bytes. Cloning only increases the reference count.
+    Shared(Arc<Vec<u8>>),
+}
+
+impl Bytes {
+    fn into_vec(self) -> Vec<u8> {
+        match self {
+            Self::Owned(bytes) => bytes,
+            Self::Shared(bytes) => Arc::try_unwrap(bytes).unwrap_or_else(|bytes| (*bytes).clone()),
+        }
+    }
+}
+
+impl Default for Bytes {
+    fn default() -> Self {
+        Self::Owned(Vec::new())
+    }
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Owned(bytes) => bytes,
+            Self::Shared(bytes) => bytes,
+        }
+    }
+}
+
 /// A string which potentially contains invalid UTF-8.
-#[derive(Clone, Eq, Debug)]
+///
+/// A byte string owns its bytes, until [`ByteString::share()`] is called to
+/// make cloning cheap. This is used for values which are cloned for every row,
+/// i.e. constants and variables. The bytes are only copied when a shared byte
+/// string is modified.
+#[derive(Clone, Debug)]
 pub struct ByteString {
     /// The raw bytes.
-    bytes: Vec<u8>,
+    bytes: Bytes,
     /// The bytes are valid ASCII until this position, which encountered the
     /// first non-ASCII bytes. This means `bytes[..ascii_len]` is entirely ASCII.
     /// Recording the ASCII length speeds up computations which uses character
@@ -31,7 +72,7 @@ pub struct ByteString {
 impl Default for ByteString {
     fn default() -> Self {
         Self {
-            bytes: Vec::new(),
+            bytes: Bytes::default(),
             ascii_len: 0,
             is_utf8: true,
         }
@@ -40,19 +81,21 @@ impl Default for ByteString {
 
 impl PartialEq for ByteString {
     fn eq(&self, other: &Self) -> bool {
-        self.bytes == other.bytes
+        *self.bytes == *other.bytes
     }
 }
 
+impl Eq for ByteString {}
+
 impl PartialOrd for ByteString {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.bytes.partial_cmp(&other.bytes)
+        (*self.bytes).partial_cmp(&*other.bytes)
     }
 }
 
 impl Ord for ByteString {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.bytes.cmp(&other.bytes)
+        (*self.bytes).cmp(&*other.bytes)
     }
 }
 
@@ -61,7 +104,7 @@ impl From<String> for ByteString {
         Self {
             ascii_len: compute_ascii_len(s.as_bytes()),
             is_utf8: true,
-            bytes: s.into_bytes(),
+            bytes: Bytes::Owned(s.into_bytes()),
         }
     }
 }
@@ -72,7 +115,7 @@ impl From<Vec<u8>> for ByteString {
         Self {
             ascii_len,
             is_utf8: from_utf8(&bytes[ascii_len..]).is_ok(),
-            bytes,
+            bytes: Bytes::Owned(bytes),
         }
     }
 }
@@ -88,14 +131,14 @@ impl From<EncodedString> for ByteString {
                 compute_ascii_len(&bytes)
             },
             is_utf8: encoding <= Encoding::Utf8,
-            bytes,
+            bytes: Bytes::Owned(bytes),
         }
     }
 }
 
 impl From<ByteString> for Vec<u8> {
     fn from(bytes: ByteString) -> Self {
-        bytes.bytes
+        bytes.into_bytes()
     }
 }
 
@@ -103,7 +146,7 @@ impl TryFrom<ByteString> for String {
     type Error = TryIntoStringError;
     fn try_from(bytes: ByteString) -> Result<Self, Self::Error> {
         if bytes.is_utf8 {
-            Ok(unsafe { Self::from_utf8_unchecked(bytes.bytes) })
+            Ok(unsafe { Self::from_utf8_unchecked(bytes.into_bytes()) })
         } else {
             Err(TryIntoStringError(bytes))
         }
@@ -174,7 +217,27 @@ impl ByteString {
 
     /// Extracts ownership of the vector of bytes from this byte string.
     pub fn into_bytes(self) -> Vec<u8> {
-        self.bytes
+        self.bytes.into_vec()
+    }
+
+    /// Makes the bytes reference-counted, so that cloning this byte string
+    /// no longer copies the bytes.
+    pub fn share(&mut self) {
+        if let Bytes::Owned(bytes) = &mut self.bytes {
+            self.bytes = Bytes::Shared(Arc::new(mem::take(bytes)));
+        }
+    }
+
+    /// Gets the bytes for modification, copying them first if they are shared
+    /// with other byte strings.
+    fn bytes_mut(&mut self) -> &mut Vec<u8> {
+        if let Bytes::Shared(_) = self.bytes {
+            self.bytes = Bytes::Owned(mem::take(&mut self.bytes).into_vec());
+        }
+        match &mut self.bytes {
+            Bytes::Owned(bytes) => bytes,
+            Bytes::Shared(_) => unreachable!(),
+        }
     }
 
     /// Extends a string to the end of this byte string.
@@ -182,7 +245,7 @@ impl ByteString {
         if self.ascii_len == self.len() {
             self.ascii_len += compute_ascii_len(s.as_bytes());
         }
-        self.bytes.extend_from_slice(s.as_bytes());
+        self.bytes_mut().extend_from_slice(s.as_bytes());
         self.debug_validate();
     }
 
@@ -192,7 +255,7 @@ impl ByteString {
         if self.ascii_len == self.len() {
             self.ascii_len += s.len();
         }
-        self.bytes.extend_from_slice(s.as_bytes());
+        self.bytes_mut().extend_from_slice(s.as_bytes());
         self.debug_validate();
     }
 
@@ -201,7 +264,7 @@ impl ByteString {
         if self.ascii_len == self.len() {
             self.ascii_len += compute_ascii_len(b);
         }
-        self.bytes.extend_from_slice(b);
+        self.bytes_mut().extend_from_slice(b);
         self.is_utf8 = from_utf8(&self.bytes[self.ascii_len..]).is_ok();
     }
 
@@ -210,7 +273,7 @@ impl ByteString {
         if self.ascii_len == self.len() {
             self.ascii_len += other.ascii_len;
         }
-        self.bytes.extend_from_slice(&other.bytes);
+        self.bytes_mut().extend_from_slice(&other.bytes);
         self.is_utf8 = match (self.is_utf8, other.is_utf8) {
             (true, true) => true,
             (true, false) | (false, true) => false,
@@ -232,7 +295,10 @@ impl ByteString {
     }
     /// Clears the entire content of the byte string.
     pub fn clear(&mut self) {
-        self.bytes.clear();
+        match &mut self.bytes {
+            Bytes::Owned(bytes) => bytes.clear(),
+            Bytes::Shared(_) => self.bytes = Bytes::default(),
+        }
         self.ascii_len = 0;
         self.is_utf8 = true;
         self.debug_validate();
@@ -258,7 +324,7 @@ impl ByteString {
                 from_utf8(&self.bytes[..len]).is_ok()
             };
         }
-        self.bytes.truncate(len);
+        self.bytes_mut().truncate(len);
         self.debug_validate();
     }
 
@@ -272,7 +338,7 @@ impl ByteString {
             return;
         }
 
-        self.bytes.drain(..len);
+        self.bytes_mut().drain(..len);
 
         if len < self.ascii_len {
             self.ascii_len -= len;
@@ -349,7 +415,7 @@ impl ByteString {
         }
         // if not splicing UTF-8 into UTF-8, just recompute validity afterwards.
 
-        self.bytes.splice(range, replacement.bytes);
+        self.bytes_mut().splice(range, replacement.into_bytes());
 
         if !is_splice_utf8_into_utf8 {
             self.is_utf8 = from_utf8(&self.bytes[self.ascii_len..]).is_ok();
@@ -357,6 +423,165 @@ impl ByteString {
 
         self.debug_validate();
     }
+
+    /// Gets the content as a string slice, if the byte string is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        if self.is_utf8 {
+            Some(unsafe { from_utf8_unchecked(&self.bytes) })
+        } else {
+            None
+        }
+    }
+
+    /// Iterates the characters of this byte string, each represented as a
+    /// slice of bytes.
+    ///
+    /// If the byte string is not valid UTF-8, every leading UTF-8 code unit
+    /// starts a new character, consistent with `char_len()`.
+    pub fn chars(&self) -> Chars<'_> {
+        Chars(&self.bytes)
+    }
+
+    /// Converts a byte index into the number of characters before it.
+    pub fn char_index(&self, byte_index: usize) -> usize {
+        if byte_index <= self.ascii_len {
+            return byte_index;
+        }
+        let end = byte_index.min(self.len());
+        self.ascii_len
+            + self.bytes[self.ascii_len..end]
+                .iter()
+                .filter(|b| is_utf8_leading_byte(**b))
+                .count()
+    }
+
+    /// Finds the byte index of the first occurrence of `needle`.
+    pub fn find(&self, needle: &[u8]) -> Option<usize> {
+        memmem::find(&self.bytes, needle)
+    }
+
+    /// Replaces all non-overlapping occurrences of `from` by `to`.
+    ///
+    /// If `from` is empty, the byte string is returned unchanged.
+    #[must_use]
+    pub fn replace(&self, from: &[u8], to: &[u8]) -> Self {
+        if from.is_empty() {
+            return self.clone();
+        }
+        let mut result = Vec::with_capacity(self.len());
+        let mut last = 0;
+        for pos in memmem::find_iter(&self.bytes, from) {
+            result.extend_from_slice(&self.bytes[last..pos]);
+            result.extend_from_slice(to);
+            last = pos + from.len();
+        }
+        result.extend_from_slice(&self.bytes[last..]);
+        result.into()
+    }
+
+    /// Repeats the byte string `n` times.
+    #[must_use]
+    pub fn repeat(&self, n: usize) -> Self {
+        let result = Self {
+            bytes: Bytes::Owned(self.bytes.repeat(n)),
+            ascii_len: if n == 0 {
+                0
+            } else if self.ascii_len == self.len() {
+                self.ascii_len * n
+            } else {
+                self.ascii_len
+            },
+            is_utf8: n == 0 || self.is_utf8,
+        };
+        result.debug_validate();
+        result
+    }
+
+    /// Converts all letters to upper case.
+    ///
+    /// If the byte string is not valid UTF-8, only ASCII letters are converted.
+    #[must_use]
+    pub fn to_uppercase(&self) -> Self {
+        match self.as_str() {
+            Some(s) => s.to_uppercase().into(),
+            None => self.bytes.to_ascii_uppercase().into(),
+        }
+    }
+
+    /// Converts all letters to lower case.
+    ///
+    /// If the byte string is not valid UTF-8, only ASCII letters are converted.
+    #[must_use]
+    pub fn to_lowercase(&self) -> Self {
+        match self.as_str() {
+            Some(s) => s.to_lowercase().into(),
+            None => self.bytes.to_ascii_lowercase().into(),
+        }
+    }
+
+    /// Converts the first letter of every word to upper case, and the rest to
+    /// lower case. Words are sequences of alphanumeric characters.
+    ///
+    /// If the byte string is not valid UTF-8, only ASCII letters are converted,
+    /// and all non-ASCII bytes are treated as part of a word.
+    #[must_use]
+    pub fn to_titlecase(&self) -> Self {
+        let mut is_in_word = false;
+        if let Some(s) = self.as_str() {
+            let mut result = String::with_capacity(s.len());
+            for c in s.chars() {
+                if is_in_word {
+                    result.extend(c.to_lowercase());
+                } else {
+                    result.extend(c.to_uppercase());
+                }
+                is_in_word = c.is_alphanumeric();
+            }
+            result.into()
+        } else {
+            let mut result = self.bytes.to_vec();
+            for b in &mut result {
+                if is_in_word {
+                    b.make_ascii_lowercase();
+                } else {
+                    b.make_ascii_uppercase();
+                }
+                is_in_word = !b.is_ascii() || b.is_ascii_alphanumeric();
+            }
+            result.into()
+        }
+    }
+}
+
+/// Iterator of characters in a byte string, created by [`ByteString::chars()`].
+#[derive(Clone, Debug)]
+pub struct Chars<'a>(&'a [u8]);
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.0.get(1..)?;
+        let len = rest
+            .iter()
+            .position(|b| is_utf8_leading_byte(*b))
+            .map_or(self.0.len(), |i| i + 1);
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+}
+
+impl DoubleEndedIterator for Chars<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let start = self.0.iter().rposition(|b| is_utf8_leading_byte(*b)).unwrap_or(0);
+        let (head, tail) = self.0.split_at(start);
+        self.0 = head;
+        Some(tail)
+    }
 }
 
 #[cfg(test)]
@@ -484,6 +709,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_modify_shared() {
+        let mut original = ByteString::from("abc".to_owned());
+        original.share();
+        assert_eq!(original.clone().as_bytes().as_ptr(), original.as_bytes().as_ptr());
+
+        let mut modified = original.clone();
+        modified.extend_str("def");
+        modified.splice(0..1, "x".to_owned().into());
+        assert_eq!(original.as_bytes(), b"abc");
+        assert_eq!(modified.as_bytes(), b"xbcdef");
+
+        let mut cleared = original.clone();
+        cleared.clear();
+        assert_eq!(original.as_bytes(), b"abc");
+        assert!(cleared.is_empty());
+    }
+
     #[test]
     fn test_truncate() {
         let test_cases: Vec<(ByteString, usize, Encoding, &[u8], Encoding, &[u8])> = vec![
@@ -634,4 +877,42 @@ mod tests {
             assert_eq!(target.as_bytes(), bytes);
         }
     }
+
+    #[test]
+    fn test_chars() {
+        let test_cases: Vec<(ByteString, &[&[u8]])> = vec![
+            ("abc".to_owned().into(), &[b"a", b"b", b"c"]),
+            (
+                "ß≠🥰".to_owned().into(),
+                &[b"\xc3\x9f", b"\xe2\x89\xa0", b"\xf0\x9f\xa5\xb0"],
+            ),
+            (b"a\xf0\x9f\xc2".to_vec().into(), &[b"a", b"\xf0\x9f", b"\xc2"]),
+            (b"\x80\x80a".to_vec().into(), &[b"\x80\x80", b"a"]),
+            (ByteString::default(), &[]),
+        ];
+
+        for (target, chars) in test_cases {
+            assert_eq!(target.chars().collect::<Vec<_>>(), chars);
+            let mut rev_chars = target.chars().rev().collect::<Vec<_>>();
+            rev_chars.reverse();
+            assert_eq!(rev_chars, chars);
+            assert_eq!(target.char_index(target.len()), target.char_len());
+        }
+    }
+
+    #[test]
+    fn test_repeat() {
+        let test_cases: Vec<(ByteString, usize, Encoding, &[u8])> = vec![
+            ("ab".to_owned().into(), 3, Encoding::Ascii, b"ababab"),
+            ("a\u{80}".to_owned().into(), 2, Encoding::Utf8, b"a\xc2\x80a\xc2\x80"),
+            (b"a\x80".to_vec().into(), 2, Encoding::Binary, b"a\x80a\x80"),
+            (b"a\x80".to_vec().into(), 0, Encoding::Ascii, b""),
+        ];
+
+        for (target, n, encoding, bytes) in test_cases {
+            let result = target.repeat(n);
+            assert_eq!(result.encoding(), encoding);
+            assert_eq!(result.as_bytes(), bytes);
+        }
+    }
 }
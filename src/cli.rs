@@ -1,9 +1,11 @@
 //! CLI driver of `dbgen`.
 
 use crate::{
+    compress::{Lz4Encoder, ParallelGzEncoder},
     error::Error,
     eval::{CompileContext, Schema, State, Table},
     format::{CsvFormat, Format, Options, SqlFormat, SqlInsertSetFormat},
+    generator::{compile_template, eval_global_row},
     lexctr::LexCtr,
     parser::{QName, Template},
     s3,
@@ -13,32 +15,27 @@ use crate::{
     writer::{self, Writer},
 };
 
+use bzip2::write::BzEncoder;
 use chrono::{NaiveDateTime, ParseResult, Utc};
-use data_encoding::{DecodeError, DecodeKind, HEXLOWER_PERMISSIVE};
-use dyn_clone::DynClone;
 use flate2::write::GzEncoder;
 use futures_lite::io::AsyncRead;
 use muldiv::MulDiv;
 use pbr::{MultiBar, Units};
-use rand::{
-    distributions::{Distribution, Standard},
-    rngs::{mock::StepRng, OsRng},
-    Rng, RngCore, SeedableRng,
-};
+use rand::{rngs::OsRng, Rng, SeedableRng};
 use rayon::{
     iter::{IntoParallelIterator, ParallelIterator},
     ThreadPoolBuilder,
 };
 use s3_server::{S3Service, SimpleAuth};
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     convert::TryInto,
-    fmt,
     fs::{create_dir_all, read_to_string, File},
     io::{self, sink, stdin, BufWriter, Read, Write},
     mem,
+    ops::{Range, RangeInclusive},
     path::{Path, PathBuf},
     pin::Pin,
     str::FromStr,
@@ -52,7 +49,12 @@ use structopt::{
     clap::AppSettings::{NextLineHelp, UnifiedHelpMessage},
     StructOpt,
 };
-use xz2::write::XzEncoder;
+use xz2::{
+    stream::{Check, MtStreamBuilder},
+    write::XzEncoder,
+};
+
+pub use crate::rng::{RngCoreClone, RngName, Seed};
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 struct RowArgs {
@@ -102,7 +104,7 @@ pub struct Args {
     pub schema_name: Option<String>,
 
     /// Output directory.
-    #[structopt(short, long, parse(from_os_str), conflicts_with("s3"), required_unless("s3"))]
+    #[structopt(short, long, parse(from_os_str), conflicts_with("s3"), required_unless_one(&["s3", "check", "explain", "preview"]))]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub out_dir: Option<PathBuf>,
 
@@ -223,14 +225,28 @@ pub struct Args {
     pub headers: bool,
 
     /// Compress data output.
-    #[structopt(short, long, possible_values(&["gzip", "gz", "xz", "zstd", "zst"]))]
+    #[structopt(
+        short,
+        long,
+        possible_values(&["gzip", "gz", "xz", "zstd", "zst", "lz4", "bzip2", "bz2", "snappy", "sz", "brotli", "br"])
+    )]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub compression: Option<CompressionName>,
 
-    /// Compression level (0-9 for gzip and xz, 1-21 for zstd).
-    #[structopt(long, default_value = "6")]
-    #[serde(skip_serializing_if = "is_six")]
-    pub compress_level: u8,
+    /// Generate each file in chunks of this number of INSERT statements in parallel.
+    #[structopt(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_size: Option<u32>,
+
+    /// Compression level. The valid range and default depend on the compression format.
+    #[structopt(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compress_level: Option<u8>,
+
+    /// Number of threads compressing each data file in parallel.
+    #[structopt(long, default_value = "1")]
+    #[serde(skip_serializing_if = "is_one")]
+    pub compress_threads: u32,
 
     /// Components to write.
     #[structopt(long, use_delimiter(true), possible_values(&["schema", "table", "data"]), default_value = "table,data", conflicts_with_all(&["no-schemas", "no-data"]))]
@@ -247,6 +263,21 @@ pub struct Args {
     #[serde(skip)]
     pub no_data: bool,
 
+    /// Only check the template for errors, without generating any files.
+    #[structopt(long)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub check: bool,
+
+    /// Print the compiled expressions of every table, without generating any files.
+    #[structopt(long)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub explain: bool,
+
+    /// Print this number of rows of every table as text, without generating any files.
+    #[structopt(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<u64>,
+
     /// Initializes the template with these global expressions.
     #[structopt(long, short = "D")]
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -258,7 +289,7 @@ pub struct Args {
     pub s3: bool,
 
     /// S3 bucket name.
-    #[structopt(long, requires("s3"), required_unless("out-dir"))]
+    #[structopt(long, requires("s3"), required_unless_one(&["out-dir", "check", "explain", "preview"]))]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub s3_bucket: Option<String>,
 
@@ -309,10 +340,15 @@ impl Default for Args {
             format_null: None,
             headers: false,
             compression: None,
-            compress_level: 6,
+            chunk_size: None,
+            compress_level: None,
+            compress_threads: 1,
             components: vec![ComponentName::Table, ComponentName::Data],
             no_schemas: false,
             no_data: false,
+            check: false,
+            explain: false,
+            preview: None,
             initialize: Vec::new(),
             s3: false,
             s3_bucket: None,
@@ -348,11 +384,6 @@ fn is_zero(u: &usize) -> bool {
     *u == 0
 }
 
-#[allow(clippy::trivially_copy_pass_by_ref)]
-fn is_six(u: &u8) -> bool {
-    *u == 6
-}
-
 fn is_utc(tz: &str) -> bool {
     tz == "UTC"
 }
@@ -482,9 +513,12 @@ fn read_template_file(path: &Path) -> Result<String, S<Error>> {
 /// Runs the CLI program.
 pub fn run(args: Args, span_registry: &mut Registry) -> Result<Option<S3Service>, S<Error>> {
     let row_args = args.row_args();
-    let input = match (args.template_string, &args.template) {
-        (Some(input), _) => input,
-        (None, Some(template)) => read_template_file(template)?,
+    let (input, path) = match (args.template_string, &args.template) {
+        (Some(input), _) => (input, None),
+        (None, Some(template)) => (
+            read_template_file(template)?,
+            Some(&**template).filter(|p| *p != Path::new("-")),
+        ),
         _ => {
             return Err(Error::UnsupportedCliParameter {
                 kind: "template",
@@ -493,7 +527,13 @@ pub fn run(args: Args, span_registry: &mut Registry) -> Result<Option<S3Service>
             .no_span());
         }
     };
-    let mut template = Template::parse(&input, &args.initialize, args.schema_name.as_deref(), span_registry)?;
+    let mut template = Template::parse(
+        &input,
+        path,
+        &args.initialize,
+        args.schema_name.as_deref(),
+        span_registry,
+    )?;
 
     let pool = ThreadPoolBuilder::new().num_threads(args.jobs).build().no_span_err()?;
 
@@ -508,17 +548,79 @@ pub fn run(args: Args, span_registry: &mut Registry) -> Result<Option<S3Service>
     ctx.zoneinfo = args.zoneinfo;
     ctx.time_zone = ctx.parse_time_zone(&args.time_zone).no_span_err()?;
     ctx.current_timestamp = args.now.unwrap_or_else(|| Utc::now().naive_utc());
-    let tables = template
-        .tables
-        .into_iter()
-        .map(|t| ctx.compile_table(t))
-        .collect::<Result<_, _>>()?;
+    let (tables, global_row) = compile_template(template, &ctx)?;
+
+    if args.check {
+        return Ok(None);
+    }
+
+    if args.explain {
+        let mut out = String::new();
+        if let Some(row_gen) = &global_row {
+            out.push_str("global:\n");
+            row_gen.explain(&mut out, 1, std::iter::empty());
+        }
+        for table in &tables {
+            table.explain(&mut out, &tables);
+        }
+        print!("{}", out);
+        return Ok(None);
+    }
+
+    let meta_seed = args.seed.unwrap_or_else(|| OsRng.gen());
+    let show_progress = !args.quiet;
+    if show_progress {
+        println!("Using seed: {}", meta_seed);
+    }
+    let mut seeding_rng = meta_seed.make_rng();
+
+    let rng_name = args.rng;
+
+    let ctx = eval_global_row(global_row, ctx, rng_name, &mut seeding_rng)?;
+
+    let format = args.format;
+    let format_options = Options {
+        escape_backslash: args.escape_backslash,
+        headers: args.headers,
+        true_string: args
+            .format_true
+            .map_or_else(|| format.default_true_string(), Cow::Owned),
+        false_string: args
+            .format_false
+            .map_or_else(|| format.default_false_string(), Cow::Owned),
+        null_string: args
+            .format_null
+            .map_or_else(|| format.default_null_string(), Cow::Owned),
+    };
+
+    if let Some(rows_count) = args.preview {
+        // use the same RNG as the first file, so the preview matches the start of the real data.
+        let state = State::new(1, rng_name.create(&mut seeding_rng), ctx);
+        let format = format.create(&format_options);
+        let mut env = writer::Env::new(tables, state, args.qualified, |_| Ok(PreviewWriter::default()))?;
+        for _ in 0..rows_count {
+            env.write_row(&*format)?;
+        }
+        for (table, w) in env.tables() {
+            let schema = table.schema(args.qualified);
+            println!("{}", schema.name);
+            print!("{}", w.to_aligned_table(&schema));
+        }
+        return Ok(None);
+    }
 
     if let Some(out_dir) = &args.out_dir {
         create_dir_all(out_dir).with_path("create output directory", out_dir)?;
     }
 
-    let compress_level = args.compress_level;
+    let (compress_level, compress_threads) = (args.compress_level, args.compress_threads);
+    let compression = args
+        .compression
+        .map(|c| {
+            c.check_options(compress_level, compress_threads)
+                .map(|level| (c, level))
+        })
+        .transpose()?;
     let mut components_mask = ComponentName::union_all(args.components);
     if args.no_data {
         ComponentName::Data.remove_from(&mut components_mask);
@@ -527,7 +629,6 @@ pub fn run(args: Args, span_registry: &mut Registry) -> Result<Option<S3Service>
         ComponentName::Schema.remove_from(&mut components_mask);
         ComponentName::Table.remove_from(&mut components_mask);
     }
-    let format = args.format;
     let env = Env {
         out_dir: args.out_dir,
         file_num_digits: args.files_count.to_string().len(),
@@ -535,22 +636,13 @@ pub fn run(args: Args, span_registry: &mut Registry) -> Result<Option<S3Service>
         qualified: args.qualified,
         rows_count: args.rows_count,
         format,
-        format_options: Options {
-            escape_backslash: args.escape_backslash,
-            headers: args.headers,
-            true_string: args
-                .format_true
-                .map_or_else(|| format.default_true_string(), Cow::Owned),
-            false_string: args
-                .format_false
-                .map_or_else(|| format.default_false_string(), Cow::Owned),
-            null_string: args
-                .format_null
-                .map_or_else(|| format.default_null_string(), Cow::Owned),
-        },
-        compression: args.compression.map(|c| (c, compress_level)),
+        format_options,
+        compression,
+        compress_threads: args.compress_threads,
         components_mask,
         file_size: args.size,
+        rng: rng_name,
+        chunk_size: args.chunk_size,
         bucket_name: args.s3_bucket,
     };
 
@@ -571,23 +663,6 @@ pub fn run(args: Args, span_registry: &mut Registry) -> Result<Option<S3Service>
         }
     }
 
-    let meta_seed = args.seed.unwrap_or_else(|| OsRng.gen());
-    let show_progress = !args.quiet;
-    if show_progress {
-        println!("Using seed: {}", meta_seed);
-    }
-    let mut seeding_rng = meta_seed.make_rng();
-
-    let rng_name = args.rng;
-
-    // Evaluate the global expressions if necessary.
-    if !template.global_exprs.is_empty() {
-        let row_gen = ctx.compile_row(template.global_exprs)?;
-        let mut state = State::new(0, rng_name.create(&mut seeding_rng), ctx);
-        row_gen.eval(&mut state)?;
-        ctx = state.into_compile_context();
-    }
-
     WRITE_FINISHED.store(false, Ordering::Relaxed);
     WRITE_PROGRESS.store(0, Ordering::Relaxed);
     WRITTEN_SIZE.store(0, Ordering::Relaxed);
@@ -658,147 +733,6 @@ pub fn run(args: Args, span_registry: &mut Registry) -> Result<Option<S3Service>
     Ok(Some(s3_service))
 }
 
-/// Random number generator (RNG) seed.
-///
-/// This is represented as a 64-digit hex string and is supposed to seed the
-/// HC-128 RNG only.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct Seed(<rand_hc::Hc128Rng as SeedableRng>::Seed);
-
-impl FromStr for Seed {
-    type Err = DecodeError;
-
-    /// Parses a 64-digit hex string into an RNG seed.
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut seed = Self::default();
-        if HEXLOWER_PERMISSIVE.decode_len(s.len())? != seed.0.len() {
-            return Err(DecodeError {
-                position: s.len(),
-                kind: DecodeKind::Length,
-            });
-        }
-        match HEXLOWER_PERMISSIVE.decode_mut(s.as_bytes(), &mut seed.0) {
-            Ok(_) => Ok(seed),
-            Err(e) => Err(e.error),
-        }
-    }
-}
-
-impl fmt::Display for Seed {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(&HEXLOWER_PERMISSIVE.encode(&self.0))
-    }
-}
-
-impl Distribution<Seed> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Seed {
-        Seed(self.sample(rng))
-    }
-}
-
-impl Serialize for Seed {
-    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        HEXLOWER_PERMISSIVE.encode(&self.0).serialize(serializer)
-    }
-}
-
-impl<'de> Deserialize<'de> for Seed {
-    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        // FIXME: support deserialize to both `&'de str` and `String`.
-        let s = String::deserialize(deserializer)?;
-        Self::from_str(&s).map_err(serde::de::Error::custom)
-    }
-}
-
-impl Seed {
-    /// Constructs a RNG from this seed.
-    pub fn make_rng(&self) -> rand_hc::Hc128Rng {
-        rand_hc::Hc128Rng::from_seed(self.0)
-    }
-}
-
-/// Names of random number generators supported by `dbgen`.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum RngName {
-    /// ChaCha12
-    ChaCha12,
-    /// ChaCha20
-    #[serde(alias = "chacha")]
-    ChaCha20,
-    /// HC-128
-    Hc128,
-    /// ISAAC
-    Isaac,
-    /// ISAAC-64
-    Isaac64,
-    /// Xorshift
-    XorShift,
-    /// PCG32
-    Pcg32,
-    /// Mock RNG which steps by a constant.
-    Step,
-}
-
-impl FromStr for RngName {
-    type Err = Error;
-    fn from_str(name: &str) -> Result<Self, Self::Err> {
-        Ok(match name {
-            "chacha12" => Self::ChaCha12,
-            "chacha" | "chacha20" => Self::ChaCha20,
-            "hc128" => Self::Hc128,
-            "isaac" => Self::Isaac,
-            "isaac64" => Self::Isaac64,
-            "xorshift" => Self::XorShift,
-            "pcg32" => Self::Pcg32,
-            "step" => Self::Step,
-            _ => {
-                return Err(Error::UnsupportedCliParameter {
-                    kind: "RNG",
-                    value: name.to_owned(),
-                });
-            }
-        })
-    }
-}
-
-impl RngName {
-    /// Creates an RNG engine given the name. The RNG engine instance will be seeded from `src`.
-    fn create(self, src: &mut rand_hc::Hc128Rng) -> Box<dyn RngCoreClone + Send> {
-        match self {
-            Self::ChaCha12 => Box::new(rand_chacha::ChaCha12Rng::from_seed(src.gen())),
-            Self::ChaCha20 => Box::new(rand_chacha::ChaCha20Rng::from_seed(src.gen())),
-            Self::Hc128 => Box::new(rand_hc::Hc128Rng::from_seed(src.gen())),
-            Self::Isaac => Box::new(rand_isaac::IsaacRng::from_seed(src.gen())),
-            Self::Isaac64 => Box::new(rand_isaac::Isaac64Rng::from_seed(src.gen())),
-            Self::XorShift => Box::new(rand_xorshift::XorShiftRng::from_seed(src.gen())),
-            Self::Pcg32 => Box::new(rand_pcg::Pcg32::from_seed(src.gen())),
-            Self::Step => Box::new(StepRng::new(src.next_u64(), src.next_u64() | 1)),
-        }
-    }
-}
-
-/// A cloneable `RngCore` trait object.
-pub trait RngCoreClone: RngCore + DynClone + Sync + Send {}
-
-dyn_clone::clone_trait_object!(RngCoreClone);
-
-impl RngCoreClone for rand_chacha::ChaCha12Rng {}
-
-impl RngCoreClone for rand_chacha::ChaCha20Rng {}
-
-impl RngCoreClone for rand_hc::Hc128Rng {}
-
-impl RngCoreClone for rand_isaac::IsaacRng {}
-
-impl RngCoreClone for rand_isaac::Isaac64Rng {}
-
-impl RngCoreClone for rand_xorshift::XorShiftRng {}
-
-impl RngCoreClone for rand_pcg::Pcg32 {}
-
-impl RngCoreClone for StepRng {}
-
 /// Names of output formats supported by `dbgen`.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
@@ -876,6 +810,17 @@ pub enum CompressionName {
     /// Compress as Zstandard format (`*.zst`).
     #[serde(alias = "zst")]
     Zstd,
+    /// Compress as LZ4 frame format (`*.lz4`).
+    Lz4,
+    /// Compress as bzip2 format (`*.bz2`).
+    #[serde(alias = "bz2")]
+    Bzip2,
+    /// Compress as Snappy framing format (`*.sz`).
+    #[serde(alias = "sz")]
+    Snappy,
+    /// Compress as Brotli format (`*.br`).
+    #[serde(alias = "br")]
+    Brotli,
 }
 
 impl FromStr for CompressionName {
@@ -885,6 +830,10 @@ impl FromStr for CompressionName {
             "gzip" | "gz" => Self::Gzip,
             "xz" => Self::Xz,
             "zstd" | "zst" => Self::Zstd,
+            "lz4" => Self::Lz4,
+            "bzip2" | "bz2" => Self::Bzip2,
+            "snappy" | "sz" => Self::Snappy,
+            "brotli" | "br" => Self::Brotli,
             _ => {
                 return Err(Error::UnsupportedCliParameter {
                     kind: "compression format",
@@ -896,26 +845,116 @@ impl FromStr for CompressionName {
 }
 
 impl CompressionName {
+    /// Obtains the name of this format.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Xz => "xz",
+            Self::Zstd => "zstd",
+            Self::Lz4 => "lz4",
+            Self::Bzip2 => "bzip2",
+            Self::Snappy => "snappy",
+            Self::Brotli => "brotli",
+        }
+    }
+
     /// Obtains the file extension when using this format.
     fn extension(self) -> &'static str {
         match self {
             Self::Gzip => "gz",
             Self::Xz => "xz",
             Self::Zstd => "zst",
+            Self::Lz4 => "lz4",
+            Self::Bzip2 => "bz2",
+            Self::Snappy => "sz",
+            Self::Brotli => "br",
         }
     }
 
-    /// Wraps a writer with a compression layer on top.
-    fn wrap<'a, W: Write + Send + 'a>(self, inner: W, level: u8) -> Box<dyn Write + Send + 'a> {
+    /// Obtains the range of compression levels and the default level of this
+    /// format, or `None` if the format has no compression levels.
+    fn levels(self) -> Option<(RangeInclusive<u8>, u8)> {
         match self {
+            Self::Gzip | Self::Xz => Some((0..=9, 6)),
+            // level 0 selects the default level of the zstd library (3).
+            Self::Zstd => Some((0..=21, 6)),
+            Self::Lz4 => Some((0..=12, 0)),
+            Self::Bzip2 => Some((1..=9, 9)),
+            Self::Snappy => None,
+            Self::Brotli => Some((0..=11, 6)),
+        }
+    }
+
+    /// Checks whether the compression level and number of threads are valid
+    /// for this format. Returns the level, or the default level if it is not
+    /// specified.
+    pub fn check_options(self, level: Option<u8>, threads: u32) -> Result<u8, S<Error>> {
+        if threads > 1 && !self.supports_threads() {
+            return Err(Error::UnsupportedCliParameter {
+                kind: "multi-threaded compression format",
+                value: self.name().to_owned(),
+            }
+            .no_span());
+        }
+        match (self.levels(), level) {
+            (Some((_, default)), None) => Ok(default),
+            (None, None) => Ok(0),
+            (Some((range, _)), Some(level)) if range.contains(&level) => Ok(level),
+            (levels, Some(level)) => Err(Error::InvalidCompressionLevel {
+                format: self.name(),
+                level,
+                expected: levels.map_or_else(
+                    || "no level".to_owned(),
+                    |(range, _)| format!("{} to {}", range.start(), range.end()),
+                ),
+            }
+            .no_span()),
+        }
+    }
+
+    /// Whether this format supports compressing with multiple threads.
+    fn supports_threads(self) -> bool {
+        matches!(self, Self::Gzip | Self::Xz | Self::Zstd)
+    }
+
+    /// Wraps a writer with a compression layer on top, using a level returned
+    /// by [`CompressionName::check_options()`]. If `threads` is more than 1,
+    /// the compression runs in that many background threads.
+    pub fn wrap<'a, W: Write + Send + 'a>(
+        self,
+        inner: W,
+        level: u8,
+        threads: u32,
+    ) -> io::Result<Box<dyn Write + Send + 'a>> {
+        Ok(match self {
+            Self::Gzip if threads > 1 => Box::new(ParallelGzEncoder::new(
+                inner,
+                flate2::Compression::new(level.into()),
+                threads as usize,
+            )?),
             Self::Gzip => Box::new(GzEncoder::new(inner, flate2::Compression::new(level.into()))),
+            Self::Xz if threads > 1 => {
+                // `XzEncoder::new` uses CRC64, while `MtStreamBuilder` defaults to no check at all.
+                let stream = MtStreamBuilder::new()
+                    .preset(level.into())
+                    .threads(threads)
+                    .check(Check::Crc64)
+                    .encoder()?;
+                Box::new(XzEncoder::new_stream(inner, stream))
+            }
             Self::Xz => Box::new(XzEncoder::new(inner, level.into())),
-            Self::Zstd => Box::new(
-                zstd::Encoder::new(inner, level.into())
-                    .expect("valid zstd encoder")
-                    .auto_finish(),
-            ),
-        }
+            Self::Zstd => {
+                let mut encoder = zstd::Encoder::new(inner, level.into())?;
+                if threads > 1 {
+                    encoder.multithread(threads)?;
+                }
+                Box::new(encoder.auto_finish())
+            }
+            Self::Lz4 => Box::new(Lz4Encoder::new(inner, level.into())?),
+            Self::Bzip2 => Box::new(BzEncoder::new(inner, bzip2::Compression::new(level.into()))),
+            Self::Snappy => Box::new(snap::write::FrameEncoder::new(inner)),
+            Self::Brotli => Box::new(brotli::CompressorWriter::new(inner, 4096, level.into(), 22)),
+        })
     }
 }
 
@@ -1083,6 +1122,79 @@ impl Writer for FormatWriter {
     }
 }
 
+/// A writer collecting the values as text, for `--preview`.
+#[derive(Debug, Default)]
+struct PreviewWriter {
+    rows: Vec<Vec<String>>,
+}
+
+impl PreviewWriter {
+    /// Formats the collected rows as an aligned table with the column names as header.
+    fn to_aligned_table(&self, schema: &Schema) -> String {
+        let header = schema.column_names().map(String::from).collect::<Vec<_>>();
+        let mut widths = Vec::new();
+        for row in Some(&header).into_iter().chain(&self.rows) {
+            if widths.len() < row.len() {
+                widths.resize(row.len(), 0);
+            }
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+
+        let format_line = |row: &[String]| {
+            let cells = widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    let value = row.get(i).map_or("", |s| &**s);
+                    format!("{}{:pad$}", value, "", pad = width - value.chars().count())
+                })
+                .collect::<Vec<_>>();
+            format!(" {}\n", cells.join(" | ").trim_end())
+        };
+        let mut out = format_line(&header);
+        let separator = widths.iter().map(|w| "-".repeat(w + 2)).collect::<Vec<_>>();
+        out.push_str(&separator.join("+"));
+        out.push('\n');
+        for row in &self.rows {
+            out.push_str(&format_line(row));
+        }
+        out
+    }
+}
+
+impl Writer for PreviewWriter {
+    fn write_value(&mut self, format: &dyn Format, value: &Value) -> Result<(), S<Error>> {
+        let mut buf = Vec::new();
+        format.write_value(&mut buf, value).unwrap(); // writing into a Vec never fails.
+        if let Some(row) = self.rows.last_mut() {
+            row.push(String::from_utf8_lossy(&buf).into_owned());
+        }
+        Ok(())
+    }
+    fn write_file_header(&mut self, _: &dyn Format, _: &Schema) -> Result<(), S<Error>> {
+        Ok(())
+    }
+    fn write_header(&mut self, _: &dyn Format, _: &Schema) -> Result<(), S<Error>> {
+        self.rows.push(Vec::new());
+        Ok(())
+    }
+    fn write_value_header(&mut self, _: &dyn Format, _: &str) -> Result<(), S<Error>> {
+        Ok(())
+    }
+    fn write_value_separator(&mut self, _: &dyn Format) -> Result<(), S<Error>> {
+        Ok(())
+    }
+    fn write_row_separator(&mut self, _: &dyn Format) -> Result<(), S<Error>> {
+        self.rows.push(Vec::new());
+        Ok(())
+    }
+    fn write_trailer(&mut self, _: &dyn Format) -> Result<(), S<Error>> {
+        Ok(())
+    }
+}
+
 /// The environmental data shared by all data writers.
 #[allow(clippy::struct_excessive_bools)] // the booleans aren't used as state-machines.
 #[derive(Debug, Clone)]
@@ -1095,8 +1207,14 @@ pub struct Env {
     format: FormatName,
     format_options: Options,
     compression: Option<(CompressionName, u8)>,
+    /// Number of threads compressing each data file.
+    compress_threads: u32,
     components_mask: u8,
     file_size: Option<u64>,
+    /// The random number generator of every chunk when `chunk_size` is set.
+    rng: RngName,
+    /// Number of INSERT statements of every chunk generated in parallel.
+    chunk_size: Option<u32>,
     /// The bucket name for generated files.
     pub bucket_name: Option<String>,
 }
@@ -1192,18 +1310,57 @@ impl Env {
             path.push(".");
             path.push(compression.extension());
             let path = PathBuf::from(path);
-            compression.wrap(File::create(&path).with_path("create data file", &path)?, level)
+            let file = File::create(&path).with_path("create data file", &path)?;
+            compression
+                .wrap(file, level, self.compress_threads)
+                .with_path("create data file", &path)?
         } else {
             Box::new(File::create(&path).with_path("create data file", &path)?)
         })
     }
 
+    /// Gets the number of rows of the INSERT statement at the given index of a file.
+    fn insert_rows_count(&self, info: &FileInfo, index: u32) -> u32 {
+        if index == info.inserts_count - 1 {
+            info.last_insert_rows_count
+        } else {
+            self.rows_count
+        }
+    }
+
+    /// Concludes an INSERT statement written to the data files, rotating the
+    /// files which exceeded the target size. Returns the paths and sizes of
+    /// the rotated files.
+    fn finish_insert(
+        &self,
+        fwe: &mut writer::Env<FormatWriter>,
+        format: &dyn Format,
+        rows_count: u32,
+    ) -> Result<Vec<(PathBuf, usize)>, S<Error>> {
+        let mut rotated = Vec::new();
+        let mut total_uncommitted_size = 0;
+        for (table, w) in fwe.tables() {
+            total_uncommitted_size += mem::take(&mut w.uncommitted_size);
+            if let Some(old_path_and_size) = w.try_rotate() {
+                rotated.push(old_path_and_size);
+
+                let new_path = w.path();
+                w.writer.flush().with_path("flush old file for rotation", &new_path)?;
+                w.writer = Box::new(BufWriter::new(self.open_data_file(new_path)?));
+                w.write_file_header(format, &table.schema(self.qualified))?;
+            }
+        }
+        WRITTEN_SIZE.fetch_add(total_uncommitted_size, Ordering::Relaxed);
+        WRITE_PROGRESS.fetch_add(rows_count.into(), Ordering::Relaxed);
+        Ok(rotated)
+    }
+
     /// Writes the data file.
     fn write_data_file(&self, info: &FileInfo, state: &mut State) -> Result<Vec<Object>, S<Error>> {
         let path_suffix = format!(".{0:01$}", info.file_index, self.file_num_digits);
         let format = self.format.create(&self.format_options);
 
-        let mut objects = Vec::new();
+        let mut rotated = Vec::new();
         let content = ObjectContent::Data((info.clone(), state.clone()));
 
         let mut fwe = writer::Env::new(self.tables.clone(), state.clone(), self.qualified, |table| {
@@ -1220,37 +1377,47 @@ impl Env {
             Ok(w)
         })?;
 
-        for i in 0..info.inserts_count {
-            let rows_count = if i == info.inserts_count - 1 {
-                info.last_insert_rows_count
-            } else {
-                self.rows_count
-            };
-            for _ in 0..rows_count {
-                fwe.write_row(&*format)?;
+        if let Some(mut chunks) = ChunkGenerator::new(self, state) {
+            // write out the current chunks while generating the next ones.
+            let mut inserts = chunks.next_window(self, info)?;
+            while !inserts.is_empty() {
+                let (written, next_inserts) = rayon::join(
+                    || {
+                        let format = self.format.create(&self.format_options);
+                        let mut rotated = Vec::new();
+                        for (rows_count, contents) in inserts {
+                            for ((_, w), content) in fwe.tables().zip(contents) {
+                                w.write_all(&content).with_path_fn("write data file", || w.path())?;
+                            }
+                            rotated.extend(self.finish_insert(&mut fwe, &*format, rows_count)?);
+                        }
+                        Ok::<_, S<Error>>(rotated)
+                    },
+                    || chunks.next_window(self, info),
+                );
+                rotated.extend(written?);
+                inserts = next_inserts?;
             }
-            fwe.write_trailer(&*format)?;
-
-            let mut total_uncommitted_size = 0;
-            for (table, w) in fwe.tables() {
-                total_uncommitted_size += mem::take(&mut w.uncommitted_size);
-                if let Some((old_path, old_size)) = w.try_rotate() {
-                    objects.push(Object {
-                        name: old_path.file_name().unwrap().to_string_lossy().to_string(),
-                        size: old_size,
-                        content: content.clone(),
-                    });
-
-                    let new_path = w.path();
-                    w.writer.flush().with_path("flush old file for rotation", &new_path)?;
-                    w.writer = Box::new(BufWriter::new(self.open_data_file(new_path)?));
-                    w.write_file_header(&*format, &table.schema(self.qualified))?;
+        } else {
+            for i in 0..info.inserts_count {
+                let rows_count = self.insert_rows_count(info, i);
+                for _ in 0..rows_count {
+                    fwe.write_row(&*format)?;
                 }
+                fwe.write_trailer(&*format)?;
+                rotated.extend(self.finish_insert(&mut fwe, &*format, rows_count)?);
             }
-            WRITTEN_SIZE.fetch_add(total_uncommitted_size, Ordering::Relaxed);
-            WRITE_PROGRESS.fetch_add(rows_count.into(), Ordering::Relaxed);
         }
 
+        let mut objects = rotated
+            .into_iter()
+            .map(|(old_path, old_size)| Object {
+                name: old_path.file_name().unwrap().to_string_lossy().to_string(),
+                size: old_size,
+                content: content.clone(),
+            })
+            .collect::<Vec<_>>();
+
         for (_, w) in fwe.tables() {
             let path = w.path();
             w.writer.flush().with_path("flush data file", &path)?;
@@ -1263,6 +1430,98 @@ impl Env {
 
         Ok(objects)
     }
+
+    /// Generates the INSERT statements in the given range of a data file into
+    /// memory.
+    fn generate_chunk(
+        &self,
+        info: &FileInfo,
+        inserts: Range<u32>,
+        state: State,
+    ) -> Result<Vec<GeneratedInsert>, S<Error>> {
+        let format = self.format.create(&self.format_options);
+        let mut fwe = writer::Env::new(self.tables.clone(), state, self.qualified, |table| {
+            let mut w = FormatWriter::new(table.name.unique_name().into(), self.format.extension(), None);
+            w.mem_writer = Some(MemWriter { buf: Vec::new() });
+            Ok(w)
+        })?;
+        inserts
+            .map(|i| {
+                let rows_count = self.insert_rows_count(info, i);
+                for _ in 0..rows_count {
+                    fwe.write_row(&*format)?;
+                }
+                fwe.write_trailer(&*format)?;
+                let contents = fwe
+                    .tables()
+                    .map(|(_, w)| w.mem_writer.as_mut().map_or_else(Vec::new, |m| mem::take(&mut m.buf)))
+                    .collect();
+                Ok((rows_count, contents))
+            })
+            .collect()
+    }
+}
+
+/// The content of an INSERT statement generated by [`ChunkGenerator`]: the
+/// number of rows, and the bytes to be written to the data file of every
+/// table.
+type GeneratedInsert = (u32, Vec<Vec<u8>>);
+
+/// Generates the INSERT statements of a data file in chunks on multiple
+/// threads, for `--chunk-size`.
+///
+/// Every chunk starts from a fresh state like a separate file, with its own
+/// random number generator seeded from that of the file. Therefore the
+/// content only depends on the seed and the chunk size, but not the number
+/// of threads.
+struct ChunkGenerator {
+    /// Number of INSERT statements of every chunk.
+    chunk_size: u32,
+    /// Index of the first INSERT statement of the next chunk.
+    next_insert: u32,
+    /// The `rownum` of the first row of the next chunk.
+    next_row_num: u64,
+    /// Generates the seeds of the random number generators of every chunk.
+    seeding_rng: rand_hc::Hc128Rng,
+    /// The compile context at the start of the file.
+    compile_context: CompileContext,
+}
+
+impl ChunkGenerator {
+    /// Creates a generator for the data file starting from `state`, if
+    /// `--chunk-size` is specified.
+    fn new(env: &Env, state: &mut State) -> Option<Self> {
+        let chunk_size = env.chunk_size.filter(|size| *size > 0)?;
+        Some(Self {
+            chunk_size,
+            next_insert: 0,
+            next_row_num: state.row_num(),
+            seeding_rng: rand_hc::Hc128Rng::from_seed(state.gen_seed()),
+            compile_context: state.compile_context().clone(),
+        })
+    }
+
+    /// Generates the next chunks in parallel, one for each thread, and returns
+    /// their INSERT statements in order. Returns an empty vector after all
+    /// INSERT statements of the file are generated.
+    fn next_window(&mut self, env: &Env, info: &FileInfo) -> Result<Vec<GeneratedInsert>, S<Error>> {
+        let mut chunks = Vec::new();
+        while chunks.len() < rayon::current_num_threads() && self.next_insert < info.inserts_count {
+            let end = info.inserts_count.min(self.next_insert.saturating_add(self.chunk_size));
+            let rng = env.rng.create(&mut self.seeding_rng);
+            let state = State::new(self.next_row_num, rng, self.compile_context.clone());
+            self.next_row_num += (self.next_insert..end)
+                .map(|i| u64::from(env.insert_rows_count(info, i)))
+                .sum::<u64>();
+            chunks.push((self.next_insert..end, state));
+            self.next_insert = end;
+        }
+        let chunks = chunks
+            .into_par_iter()
+            .map(|(inserts, state)| env.generate_chunk(info, inserts, state))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(chunks.into_iter().flatten().collect())
+    }
 }
 
 /// A data file reader.
@@ -1273,6 +1532,8 @@ pub struct DataFile {
     start: usize,
     end: usize,
     fwe: writer::Env<FormatWriter>,
+    chunks: Option<ChunkGenerator>,
+    pending_inserts: VecDeque<GeneratedInsert>,
     wrote_header: bool,
     inserts_count: u32,
     buf: Vec<u8>,
@@ -1283,6 +1544,7 @@ pub struct DataFile {
 impl DataFile {
     /// Creates a new DataFile.
     pub fn new(env: Env, info: FileInfo, path: String, state: State, start: usize, end: usize) -> Self {
+        let chunks = ChunkGenerator::new(&env, &mut state.clone());
         let path_suffix = format!(".{0:01$}", info.file_index, env.file_num_digits);
 
         let fwe = writer::Env::new(env.tables.clone(), state, env.qualified, |table| {
@@ -1294,7 +1556,7 @@ impl DataFile {
             let w = FormatWriter::new(path, env.format.extension(), env.file_size);
             Ok(w)
         })
-        .unwrap();
+        .expect("creating a format writer never fails");
         Self {
             env,
             info,
@@ -1302,6 +1564,8 @@ impl DataFile {
             start,
             end,
             fwe,
+            chunks,
+            pending_inserts: VecDeque::new(),
             wrote_header: false,
             inserts_count: 0,
             buf: Vec::with_capacity(4096),
@@ -1313,8 +1577,15 @@ impl DataFile {
 
 impl AsyncRead for DataFile {
     fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.read_into(buf).map_err(|e| io::Error::other(e.inner)))
+    }
+}
+
+impl DataFile {
+    /// Generates the content of this file into the buffer.
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<usize, S<Error>> {
         if self.read_pos >= self.end {
-            return Poll::Ready(Ok(0));
+            return Ok(0);
         }
         if self.buf_pos >= self.buf.len() {
             let path = self.path.clone();
@@ -1323,6 +1594,19 @@ impl AsyncRead for DataFile {
             let schema = self.env.tables[0].schema(self.env.qualified);
 
             while self.inserts_count < self.info.inserts_count {
+                if self.chunks.is_some() {
+                    self.read_next_chunked_insert()?;
+                    if self.read_pos + self.buf.len() <= self.start {
+                        self.read_pos += self.buf.len();
+                        continue;
+                    }
+                    if self.read_pos < self.start {
+                        self.buf_pos = self.start - self.read_pos;
+                        self.read_pos = self.start;
+                    }
+                    break;
+                }
+
                 self.buf.truncate(0);
                 let mem_writer = MemWriter {
                     buf: mem::take(&mut self.buf),
@@ -1332,10 +1616,10 @@ impl AsyncRead for DataFile {
                     .fwe
                     .tables()
                     .find(|(_, w)| w.path().to_string_lossy().to_string() == path)
-                    .unwrap();
+                    .expect("the file belongs to a table");
                 w.mem_writer = Some(mem_writer);
                 if !wrote_header {
-                    w.write_file_header(&*format, &schema).unwrap();
+                    w.write_file_header(&*format, &schema)?;
                     self.wrote_header = true;
                 }
 
@@ -1346,17 +1630,17 @@ impl AsyncRead for DataFile {
                     self.env.rows_count
                 };
                 for _ in 0..rows_count {
-                    self.fwe.write_row(&*format).unwrap();
+                    self.fwe.write_row(&*format)?;
                 }
-                self.fwe.write_trailer(&*format).unwrap();
+                self.fwe.write_trailer(&*format)?;
                 self.inserts_count += 1;
 
                 let (_, w) = self
                     .fwe
                     .tables()
                     .find(|(_, w)| w.path().to_string_lossy().to_string() == path)
-                    .unwrap();
-                let mem_writer = w.mem_writer.take().unwrap();
+                    .expect("the file belongs to a table");
+                let mem_writer = w.mem_writer.take().expect("memory writer was installed above");
                 self.buf = mem_writer.buf;
 
                 if self.read_pos + self.buf.len() <= self.start {
@@ -1381,7 +1665,37 @@ impl AsyncRead for DataFile {
         buf[..n].copy_from_slice(&self.buf[self.buf_pos..self.buf_pos + n]);
         self.buf_pos += n;
         self.read_pos += n;
-        return Poll::Ready(Ok(n));
+        Ok(n)
+    }
+
+    /// Reads the next INSERT statement of this file into the buffer, when
+    /// the file is generated in chunks.
+    fn read_next_chunked_insert(&mut self) -> Result<(), S<Error>> {
+        let path = &self.path;
+        let table_index = self
+            .fwe
+            .tables()
+            .position(|(_, w)| w.path().to_string_lossy() == *path)
+            .expect("the file belongs to a table");
+        self.buf.clear();
+        if !mem::replace(&mut self.wrote_header, true) {
+            let format = self.env.format.create(&self.env.format_options);
+            let schema = self.env.tables[table_index].schema(self.env.qualified);
+            format
+                .write_file_header(&mut self.buf, &schema)
+                .with_path("write file header", Path::new(path))?;
+        }
+        if self.pending_inserts.is_empty() {
+            let chunks = self.chunks.as_mut().expect("the file is generated in chunks");
+            self.pending_inserts = chunks.next_window(&self.env, &self.info)?.into();
+        }
+        let (_, contents) = self
+            .pending_inserts
+            .pop_front()
+            .expect("the file has more INSERT statements");
+        self.buf.extend_from_slice(&contents[table_index]);
+        self.inserts_count += 1;
+        Ok(())
     }
 }
 
@@ -1443,6 +1757,7 @@ fn run_progress_thread(total_rows: u64) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::mock::StepRng;
 
     #[test]
     fn test_row_args() {
@@ -1583,4 +1898,120 @@ mod tests {
             assert_eq!(args.row_args(), row_args);
         }
     }
+
+    #[test]
+    fn test_preview() {
+        let mut registry = Registry::default();
+        let template = Template::parse(
+            "create table t (id int {{ rownum * 5 }}, name text {{ 'name-' || rownum }});",
+            None,
+            &[],
+            None,
+            &mut registry,
+        )
+        .unwrap();
+        let ctx = CompileContext::new(template.variables_count);
+        let tables = vec![ctx.compile_table(template.tables.into_iter().next().unwrap()).unwrap()];
+        let state = State::new(1, Box::new(StepRng::new(0, 1)), ctx);
+        let format = FormatName::Csv.create(&Options::default());
+        let mut env = writer::Env::new(tables, state, false, |_| Ok(PreviewWriter::default())).unwrap();
+        for _ in 0..3 {
+            env.write_row(&*format).unwrap();
+        }
+        let (table, w) = env.tables().next().unwrap();
+        assert_eq!(
+            w.to_aligned_table(&table.schema(false)),
+            concat!(
+                " id | name\n",
+                "----+----------\n",
+                " 5  | \"name-1\"\n",
+                " 10 | \"name-2\"\n",
+                " 15 | \"name-3\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        use std::io::Read;
+
+        let input = (0..100_000)
+            .map(|i| format!("({}, 'row {}'),\n", i, i % 7))
+            .collect::<String>();
+        for &(name, threads) in &[
+            ("gzip", 1),
+            ("gzip", 3),
+            ("xz", 1),
+            ("xz", 3),
+            ("zstd", 1),
+            ("zstd", 3),
+            ("lz4", 1),
+            ("bzip2", 1),
+            ("snappy", 1),
+            ("brotli", 1),
+        ] {
+            let compression = CompressionName::from_str(name).unwrap();
+            let level = compression.check_options(None, threads).unwrap();
+            let mut compressed = Vec::new();
+            {
+                let mut w = compression.wrap(&mut compressed, level, threads).unwrap();
+                w.write_all(input.as_bytes()).unwrap();
+            }
+            let mut r: Box<dyn Read> = match compression {
+                CompressionName::Gzip => Box::new(flate2::read::MultiGzDecoder::new(&*compressed)),
+                CompressionName::Xz => Box::new(xz2::read::XzDecoder::new(&*compressed)),
+                CompressionName::Zstd => Box::new(zstd::Decoder::new(&*compressed).unwrap()),
+                CompressionName::Lz4 => Box::new(lz4::Decoder::new(&*compressed).unwrap()),
+                CompressionName::Bzip2 => Box::new(bzip2::read::BzDecoder::new(&*compressed)),
+                CompressionName::Snappy => Box::new(snap::read::FrameDecoder::new(&*compressed)),
+                CompressionName::Brotli => Box::new(brotli::Decompressor::new(&*compressed, 4096)),
+            };
+            let mut output = String::new();
+            r.read_to_string(&mut output).unwrap();
+            assert!(output == input, "{} with {} threads", name, threads);
+        }
+    }
+
+    #[test]
+    fn test_xz_integrity_check() {
+        let xz = CompressionName::Xz;
+        let check_ids = [1, 3].map(|threads| {
+            let mut compressed = Vec::new();
+            xz.wrap(&mut compressed, 6, threads)
+                .unwrap()
+                .write_all(b"data")
+                .unwrap();
+            // the stream header is 6 bytes of magic followed by the stream flags, whose second byte
+            // is the check ID.
+            assert_eq!(compressed[..7], *b"\xfd7zXZ\0\0", "{} threads", threads);
+            compressed[7]
+        });
+        assert_eq!(check_ids, [0x04; 2], "both paths should use CRC64");
+    }
+
+    #[test]
+    fn test_compression_options() {
+        let check = |name: &str, level: Option<u8>, threads: u32| {
+            CompressionName::from_str(name)
+                .unwrap()
+                .check_options(level, threads)
+                .map_err(|e| e.inner.to_string())
+        };
+        assert_eq!(check("gzip", None, 1), Ok(6));
+        assert_eq!(check("zstd", Some(21), 4), Ok(21));
+        assert_eq!(check("zstd", Some(0), 1), Ok(0));
+        assert_eq!(check("bz2", None, 1), Ok(9));
+        assert_eq!(
+            check("gzip", Some(10), 1),
+            Err("compression level 10 is not supported by gzip (expected 0 to 9)".to_owned())
+        );
+        assert_eq!(
+            check("snappy", Some(1), 1),
+            Err("compression level 1 is not supported by snappy (expected no level)".to_owned())
+        );
+        assert_eq!(
+            check("brotli", None, 2),
+            Err("unsupported multi-threaded compression format brotli".to_owned())
+        );
+    }
 }
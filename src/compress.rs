@@ -0,0 +1,167 @@
+//! Compression encoders not directly provided by the compression libraries.
+
+use flate2::{write::GzEncoder, Compression};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    mem,
+    sync::mpsc::{sync_channel, Receiver},
+};
+
+/// Size of the uncompressed input of every gzip member.
+const GZIP_BLOCK_SIZE: usize = 1 << 20;
+
+/// A gzip encoder which compresses blocks of the input in parallel, in the
+/// same way as `pigz`.
+///
+/// Every block is compressed into a separate gzip member. A sequence of
+/// members is still a single valid gzip file, which decompresses to the
+/// concatenation of all blocks.
+pub struct ParallelGzEncoder<W: Write> {
+    inner: W,
+    compression: Compression,
+    pool: ThreadPool,
+    /// The uncompressed input not yet submitted for compression.
+    block: Vec<u8>,
+    /// The members being compressed, in the order they should be written.
+    pending: VecDeque<Receiver<io::Result<Vec<u8>>>>,
+    /// Whether any member has been submitted.
+    started: bool,
+}
+
+impl<W: Write> ParallelGzEncoder<W> {
+    /// Creates a new encoder using the given number of compression threads.
+    pub fn new(inner: W, compression: Compression, threads: usize) -> io::Result<Self> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("gzip-{}", i))
+            .build()
+            .map_err(io::Error::other)?;
+        Ok(Self {
+            inner,
+            compression,
+            pool,
+            block: Vec::with_capacity(GZIP_BLOCK_SIZE),
+            pending: VecDeque::new(),
+            started: false,
+        })
+    }
+
+    /// Submits the current block for compression. If too many blocks are
+    /// already in flight, waits for the oldest one and writes it out first.
+    fn submit_block(&mut self) -> io::Result<()> {
+        if self.pending.len() >= 2 * self.pool.current_num_threads() {
+            self.write_oldest()?;
+        }
+        let block = mem::replace(&mut self.block, Vec::with_capacity(GZIP_BLOCK_SIZE));
+        let compression = self.compression;
+        let (tx, rx) = sync_channel(1);
+        self.pool.spawn(move || {
+            let mut encoder = GzEncoder::new(Vec::with_capacity(block.len() / 2), compression);
+            let result = encoder.write_all(&block).and_then(|()| encoder.finish());
+            let _ = tx.send(result);
+        });
+        self.pending.push_back(rx);
+        self.started = true;
+        Ok(())
+    }
+
+    /// Waits for the oldest pending member and writes it to the inner writer.
+    fn write_oldest(&mut self) -> io::Result<()> {
+        if let Some(rx) = self.pending.pop_front() {
+            let member = rx.recv().map_err(io::Error::other)??;
+            self.inner.write_all(&member)?;
+        }
+        Ok(())
+    }
+
+    /// Compresses all remaining input and writes out every member.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        // an empty input still needs one member to be a valid gzip file.
+        if !self.block.is_empty() || !self.started {
+            self.submit_block()?;
+        }
+        while !self.pending.is_empty() {
+            self.write_oldest()?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for ParallelGzEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(GZIP_BLOCK_SIZE - self.block.len());
+        self.block.extend_from_slice(&buf[..len]);
+        if self.block.len() >= GZIP_BLOCK_SIZE {
+            self.submit_block()?;
+        }
+        Ok(len)
+    }
+
+    /// Writes out the members already submitted. The partially filled block
+    /// is kept, to avoid producing many tiny members.
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            self.write_oldest()?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for ParallelGzEncoder<W> {
+    fn drop(&mut self) {
+        let _ = self.try_finish();
+    }
+}
+
+/// An LZ4 frame encoder which finishes the frame when dropped.
+pub struct Lz4Encoder<W: Write>(Option<lz4::Encoder<W>>);
+
+impl<W: Write> Lz4Encoder<W> {
+    /// Creates a new encoder with the given compression level.
+    pub fn new(inner: W, level: u32) -> io::Result<Self> {
+        let encoder = lz4::EncoderBuilder::new().level(level).build(inner)?;
+        Ok(Self(Some(encoder)))
+    }
+}
+
+impl<W: Write> Write for Lz4Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.as_mut().expect("encoder not finished").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.as_mut().expect("encoder not finished").flush()
+    }
+}
+
+impl<W: Write> Drop for Lz4Encoder<W> {
+    fn drop(&mut self) {
+        if let Some(encoder) = self.0.take() {
+            let _ = encoder.finish().1;
+        }
+    }
+}
+
+#[test]
+fn test_parallel_gzip() {
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+
+    let input = (0..3 * GZIP_BLOCK_SIZE + 12345)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<_>>();
+    for &len in &[0, 100, GZIP_BLOCK_SIZE, input.len()] {
+        let mut output = Vec::new();
+        {
+            let mut encoder = ParallelGzEncoder::new(&mut output, Compression::fast(), 3).unwrap();
+            for chunk in input[..len].chunks(7777) {
+                encoder.write_all(chunk).unwrap();
+            }
+        }
+        let mut decoded = Vec::new();
+        MultiGzDecoder::new(&*output).read_to_end(&mut decoded).unwrap();
+        assert!(decoded == input[..len], "length {}", len);
+    }
+}
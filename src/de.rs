@@ -0,0 +1,504 @@
+//! Deserializing generated rows into Rust types.
+
+use crate::{error::Error, json::Json, value::Value};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, SecondsFormat, TimeZone};
+use serde::{
+    de::{
+        value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer},
+        Deserialize, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+use std::{fmt, iter::Zip, slice::Iter};
+
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Deserialize(msg.to_string())
+    }
+}
+
+/// Deserializes a row into `T`.
+///
+/// Structs and maps are deserialized by column names, while tuples and sequences are deserialized
+/// by the column positions. The column names should be obtained from
+/// [`Schema::unescaped_column_names()`](crate::eval::Schema::unescaped_column_names).
+///
+/// The values are converted as follows:
+///
+/// * NULL becomes `None` or `()`.
+/// * Numbers become integers, floating point numbers or booleans. Decimals with a fractional part
+///   become strings holding their exact values, unless deserialized as floating point numbers.
+///   Any number can be deserialized as a boolean, where zero is `false`, or as a string.
+/// * Strings become `String` or `&str` if they are valid UTF-8. All strings can be deserialized
+///   as `serde_bytes::ByteBuf` or `Vec<u8>`. Strings can also be deserialized as unit enum
+///   variants.
+/// * Timestamps become RFC 3339 strings in the time zone of the generator, which can be
+///   deserialized as `chrono::DateTime<FixedOffset>` or `chrono::DateTime<Utc>`. Use
+///   [`naive_date_time`] to deserialize them as `chrono::NaiveDateTime`.
+/// * Intervals become integers in microseconds.
+/// * Arrays become sequences such as `Vec`.
+/// * JSON values are deserialized as is.
+pub fn from_row<'de, T: Deserialize<'de>>(column_names: &'de [String], values: &'de [Value]) -> Result<T, Error> {
+    T::deserialize(RowDeserializer { column_names, values })
+}
+
+/// Deserializes a timestamp as a `NaiveDateTime` in its local time, which is the same as the
+/// SQL output.
+///
+/// This function is intended to be used with `#[serde(deserialize_with = "...")]`.
+pub fn naive_date_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDateTime, D::Error> {
+    DateTime::<FixedOffset>::deserialize(deserializer).map(|dt| dt.naive_local())
+}
+
+/// Deserializer of a row.
+struct RowDeserializer<'de> {
+    column_names: &'de [String],
+    values: &'de [Value],
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(Columns {
+            iter: self.column_names.iter().zip(self.values),
+            column: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visit_seq(self.values.iter(), visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct enum identifier ignored_any
+    }
+}
+
+/// Map access of the columns of a row.
+struct Columns<'de> {
+    iter: Zip<Iter<'de, String>, Iter<'de, Value>>,
+    /// The column whose value is to be deserialized next.
+    column: Option<(&'de str, &'de Value)>,
+}
+
+impl<'de> MapAccess<'de> for Columns<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        if let Some((name, value)) = self.iter.next() {
+            self.column = Some((name, value));
+            seed.deserialize(BorrowedStrDeserializer::new(name)).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (name, value) = self.column.take().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(value).map_err(|e| match e {
+            Error::Deserialize(msg) => Error::Deserialize(format!("{} (column {})", msg, name)),
+            e => e,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Visits a sequence, and checks that the visitor has consumed every element.
+fn visit_seq<'de, I, V>(iter: I, visitor: V) -> Result<V::Value, Error>
+where
+    I: Iterator,
+    I::Item: IntoDeserializer<'de, Error>,
+    V: Visitor<'de>,
+{
+    let mut seq = SeqDeserializer::new(iter);
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+/// Formats a timestamp as RFC 3339 in its own time zone.
+fn format_timestamp<Tz: TimeZone>(timestamp: &NaiveDateTime, tz: &Tz) -> String {
+    let dt = tz.from_utc_datetime(timestamp);
+    dt.with_timezone(&dt.offset().fix())
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Number(n) => n.visit(visitor),
+            Value::Bytes(b) => match b.as_str() {
+                Some(s) => visitor.visit_borrowed_str(s),
+                None => visitor.visit_borrowed_bytes(b.as_bytes()),
+            },
+            Value::Timestamp(timestamp, tz) => visitor.visit_string(format_timestamp(timestamp, tz)),
+            Value::Interval(i) => visitor.visit_i64(*i),
+            Value::Array(array) => visit_seq(array.iter(), visitor),
+            Value::Json(json) => (**json).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Number(_) => visitor.visit_bool(self.is_sql_true()?),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Number(n) => visitor.visit_f64((*n).into()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Number(n) => visitor.visit_string(n.to_string()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Bytes(b) => visitor.visit_borrowed_bytes(b.as_bytes()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Bytes(b) => visit_seq(b.as_bytes().iter().copied(), visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            Value::Json(json) => (**json).deserialize_option(visitor),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Value::Bytes(b) => match b.as_str() {
+                Some(s) => visitor.visit_enum(s.into_deserializer()),
+                None => self.deserialize_any(visitor),
+            },
+            Value::Json(json) => (**json).deserialize_enum(name, variants, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char
+        unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Json {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for &'de Json {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Json::Null => visitor.visit_unit(),
+            Json::Bool(b) => visitor.visit_bool(*b),
+            Json::Number(n) => n.visit(visitor),
+            Json::String(s) => visitor.visit_borrowed_str(s),
+            Json::Array(array) => visit_seq(array.iter(), visitor),
+            Json::Object(object) => {
+                let mut map = MapDeserializer::new(object.iter().map(|(k, v)| (k.as_str(), v)));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Json::Number(n) => visitor.visit_f64((*n).into()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Json::Number(n) => visitor.visit_string(n.to_string()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Json::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Json::String(s) => visitor.visit_enum(s.as_str().into_deserializer()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char
+        bytes byte_buf seq unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[test]
+fn test_from_row() {
+    use crate::{generator::Generator, span::Registry};
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Record {
+        id: u8,
+        #[serde(rename = "Full Name")]
+        full_name: String,
+        active: bool,
+        color: Color,
+        #[serde(with = "serde_bytes_vec")]
+        data: Vec<u8>,
+        created: DateTime<Utc>,
+        #[serde(deserialize_with = "naive_date_time")]
+        created_local: NaiveDateTime,
+        duration: i64,
+        tags: Vec<Option<String>>,
+        extra: HashMap<String, f64>,
+        missing: Option<u32>,
+    }
+
+    mod serde_bytes_vec {
+        pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+            struct BytesVisitor;
+            impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+                type Value = Vec<u8>;
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("bytes")
+                }
+                fn visit_bytes<E>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+                    Ok(v.to_owned())
+                }
+            }
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+
+    let mut registry = Registry::default();
+    let generator = Generator::builder(
+        r#"CREATE TABLE t (
+            ID INT {{ rownum }},
+            "Full Name" TEXT {{ 'user ' || rownum }},
+            active INT {{ 2 - rownum }},
+            color TEXT {{ CASE rownum WHEN 1 THEN 'red' ELSE 'green' END }},
+            data BLOB {{ X'00FF' }},
+            created TIMESTAMP {{ TIMESTAMP '2020-01-02 03:04:05.5' }},
+            created_local TIMESTAMP {{ TIMESTAMP '2020-01-02 03:04:05' }},
+            duration INT {{ INTERVAL 2 SECOND }},
+            tags TEXT {{ ARRAY['a', NULL] }},
+            extra JSON {{ json_object('x', 1, 'y', 2.5) }},
+            missing INT {{ NULL }}
+        );"#,
+    )
+    .build(&mut registry)
+    .unwrap();
+
+    let mut records = generator.deserialize_rows::<Record>("t");
+    assert_eq!(
+        records.next().unwrap().unwrap(),
+        Record {
+            id: 1,
+            full_name: "user 1".to_owned(),
+            active: true,
+            color: Color::Red,
+            data: vec![0, 255],
+            created: Utc.ymd(2020, 1, 2).and_hms_milli(3, 4, 5, 500),
+            created_local: NaiveDateTime::parse_from_str("2020-01-02 03:04:05", "%Y-%m-%d %H:%M:%S").unwrap(),
+            duration: 2_000_000,
+            tags: vec![Some("a".to_owned()), None],
+            extra: vec![("x".to_owned(), 1.0), ("y".to_owned(), 2.5)].into_iter().collect(),
+            missing: None,
+        }
+    );
+    let record = records.next().unwrap().unwrap();
+    assert!(!record.active);
+    assert_eq!(record.color, Color::Green);
+
+    let row = generator.rows("t").next().unwrap().unwrap();
+    let column_names = vec!["id".to_owned(), "name".to_owned()];
+    let map = from_row::<HashMap<&str, &str>>(&column_names, &row[1..2]).unwrap();
+    assert_eq!(map, vec![("id", "user 1")].into_iter().collect());
+    let tuple = from_row::<(u64, &str)>(&column_names, &row[..2]).unwrap();
+    assert_eq!(tuple, (1, "user 1"));
+
+    let err = from_row::<(u64, u64)>(&column_names, &row[..2]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"cannot deserialize row: invalid type: string "user 1", expected u64"#
+    );
+    let err = from_row::<(u64,)>(&column_names, &row[..2]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot deserialize row: invalid length 2, expected 1 element in sequence"
+    );
+
+    #[derive(serde::Deserialize, Debug)]
+    struct WrongType {
+        #[allow(dead_code)]
+        color: u32,
+    }
+    let err = generator
+        .deserialize_rows::<WrongType>("t")
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(
+        err.inner.to_string(),
+        r#"cannot deserialize row: invalid type: string "red", expected u32 (column color)"#
+    );
+}
+
+#[test]
+fn test_from_row_numbers() {
+    use crate::number::Number;
+
+    let column_names = vec!["a".to_owned(), "b".to_owned(), "c".to_owned(), "d".to_owned()];
+    let row = [
+        Value::Number(Number::from_decimal(250, 2).unwrap()),
+        Value::Number(Number::from_decimal(-50, 1).unwrap()),
+        Value::from(7),
+        Value::Number(Number::from_decimal(12_345_678_901_234_567_890_123_456_789, 10).unwrap()),
+    ];
+
+    let strings = from_row::<(String, String, String, String)>(&column_names, &row).unwrap();
+    assert_eq!(
+        strings,
+        (
+            "2.50".to_owned(),
+            "-5.0".to_owned(),
+            "7".to_owned(),
+            "1234567890123456789.0123456789".to_owned()
+        )
+    );
+    let integers = from_row::<(i64, i64)>(&column_names, &row[1..3]).unwrap();
+    assert_eq!(integers, (-5, 7));
+    let floats = from_row::<(f64, f64, f32)>(&column_names, &row[..3]).unwrap();
+    assert_eq!(floats, (2.5, -5.0, 7.0));
+
+    let err = from_row::<(i64,)>(&column_names, &row[..1]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"cannot deserialize row: invalid type: string "2.50", expected i64"#
+    );
+    let err = from_row::<(i64,)>(&column_names, &row[3..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"cannot deserialize row: invalid type: string "1234567890123456789.0123456789", expected i64"#
+    );
+}
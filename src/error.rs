@@ -2,7 +2,7 @@
 
 #![allow(clippy::used_underscore_binding)]
 
-use crate::{parser::Rule, span::S};
+use crate::{parser::Rule, span::S, types::Types};
 use std::{convert::Infallible, fmt, path::PathBuf};
 use thiserror::Error as ThisError;
 
@@ -18,6 +18,41 @@ pub enum Error {
     #[error("unknown function")]
     UnknownFunction,
 
+    /// Unknown identifier, e.g. a bare name which is not a function parameter.
+    #[error("unknown identifier '{name}'")]
+    UnknownIdentifier {
+        /// The identifier.
+        name: String,
+    },
+
+    /// Unknown column referenced by `col()`.
+    #[error("unknown column '{name}'")]
+    UnknownColumn {
+        /// The column name.
+        name: String,
+    },
+
+    /// Column referenced by `col()` before it is evaluated.
+    #[error("column '{name}' is referenced before it is evaluated")]
+    ColumnNotYetEvaluated {
+        /// The column name.
+        name: String,
+    },
+
+    /// The row still fails the predicate after all attempts of `RETRY UNTIL`.
+    #[error("row still does not satisfy the RETRY UNTIL predicate after {attempts} attempts")]
+    RetryLimitExceeded {
+        /// The maximum number of attempts.
+        attempts: u64,
+    },
+
+    /// User-defined function having the same name as an existing function.
+    #[error("function {name}() is already defined")]
+    DuplicateFunction {
+        /// The function name.
+        name: String,
+    },
+
     /// Integer is too big.
     #[error("integer '{0}' is too big")]
     IntegerOverflow(
@@ -25,10 +60,51 @@ pub enum Error {
         String,
     ),
 
+    /// String produced by a function is longer than the maximum allowed.
+    #[error("string of {length} bytes is longer than the maximum of {max} bytes")]
+    StringTooLong {
+        /// The length of the string in bytes.
+        length: u128,
+        /// The maximum length.
+        max: usize,
+    },
+
+    /// Numerical computation whose result is undefined.
+    #[error("result of '{0}' is not a number")]
+    NotANumber(
+        /// The string representation of the expression that produced NaN.
+        String,
+    ),
+
+    /// Numerical computation whose result is infinite or too large to be represented.
+    #[error("result of '{0}' is out of range")]
+    NumberOutOfRange(
+        /// The string representation of the expression that produced the result.
+        String,
+    ),
+
     /// Not enough arguments provided to the SQL function.
     #[error("not enough arguments")]
     NotEnoughArguments,
 
+    /// Too many arguments provided to the SQL function.
+    #[error("expected at most {expected} arguments, found {found}")]
+    TooManyArguments {
+        /// The maximum number of arguments accepted.
+        expected: usize,
+        /// The actual number of arguments.
+        found: usize,
+    },
+
+    /// The type of an argument does not match the function signature.
+    #[error("expected {expected}, found {found}")]
+    TypeMismatch {
+        /// The types accepted by the function.
+        expected: Types,
+        /// The possible types of the argument.
+        found: Types,
+    },
+
     /// Invalid regex.
     #[error("invalid regex")]
     InvalidRegex(#[from] rand_regex::Error),
@@ -40,6 +116,10 @@ pub enum Error {
         char,
     ),
 
+    /// Invalid regex for pattern matching.
+    #[error("invalid regex pattern")]
+    InvalidRegexPattern(#[from] regex::Error),
+
     /// Hex/Base64 decode error.
     #[error("decode error")]
     DecodeError(#[from] data_encoding::DecodeError),
@@ -91,6 +171,42 @@ pub enum Error {
         source: std::io::Error,
     },
 
+    /// Failed to write the generated data into the output.
+    #[error("failed to write output")]
+    WriteOutput(
+        /// Source of error.
+        #[source]
+        std::io::Error,
+    ),
+
+    /// Failed to deserialize a generated row.
+    #[error("cannot deserialize row: {0}")]
+    Deserialize(String),
+
+    /// The table does not exist in the template.
+    #[error("unknown table {name}")]
+    UnknownTable {
+        /// The table name.
+        name: String,
+    },
+
+    /// No rows of the table are generated after many attempts, e.g. because
+    /// `WHERE` rejects every row.
+    #[error("no rows of table {name} are generated after {attempts} attempts")]
+    NoRowsGenerated {
+        /// The table name.
+        name: String,
+        /// The number of attempts.
+        attempts: u32,
+    },
+
+    /// A template includes itself, directly or indirectly.
+    #[error("template {path} includes itself")]
+    IncludeCycle {
+        /// Path of the template being included again.
+        path: PathBuf,
+    },
+
     /// Invalid time zone file.
     #[error("failed to parse time zone file ({time_zone})")]
     InvalidTimeZone {
@@ -118,6 +234,17 @@ pub enum Error {
         value: String,
     },
 
+    /// The compression level is not supported by the compression format.
+    #[error("compression level {level} is not supported by {format} (expected {expected})")]
+    InvalidCompressionLevel {
+        /// The compression format.
+        format: &'static str,
+        /// Level provided by user.
+        level: u8,
+        /// Description of the supported levels.
+        expected: String,
+    },
+
     /// Forced panic.
     #[error("runtime panic: {message}")]
     Panic {
@@ -1,18 +1,30 @@
 //! Evaluating compiled expressions into values.
 
 use crate::{
-    cli::RngCoreClone,
+    bytes::ByteString,
     error::Error,
     functions::{Arguments, Function},
-    parser::{Expr, QName},
+    number::Number,
+    parser::{unescape_ident, Expr, QName},
+    rng::RngCoreClone,
     span::{ResultExt, Span, SpanExt, S},
+    types::Types,
     value::Value,
 };
 use chrono::{NaiveDateTime, Utc};
 use rand::{distributions::Bernoulli, seq::SliceRandom, Rng};
 use rand_distr::{LogNormal, Uniform};
 use rand_regex::EncodedString;
-use std::{cmp::Ordering, fmt, fs, ops::Range, path::PathBuf, sync::Arc};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashSet, VecDeque},
+    convert::TryFrom,
+    fmt, fs, mem,
+    ops::Range,
+    path::PathBuf,
+    sync::Arc,
+};
 use tzfile::{ArcTz, Tz};
 use zipf::ZipfDistribution;
 
@@ -67,6 +79,12 @@ pub struct State {
     pub sub_row_num: u64,
     rng: Box<dyn RngCoreClone>,
     compile_context: CompileContext,
+    /// The values of the columns evaluated so far in the current row.
+    row_values: Vec<Value>,
+    /// The previous rows of the table being evaluated, the most recent first.
+    history: VecDeque<Vec<Value>>,
+    /// Functions recently compiled from non-constant arguments.
+    compile_cache: Box<CompileCache>,
 }
 
 impl fmt::Debug for State {
@@ -76,6 +94,9 @@ impl fmt::Debug for State {
             .field("sub_row_num", &self.sub_row_num)
             .field("rng", &())
             .field("variables", &self.compile_context.variables)
+            .field("row_values", &self.row_values)
+            .field("history", &self.history)
+            .field("compile_cache", &self.compile_cache)
             .finish()
     }
 }
@@ -94,18 +115,86 @@ impl State {
             sub_row_num: 1,
             rng,
             compile_context,
+            row_values: Vec::new(),
+            history: VecDeque::new(),
+            compile_cache: Box::default(),
         }
     }
 
+    /// Gets the compile context of the state.
+    pub fn compile_context(&self) -> &CompileContext {
+        &self.compile_context
+    }
+
     /// Extracts the compile context from the state.
     pub fn into_compile_context(self) -> CompileContext {
         self.compile_context
     }
 
+    /// Gets the current row number, i.e. the value of `rownum`.
+    pub fn row_num(&self) -> u64 {
+        self.row_num
+    }
+
+    /// Draws a seed from the random number generator of this state, e.g. for
+    /// seeding independent random number generators.
+    pub fn gen_seed(&mut self) -> [u8; 32] {
+        self.rng.gen()
+    }
+
     /// Increases the rownum by 1.
     pub fn increase_row_num(&mut self) {
         self.row_num += 1;
     }
+
+    /// Swaps the previous rows used by `lag()` with the given history.
+    ///
+    /// The history should be swapped in before evaluating a row of a table,
+    /// and swapped out afterwards.
+    pub fn swap_history(&mut self, history: &mut VecDeque<Vec<Value>>) {
+        mem::swap(&mut self.history, history);
+    }
+}
+
+/// The maximum number of entries in a [`CompileCache`].
+const COMPILE_CACHE_CAPACITY: usize = 16;
+
+/// A least-recently-used cache of functions compiled from non-constant
+/// arguments, so e.g. `rand.regex(@pattern)` does not need to rebuild the
+/// regex on every row.
+///
+/// Entries are keyed by the span of the function call and the argument values.
+/// Function calls which are compiled into a constant are not cached, since
+/// computing them again is usually as cheap as looking them up.
+#[derive(Clone, Debug, Default)]
+struct CompileCache {
+    /// The cached entries, the most recently used first.
+    entries: VecDeque<(Span, Box<[Value]>, Arc<Compiled>)>,
+    /// Spans of the function calls which have been compiled into a constant.
+    constant_calls: HashSet<Span>,
+}
+
+impl CompileCache {
+    /// Finds the compiled function from the cache, and marks it as the most
+    /// recently used.
+    fn get(&mut self, span: Span, args: &[S<Value>]) -> Option<Arc<Compiled>> {
+        let index = self.entries.iter().position(|(s, a, _)| {
+            *s == span && a.len() == args.len() && a.iter().zip(args).all(|(x, y)| x.is_identical(&y.inner))
+        })?;
+        let entry = self.entries.remove(index)?;
+        let compiled = entry.2.clone();
+        self.entries.push_front(entry);
+        Some(compiled)
+    }
+
+    /// Inserts a compiled function into the cache, evicting the least recently
+    /// used entry if the cache is full.
+    fn insert(&mut self, span: Span, args: Box<[Value]>, compiled: Arc<Compiled>) {
+        if self.entries.len() == COMPILE_CACHE_CAPACITY {
+            self.entries.pop_back();
+        }
+        self.entries.push_front((span, args, compiled));
+    }
 }
 
 /// A compiled table
@@ -121,6 +210,13 @@ pub struct Table {
     pub row: Row,
     /// Information of dervied tables (index, and number of rows to generate)
     pub derived: Vec<(usize, Compiled)>,
+    /// The number of previous rows needed by `lag()`.
+    pub lag_rows: usize,
+    /// The predicate of the `WHERE` or `RETRY UNTIL` directive.
+    pub filter: Option<Compiled>,
+    /// The maximum number of attempts of the `RETRY UNTIL` directive, or
+    /// `None` for the `WHERE` directive.
+    pub retry_limit: Option<u64>,
 }
 
 /// The schema information extracted from the compiled table.
@@ -139,6 +235,12 @@ impl Schema {
     pub fn column_names(&self) -> impl Iterator<Item = &str> + '_ {
         self.column_name_ranges.iter().map(move |r| &self.content[r.clone()])
     }
+
+    /// Returns an iterator of column names with the quotation marks removed. Unquoted names are
+    /// converted to lower case.
+    pub fn unescaped_column_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.column_names().map(unescape_ident)
+    }
 }
 
 impl Table {
@@ -165,6 +267,9 @@ impl CompileContext {
                 .into_iter()
                 .map(|(i, e)| self.compile(e).map(|c| (i, c)))
                 .collect::<Result<_, _>>()?,
+            lag_rows: table.lag_rows,
+            filter: table.filter.map(|e| self.compile(e)).transpose()?,
+            retry_limit: table.retry_limit,
         })
     }
 }
@@ -185,12 +290,202 @@ impl CompileContext {
 
 impl Row {
     /// Evaluates the row into a vector of values.
+    ///
+    /// The values evaluated so far are available to the later columns through
+    /// `col()`.
     pub fn eval(&self, state: &mut State) -> Result<Vec<Value>, S<Error>> {
-        let mut result = Vec::with_capacity(self.0.len());
-        for compiled in &self.0 {
-            result.push(compiled.eval(state)?);
+        let mut values = Vec::with_capacity(self.0.len());
+        self.eval_into(state, &mut values)?;
+        Ok(values)
+    }
+
+    /// Evaluates the row into `values`, replacing its previous content.
+    ///
+    /// Reusing the same vector for every row avoids allocating a new one each
+    /// time.
+    pub fn eval_into(&self, state: &mut State, values: &mut Vec<Value>) -> Result<(), S<Error>> {
+        self.eval_filtered(state, None, values).map(drop)
+    }
+
+    /// Evaluates the row into `values`, and then checks whether the predicate
+    /// is true given the values of the row.
+    fn eval_filtered(
+        &self,
+        state: &mut State,
+        predicate: Option<&Compiled>,
+        values: &mut Vec<Value>,
+    ) -> Result<bool, S<Error>> {
+        values.clear();
+        mem::swap(&mut state.row_values, values);
+        let result = (|| {
+            for compiled in &self.0 {
+                let value = compiled.eval(state)?;
+                state.row_values.push(value);
+            }
+            match predicate {
+                Some(p) => p.eval(state)?.is_sql_true().span_err(p.0.span),
+                None => Ok(true),
+            }
+        })();
+        mem::swap(&mut state.row_values, values);
+        result
+    }
+}
+
+impl Table {
+    /// Evaluates a row of this table into `values`, applying the `WHERE` or
+    /// `RETRY UNTIL` directive.
+    ///
+    /// Rejected attempts have no effects on the variables, although the
+    /// random number generator is still advanced. Returns `false` if the row
+    /// is dropped by `WHERE`.
+    pub fn eval_row(&self, state: &mut State, values: &mut Vec<Value>) -> Result<bool, S<Error>> {
+        let Some(filter) = &self.filter else {
+            self.row.eval_into(state, values)?;
+            return Ok(true);
+        };
+        for _ in 0..self.retry_limit.unwrap_or(1) {
+            let variables = state.compile_context.variables.clone();
+            if self.row.eval_filtered(state, Some(filter), values)? {
+                return Ok(true);
+            }
+            state.compile_context.variables = variables;
+        }
+        match self.retry_limit {
+            Some(attempts) => Err(Error::RetryLimitExceeded { attempts }.span(filter.0.span)),
+            None => Ok(false),
+        }
+    }
+}
+
+/// The values of a column evaluated by [`Row::eval_batch()`].
+#[derive(Clone, Debug)]
+pub enum Column {
+    /// The same value in every row.
+    Constant(Value),
+    /// Consecutive integers starting from the given number, e.g. `rownum`.
+    Sequence(u64),
+    /// Numbers.
+    Numbers(Vec<Number>),
+    /// Strings.
+    Bytes(Vec<ByteString>),
+    /// Values of other types, or of mixed types.
+    Values(Vec<Value>),
+}
+
+impl Default for Column {
+    fn default() -> Self {
+        Self::Values(Vec::new())
+    }
+}
+
+impl Column {
+    /// Gets the value of the row at the given index of the batch.
+    pub fn get(&self, index: usize) -> Value {
+        match self {
+            Self::Constant(v) => v.clone(),
+            Self::Sequence(start) => (start + index as u64).into(),
+            Self::Numbers(v) => Value::Number(v[index]),
+            Self::Bytes(v) => Value::Bytes(v[index].clone()),
+            Self::Values(v) => v[index].clone(),
+        }
+    }
+
+    /// Appends a value to the column.
+    ///
+    /// The type of an empty column is decided by the first value. The column
+    /// is converted into `Values` when a value of another type is appended.
+    fn push(&mut self, value: Value) {
+        match (&mut *self, value) {
+            (Self::Numbers(v), Value::Number(n)) => v.push(n),
+            (Self::Bytes(v), Value::Bytes(b)) => v.push(b),
+            (Self::Values(v), Value::Number(n)) if v.is_empty() => *self = Self::Numbers(vec![n]),
+            (Self::Values(v), Value::Bytes(b)) if v.is_empty() => *self = Self::Bytes(vec![b]),
+            (Self::Values(v), value) => v.push(value),
+            (Self::Numbers(v), value) => {
+                let mut values = v.drain(..).map(Value::Number).collect::<Vec<_>>();
+                values.push(value);
+                *self = Self::Values(values);
+            }
+            (Self::Bytes(v), value) => {
+                let mut values = v.drain(..).map(Value::Bytes).collect::<Vec<_>>();
+                values.push(value);
+                *self = Self::Values(values);
+            }
+            (Self::Constant(_) | Self::Sequence(_), _) => unreachable!("cannot push into a vectorized column"),
         }
-        Ok(result)
+    }
+}
+
+/// Rows evaluated by [`Row::eval_batch()`], stored column by column.
+#[derive(Clone, Debug)]
+pub struct Batch {
+    /// The number of rows.
+    pub len: usize,
+    /// The values of every column.
+    pub columns: Vec<Column>,
+}
+
+impl Batch {
+    /// Gets the values of the row at the given index.
+    pub fn row(&self, index: usize) -> Vec<Value> {
+        self.columns.iter().map(|c| c.get(index)).collect()
+    }
+}
+
+impl Row {
+    /// Evaluates `n` consecutive rows starting from the current `rownum`, and
+    /// stores the values column by column. The `rownum` is advanced by `n`
+    /// afterwards.
+    ///
+    /// Constants, `rownum` and `subrownum` are stored without repeating the
+    /// values. If the only other column is a random distribution, it is
+    /// sampled in a tight loop. Otherwise the rows are evaluated one by one,
+    /// since the columns may depend on each other, and sampling several
+    /// distributions column by column would change the random number
+    /// stream. Either way the result is the same as calling [`Row::eval()`]
+    /// `n` times.
+    pub fn eval_batch(&self, state: &mut State, n: usize) -> Result<Batch, S<Error>> {
+        let mut columns = self.0.iter().map(|c| c.vectorize(state)).collect::<Vec<_>>();
+        let mut remaining = self.0.iter().zip(&columns).filter(|(_, col)| col.is_none());
+        let is_columnar = match (remaining.next(), remaining.next()) {
+            (None, _) => true,
+            (Some((compiled, _)), None) => compiled.is_distribution(),
+            _ => false,
+        };
+
+        if is_columnar {
+            for (compiled, column) in self.0.iter().zip(&mut columns) {
+                if column.is_none() {
+                    let mut samples = Column::default();
+                    for _ in 0..n {
+                        samples.push(compiled.eval(state)?);
+                    }
+                    *column = Some(samples);
+                }
+            }
+            state.row_num += n as u64;
+        } else {
+            let mut evaluated = columns.iter().map(|_| Column::default()).collect::<Vec<_>>();
+            let mut values = Vec::with_capacity(self.0.len());
+            for _ in 0..n {
+                self.eval_into(state, &mut values)?;
+                state.increase_row_num();
+                for ((column, vectorized), value) in evaluated.iter_mut().zip(&columns).zip(values.drain(..)) {
+                    if vectorized.is_none() {
+                        column.push(value);
+                    }
+                }
+            }
+            for (column, evaluated) in columns.iter_mut().zip(evaluated) {
+                column.get_or_insert(evaluated);
+            }
+        }
+
+        Ok(Batch {
+            len: n,
+            columns: columns.into_iter().map(Option::unwrap_or_default).collect(),
+        })
     }
 }
 
@@ -207,11 +502,24 @@ pub enum C {
     RawFunction {
         /// The function.
         function: &'static dyn Function,
+        /// The function name or operator as written in the template.
+        name: Cow<'static, str>,
         /// Function arguments.
         args: Box<[Compiled]>,
     },
     /// Obtains a local variable.
     GetVariable(usize),
+    /// Obtains the value of an earlier column in the current row.
+    GetColumn(usize),
+    /// Obtains the value of a column in a previous row.
+    Lag {
+        /// The column index.
+        column: usize,
+        /// The number of rows before the current row (at least 1).
+        offset: usize,
+        /// The value when there are not enough previous rows.
+        default: Box<Compiled>,
+    },
     /// Assigns a value to a local variable.
     SetVariable(usize, Box<Compiled>),
     /// The `CASE … WHEN` expression.
@@ -223,6 +531,17 @@ pub enum C {
         /// The result when all conditions failed.
         otherwise: Box<Compiled>,
     },
+    /// The lazy `AND`/`OR` expression.
+    Logic {
+        /// The identity value. True means `AND` and false means `OR`.
+        identity: bool,
+        /// The operands, evaluated until one of them is not the identity.
+        args: Box<[Compiled]>,
+    },
+    /// The lazy `coalesce` expression.
+    Coalesce(Box<[Compiled]>),
+    /// The lazy `nullif` expression.
+    NullIf(Box<Compiled>, Box<Compiled>),
 
     /// Regex-based random string.
     RandRegex(rand_regex::Regex),
@@ -232,6 +551,8 @@ pub enum C {
     RandUniformI64(Uniform<i64>),
     /// Uniform distribution for `f64`.
     RandUniformF64(Uniform<f64>),
+    /// Uniform distribution for exact decimals, as the mantissa and scale.
+    RandDecimal(Uniform<i128>, u8),
     /// Zipfian distribution.
     RandZipf(ZipfDistribution),
     /// Log-normal distribution.
@@ -250,6 +571,47 @@ pub enum C {
     RandUuid,
 }
 
+/// Strategy for evaluating functions whose arguments are not always needed.
+#[derive(Copy, Clone, Debug)]
+pub enum Lazy {
+    /// `AND` (identity is true) or `OR` (identity is false).
+    Logic(bool),
+    /// `coalesce(v1, v2, ...)`.
+    Coalesce,
+    /// `nullif(v1, v2)`.
+    NullIf,
+    /// `if(cond, then, else)`.
+    If,
+}
+
+impl Lazy {
+    /// Compiles the non-constant arguments into a lazily evaluated expression.
+    fn compile(self, span: Span, args: Vec<Compiled>) -> Result<C, S<Error>> {
+        Ok(match self {
+            Self::Logic(identity) => C::Logic {
+                identity,
+                args: args.into_boxed_slice(),
+            },
+            Self::Coalesce => C::Coalesce(args.into_boxed_slice()),
+            Self::NullIf | Self::If => {
+                let mut it = args.into_iter();
+                let mut next = || it.next().ok_or_else(|| Error::NotEnoughArguments.span(span));
+                let first = next()?;
+                let second = next()?;
+                if let Self::If = self {
+                    C::CaseValueWhen {
+                        value: None,
+                        conditions: vec![(first, second)].into_boxed_slice(),
+                        otherwise: Box::new(next()?),
+                    }
+                } else {
+                    C::NullIf(Box::new(first), Box::new(second))
+                }
+            }
+        })
+    }
+}
+
 impl C {
     fn span(self, span: Span) -> Compiled {
         Compiled(S { span, inner: self })
@@ -263,18 +625,30 @@ pub struct Compiled(pub(crate) S<C>);
 impl CompileContext {
     /// Compiles an expression.
     pub fn compile(&self, expr: S<Expr>) -> Result<Compiled, S<Error>> {
-        Ok(match expr.inner {
+        let mut compiled = match expr.inner {
             Expr::RowNum => C::RowNum,
             Expr::SubRowNum => C::SubRowNum,
             Expr::CurrentTimestamp => C::Constant(Value::Timestamp(self.current_timestamp, self.time_zone.clone())),
             Expr::Value(v) => C::Constant(v),
             Expr::GetVariable(index) => C::GetVariable(index),
+            Expr::GetColumn(index) => C::GetColumn(index),
+            Expr::Lag {
+                column,
+                offset,
+                default,
+            } => C::Lag {
+                column,
+                offset,
+                default: Box::new(self.compile(*default)?),
+            },
             Expr::SetVariable(index, e) => C::SetVariable(index, Box::new(self.compile(*e)?)),
-            Expr::Function { function, args } => {
+            Expr::Function { function, name, args } => {
                 let args = args
                     .into_iter()
                     .map(|e| self.compile(e))
                     .collect::<Result<Vec<_>, _>>()?;
+                let arg_types = args.iter().map(|c| c.types().span(c.0.span)).collect::<Vec<_>>();
+                function.signature().check(expr.span, &arg_types)?;
                 if args.iter().all(Compiled::is_constant) {
                     let args = args
                         .into_iter()
@@ -284,9 +658,12 @@ impl CompileContext {
                         })
                         .collect();
                     function.compile(self, expr.span, args)?
+                } else if let Some(lazy) = function.lazy() {
+                    lazy.compile(expr.span, args)?
                 } else {
                     C::RawFunction {
                         function,
+                        name,
                         args: args.into_boxed_slice(),
                     }
                 }
@@ -302,6 +679,18 @@ impl CompileContext {
                     .map(|(p, r)| Ok((self.compile(p)?, self.compile(r)?)))
                     .collect::<Result<Vec<_>, _>>()?
                     .into_boxed_slice();
+                if value.is_none() {
+                    for (p, _) in &*conditions {
+                        let found = p.types();
+                        if !found.intersects(Types::NULLABLE_BOOL) {
+                            return Err(Error::TypeMismatch {
+                                expected: Types::NULLABLE_BOOL,
+                                found,
+                            }
+                            .span(p.0.span));
+                        }
+                    }
+                }
                 let otherwise = Box::new(if let Some(o) = otherwise {
                     self.compile(*o)?
                 } else {
@@ -313,37 +702,294 @@ impl CompileContext {
                     otherwise,
                 }
             }
+        };
+        // constants are cloned for every row.
+        if let C::Constant(value) = &mut compiled {
+            value.share();
+        }
+        Ok(compiled.span(expr.span))
+    }
+}
+
+/// Writes a line of the `--explain` output.
+fn explain_line(out: &mut String, depth: usize, label: impl fmt::Display) {
+    use std::fmt::Write;
+    writeln!(out, "{:indent$}{}", "", label, indent = depth * 2).unwrap();
+}
+
+impl Compiled {
+    /// Gets the column of a batch if this expression has the same or
+    /// consecutive values for every row, without evaluating it.
+    fn vectorize(&self, state: &State) -> Option<Column> {
+        match &self.0.inner {
+            C::Constant(v) => Some(Column::Constant(v.clone())),
+            C::RowNum => Some(Column::Sequence(state.row_num)),
+            C::SubRowNum => Some(Column::Constant(state.sub_row_num.into())),
+            _ => None,
+        }
+    }
+
+    /// Checks whether this expression is a random distribution, which only
+    /// depends on the random number generator.
+    fn is_distribution(&self) -> bool {
+        matches!(
+            self.0.inner,
+            C::RandRegex(_)
+                | C::RandUniformU64(_)
+                | C::RandUniformI64(_)
+                | C::RandUniformF64(_)
+                | C::RandDecimal(..)
+                | C::RandZipf(_)
+                | C::RandLogNormal(_)
+                | C::RandBool(_)
+                | C::RandFiniteF32(_)
+                | C::RandFiniteF64(_)
+                | C::RandU31Timestamp(_)
+                | C::RandShuffle(_)
+                | C::RandUuid
+        )
+    }
+
+    /// Describes the expression tree for `--explain`, one node per line.
+    ///
+    /// Constants are already folded during compilation. Functions are
+    /// evaluated again for every row, while distributions are compiled once
+    /// and only sampled for every row.
+    pub fn explain(&self, out: &mut String, depth: usize) {
+        let (label, children): (_, Vec<&Self>) = match &self.0.inner {
+            C::RowNum => ("rownum".to_owned(), vec![]),
+            C::SubRowNum => ("subrownum".to_owned(), vec![]),
+            C::Constant(v) => (format!("constant {}", v), vec![]),
+            C::RawFunction { name, args, .. } => (format!("function {}", name), args.iter().collect()),
+            C::GetVariable(index) => (format!("variable #{}", index), vec![]),
+            C::GetColumn(index) => (format!("column #{}", index + 1), vec![]),
+            C::Lag {
+                column,
+                offset,
+                default,
+            } => (
+                format!("lag column #{} by {} rows, or else", column + 1, offset),
+                vec![&**default],
+            ),
+            C::SetVariable(index, value) => (format!("set variable #{}", index), vec![&**value]),
+            C::CaseValueWhen {
+                value,
+                conditions,
+                otherwise,
+            } => {
+                explain_line(out, depth, "case");
+                if let Some(value) = value {
+                    value.explain(out, depth + 1);
+                }
+                for (condition, result) in &**conditions {
+                    explain_line(out, depth + 1, "when");
+                    condition.explain(out, depth + 2);
+                    explain_line(out, depth + 1, "then");
+                    result.explain(out, depth + 2);
+                }
+                explain_line(out, depth + 1, "else");
+                otherwise.explain(out, depth + 2);
+                return;
+            }
+            C::Logic { identity, args } => (
+                (if *identity { "lazy and" } else { "lazy or" }).to_owned(),
+                args.iter().collect(),
+            ),
+            C::Coalesce(args) => ("lazy coalesce".to_owned(), args.iter().collect()),
+            C::NullIf(value, other) => ("lazy nullif".to_owned(), vec![&**value, &**other]),
+            C::RandRegex(_) => ("distribution rand.regex".to_owned(), vec![]),
+            C::RandUniformU64(_) | C::RandUniformI64(_) => ("distribution rand.range".to_owned(), vec![]),
+            C::RandUniformF64(_) => ("distribution rand.uniform".to_owned(), vec![]),
+            C::RandDecimal(..) => ("distribution rand.decimal".to_owned(), vec![]),
+            C::RandZipf(_) => ("distribution rand.zipf".to_owned(), vec![]),
+            C::RandLogNormal(_) => ("distribution rand.log_normal".to_owned(), vec![]),
+            C::RandBool(_) => ("distribution rand.bool".to_owned(), vec![]),
+            C::RandFiniteF32(_) => ("distribution rand.finite_f32".to_owned(), vec![]),
+            C::RandFiniteF64(_) => ("distribution rand.finite_f64".to_owned(), vec![]),
+            C::RandU31Timestamp(_) => ("distribution rand.u31_timestamp".to_owned(), vec![]),
+            C::RandShuffle(_) => ("distribution rand.shuffle".to_owned(), vec![]),
+            C::RandUuid => ("distribution rand.uuid".to_owned(), vec![]),
+        };
+        explain_line(out, depth, label);
+        for child in children {
+            child.explain(out, depth + 1);
+        }
+    }
+}
+
+impl Row {
+    /// Describes the expressions of every column for `--explain`.
+    ///
+    /// Columns without a name in `names` are labeled by their position.
+    pub fn explain<'a>(&self, out: &mut String, depth: usize, names: impl Iterator<Item = &'a str>) {
+        let mut names = names.fuse();
+        for (i, compiled) in self.0.iter().enumerate() {
+            match names.next() {
+                Some(name) if !name.is_empty() => explain_line(out, depth, format_args!("{}:", name)),
+                _ => explain_line(out, depth, format_args!("#{}:", i + 1)),
+            }
+            compiled.explain(out, depth + 1);
+        }
+    }
+}
+
+impl Table {
+    /// Describes the compiled expressions of this table for `--explain`.
+    ///
+    /// The `tables` are all tables in the template, used to name the derived
+    /// tables.
+    pub fn explain(&self, out: &mut String, tables: &[Self]) {
+        let schema = self.schema(true);
+        explain_line(out, 0, format_args!("table {}:", schema.name));
+        self.row.explain(out, 1, schema.column_names());
+        if let Some(filter) = &self.filter {
+            match self.retry_limit {
+                Some(attempts) => explain_line(out, 1, format_args!("retry until (at most {} attempts):", attempts)),
+                None => explain_line(out, 1, "where:"),
+            }
+            filter.explain(out, 2);
+        }
+        for (index, count) in &self.derived {
+            explain_line(
+                out,
+                1,
+                format_args!(
+                    "for each row generate rows of {}:",
+                    tables[*index].name.table_name(true)
+                ),
+            );
+            count.explain(out, 2);
         }
-        .span(expr.span))
     }
 }
 
+/// Evaluates the lazy `AND`/`OR` expression.
+fn eval_logic(identity: bool, args: &[Compiled], state: &mut State) -> Result<Value, S<Error>> {
+    let mut result = Some(identity);
+    for arg in args {
+        match Option::<bool>::try_from(arg.eval(state)?).span_err(arg.0.span)? {
+            Some(v) if v == identity => {}
+            Some(v) => return Ok(v.into()),
+            None => result = None,
+        }
+    }
+    Ok(result.into())
+}
+
+/// Evaluates the lazy `coalesce` expression.
+fn eval_coalesce(args: &[Compiled], state: &mut State) -> Result<Value, S<Error>> {
+    for arg in args {
+        let value = arg.eval(state)?;
+        if value != Value::Null {
+            return Ok(value);
+        }
+    }
+    Ok(Value::Null)
+}
+
+/// Evaluates the lazy `nullif` expression.
+fn eval_null_if(value: &Compiled, other: &Compiled, state: &mut State) -> Result<Value, S<Error>> {
+    let value = value.eval(state)?;
+    if value == Value::Null {
+        return Ok(value);
+    }
+    let other_span = other.0.span;
+    let other = other.eval(state)?;
+    Ok(
+        if value.sql_cmp(&other).span_err(other_span)? == Some(Ordering::Equal) {
+            Value::Null
+        } else {
+            value
+        },
+    )
+}
+
 impl Compiled {
     /// Returns whether this compiled value is a constant.
     pub fn is_constant(&self) -> bool {
         matches!(self.0.inner, C::Constant(_))
     }
 
+    /// Infers the possible types of the value evaluated from this expression.
+    ///
+    /// Variables and column references are assumed to be of any type.
+    pub fn types(&self) -> Types {
+        match &self.0.inner {
+            C::RowNum
+            | C::SubRowNum
+            | C::RandUniformU64(_)
+            | C::RandUniformI64(_)
+            | C::RandUniformF64(_)
+            | C::RandDecimal(..)
+            | C::RandZipf(_)
+            | C::RandLogNormal(_)
+            | C::RandBool(_)
+            | C::RandFiniteF32(_)
+            | C::RandFiniteF64(_) => Types::NUMBER,
+            C::Constant(v) => Types::of(v),
+            C::RawFunction { function, .. } => function.signature().result(),
+            C::GetVariable(_) | C::GetColumn(_) | C::Lag { .. } => Types::ANY,
+            C::SetVariable(_, c) => c.types(),
+            C::CaseValueWhen {
+                conditions, otherwise, ..
+            } => conditions.iter().fold(otherwise.types(), |t, (_, r)| t | r.types()),
+            C::Logic { .. } => Types::NULLABLE_BOOL,
+            C::Coalesce(args) => args.iter().fold(Types::NULL, |t, c| t | c.types()),
+            C::NullIf(value, _) => value.types() | Types::NULL,
+            C::RandRegex(_) | C::RandUuid => Types::BYTES,
+            C::RandU31Timestamp(_) => Types::TIMESTAMP,
+            C::RandShuffle(_) => Types::ARRAY,
+        }
+    }
+
     /// Evaluates a compiled expression and updates the state. Returns the evaluated value.
+    #[allow(clippy::too_many_lines)] // one arm per variant, splitting it would not be clearer.
     pub fn eval(&self, state: &mut State) -> Result<Value, S<Error>> {
         let span = self.0.span;
         Ok(match &self.0.inner {
             C::RowNum => state.row_num.into(),
             C::SubRowNum => state.sub_row_num.into(),
             C::Constant(v) => v.clone(),
-            C::RawFunction { function, args } => {
+            C::RawFunction { function, args, .. } => {
                 let mut eval_args = Arguments::with_capacity(args.len());
                 for c in &**args {
                     eval_args.push(c.eval(state)?.span(c.0.span));
                 }
-                (*function)
-                    .compile(&state.compile_context, span, eval_args)?
-                    .span(span)
-                    .eval(state)?
+                if span == Span::default() || state.compile_cache.constant_calls.contains(&span) {
+                    return (*function)
+                        .compile(&state.compile_context, span, eval_args)?
+                        .span(span)
+                        .eval(state);
+                }
+                let compiled = if let Some(compiled) = state.compile_cache.get(span, &eval_args) {
+                    compiled
+                } else {
+                    let key = eval_args.iter().map(|arg| arg.inner.clone()).collect();
+                    let compiled = (*function).compile(&state.compile_context, span, eval_args)?;
+                    if let C::Constant(value) = compiled {
+                        state.compile_cache.constant_calls.insert(span);
+                        return Ok(value);
+                    }
+                    let compiled = Arc::new(compiled.span(span));
+                    state.compile_cache.insert(span, key, compiled.clone());
+                    compiled
+                };
+                compiled.eval(state)?
             }
             C::GetVariable(index) => state.compile_context.variables[*index].clone(),
+            C::GetColumn(index) => state.row_values[*index].clone(),
+            C::Lag {
+                column,
+                offset,
+                default,
+            } => match state.history.get(*offset - 1) {
+                Some(row) => row[*column].clone(),
+                None => default.eval(state)?,
+            },
             C::SetVariable(index, c) => {
-                let value = c.eval(state)?;
+                let mut value = c.eval(state)?;
+                // variables are cloned every time they are read.
+                value.share();
                 state.compile_context.variables[*index] = value.clone();
                 value
             }
@@ -377,10 +1023,17 @@ impl Compiled {
                 otherwise.eval(state)?
             }
 
+            C::Logic { identity, args } => eval_logic(*identity, args, state)?,
+            C::Coalesce(args) => eval_coalesce(args, state)?,
+            C::NullIf(value, other) => eval_null_if(value, other, state)?,
+
             C::RandRegex(generator) => state.rng.sample::<EncodedString, _>(generator).into(),
             C::RandUniformU64(uniform) => state.rng.sample(uniform).into(),
             C::RandUniformI64(uniform) => state.rng.sample(uniform).into(),
             C::RandUniformF64(uniform) => Value::from_finite_f64(state.rng.sample(uniform)),
+            C::RandDecimal(uniform, scale) => Number::from_decimal(state.rng.sample(uniform), *scale)
+                .expect("scale has been validated")
+                .into(),
             C::RandZipf(zipf) => (state.rng.sample(zipf) as u64).into(),
             C::RandLogNormal(log_normal) => Value::from_finite_f64(state.rng.sample(log_normal)),
             C::RandBool(bern) => state.rng.sample(bern).into(),
@@ -422,3 +1075,44 @@ impl Compiled {
         })
     }
 }
+
+#[test]
+fn test_eval_batch() {
+    use crate::{parser::Template, span::Registry};
+    use rand::SeedableRng;
+
+    let test_cases = [
+        "create table a ({{ rownum }}, {{ 'x' }}, {{ rand.range(0, 1000) }});",
+        "create table a (x {{ rownum }}, y {{ rand.range(0, 1000) }}, z {{ rand.regex('[a-z]{1,5}') }}, w {{ col(y) * 2 }});",
+        "create table a ({{ subrownum }}, {{ CASE WHEN rownum > 3 THEN 'x' ELSE rownum END }});",
+    ];
+    for input in &test_cases {
+        let mut registry = Registry::default();
+        let template = Template::parse(input, None, &[], None, &mut registry).unwrap();
+        let ctx = CompileContext::new(template.variables_count);
+        let row = ctx.compile_row(template.tables[0].exprs.clone()).unwrap();
+        let new_state = || State::new(5, Box::new(rand_hc::Hc128Rng::from_seed([0x41; 32])), ctx.clone());
+
+        let mut state = new_state();
+        let batch = row.eval_batch(&mut state, 7).unwrap();
+        assert_eq!(batch.len, 7);
+        assert_eq!(state.row_num, 12);
+        if input == &test_cases[0] {
+            assert!(matches!(batch.columns[0], Column::Sequence(5)));
+            assert!(matches!(batch.columns[1], Column::Constant(_)));
+            assert!(matches!(batch.columns[2], Column::Numbers(_)));
+        }
+
+        let mut expected_state = new_state();
+        for i in 0..7 {
+            assert_eq!(
+                batch.row(i),
+                row.eval(&mut expected_state).unwrap(),
+                "{} row {}",
+                input,
+                i
+            );
+            expected_state.increase_row_num();
+        }
+    }
+}
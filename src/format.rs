@@ -1,8 +1,9 @@
 //! Output formatter
 
-use crate::{bytes::ByteString, eval::Schema, value::Value};
+use crate::{bytes::ByteString, eval::Schema, json::Json, value::Value};
 
 use chrono::{DateTime, Datelike, TimeZone, Timelike};
+use data_encoding::HEXUPPER;
 use memchr::{memchr2_iter, memchr3_iter, memchr_iter};
 use rand_regex::Encoding;
 use std::{
@@ -215,32 +216,60 @@ fn write_with_escape(writer: &mut dyn Write, bytes: &[u8], rules: &[(u8, EscapeR
     writer.write_all(&bytes[state.prev_end..])
 }
 
+/// A writer escaping all content written through it, so that values such as
+/// JSON can be written into the output directly without an intermediate string.
+struct EscapeWriter<'a> {
+    writer: &'a mut dyn Write,
+    rules: &'static [(u8, EscapeRule)],
+}
+
+impl Write for EscapeWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        write_with_escape(self.writer, buf, self.rules)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+}
+
 impl Options {
+    fn sql_escape_rules(&self) -> &'static [(u8, EscapeRule)] {
+        if self.escape_backslash {
+            &[
+                (b'\'', EscapeRule::Escape(b"''")),
+                (b'\\', EscapeRule::Escape(br"\\")),
+                (b'\0', EscapeRule::Escape(br"\0")),
+            ]
+        } else {
+            &[(b'\'', EscapeRule::Escape(b"''"))]
+        }
+    }
+
     fn write_sql_bytes(&self, writer: &mut dyn Write, bytes: &ByteString) -> Result<(), Error> {
         if bytes.encoding() == Encoding::Binary {
             writer.write_all(b"X'")?;
-            for b in bytes.as_bytes() {
-                write!(writer, "{:02X}", b)?;
+            let mut buf = [0; 256];
+            for chunk in bytes.as_bytes().chunks(buf.len() / 2) {
+                let hex = &mut buf[..chunk.len() * 2];
+                HEXUPPER.encode_mut(chunk, hex);
+                writer.write_all(hex)?;
             }
         } else {
             writer.write_all(b"'")?;
-            write_with_escape(
-                writer,
-                bytes.as_bytes(),
-                if self.escape_backslash {
-                    &[
-                        (b'\'', EscapeRule::Escape(b"''")),
-                        (b'\\', EscapeRule::Escape(br"\\")),
-                        (b'\0', EscapeRule::Escape(br"\0")),
-                    ]
-                } else {
-                    &[(b'\'', EscapeRule::Escape(b"''"))]
-                },
-            )?;
+            write_with_escape(writer, bytes.as_bytes(), self.sql_escape_rules())?;
         }
         writer.write_all(b"'")
     }
 
+    fn write_sql_json(&self, writer: &mut dyn Write, json: &Json) -> Result<(), Error> {
+        writer.write_all(b"'")?;
+        let rules = self.sql_escape_rules();
+        write!(EscapeWriter { writer, rules }, "{}", json)?;
+        writer.write_all(b"'")
+    }
+
     /// Writes a value in SQL format.
     pub fn write_sql_value(&self, writer: &mut dyn Write, value: &Value) -> Result<(), Error> {
         match value {
@@ -249,6 +278,7 @@ impl Options {
             Value::Bytes(bytes) => self.write_sql_bytes(writer, bytes),
             Value::Timestamp(timestamp, tz) => write_timestamp(writer, "'", &tz.from_utc_datetime(timestamp)),
             Value::Interval(interval) => write_interval(writer, "'", *interval),
+            Value::Json(json) => self.write_sql_json(writer, json),
             Value::Array(array) => {
                 writer.write_all(b"ARRAY[")?;
                 for (i, item) in array.iter().enumerate() {
@@ -335,17 +365,24 @@ impl Format for SqlInsertSetFormat {
 }
 
 impl CsvFormat {
+    fn escape_rules(&self) -> &'static [(u8, EscapeRule)] {
+        if self.0.escape_backslash {
+            &[(b'"', EscapeRule::Escape(b"\"\"")), (b'\\', EscapeRule::Escape(br"\\"))]
+        } else {
+            &[(b'"', EscapeRule::Escape(b"\"\""))]
+        }
+    }
+
     fn write_bytes(&self, writer: &mut dyn Write, bytes: &ByteString) -> Result<(), Error> {
         writer.write_all(b"\"")?;
-        write_with_escape(
-            writer,
-            bytes.as_bytes(),
-            if self.0.escape_backslash {
-                &[(b'"', EscapeRule::Escape(b"\"\"")), (b'\\', EscapeRule::Escape(br"\\"))]
-            } else {
-                &[(b'"', EscapeRule::Escape(b"\"\""))]
-            },
-        )?;
+        write_with_escape(writer, bytes.as_bytes(), self.escape_rules())?;
+        writer.write_all(b"\"")
+    }
+
+    fn write_json(&self, writer: &mut dyn Write, json: &Json) -> Result<(), Error> {
+        writer.write_all(b"\"")?;
+        let rules = self.escape_rules();
+        write!(EscapeWriter { writer, rules }, "{}", json)?;
         writer.write_all(b"\"")
     }
 
@@ -376,6 +413,7 @@ impl Format for CsvFormat {
             Value::Bytes(bytes) => self.write_bytes(writer, bytes),
             Value::Timestamp(timestamp, tz) => write_timestamp(writer, "", &tz.from_utc_datetime(timestamp)),
             Value::Interval(interval) => write_interval(writer, "", *interval),
+            Value::Json(json) => self.write_json(writer, json),
             Value::Array(array) => {
                 writer.write_all(b"{")?;
                 for (i, item) in array.iter().enumerate() {
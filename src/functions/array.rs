@@ -5,6 +5,7 @@ use crate::{
     error::Error,
     eval::{CompileContext, C},
     span::{ResultExt, Span, SpanExt, S},
+    types::{Signature, Types},
     value::Value,
 };
 use std::{cmp::Ordering, sync::Arc};
@@ -19,6 +20,10 @@ impl Function for Array {
             args.into_iter().map(|arg| arg.inner).collect(),
         )))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::ARRAY).optional_after(0).variadic()
+    }
 }
 
 /// The array subscript operator.
@@ -34,6 +39,10 @@ impl Function for Subscript {
             base[index - 1].clone()
         }))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ARRAY, Types::NUMBER], Types::ANY)
+    }
 }
 
 /// The `generate_series` SQL function.
@@ -64,4 +73,8 @@ impl Function for GenerateSeries {
 
         Ok(C::Constant(Value::Array(result.into())))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY, Types::ANY, Types::ANY], Types::ARRAY).optional_after(2)
+    }
 }
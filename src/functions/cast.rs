@@ -0,0 +1,273 @@
+//! Type conversion functions.
+
+use super::{Arguments, Function};
+use crate::{
+    bytes::ByteString,
+    error::Error,
+    eval::{CompileContext, C},
+    json::Json,
+    number::{Number, MAX_DECIMAL_SCALE},
+    span::{ResultExt, Span, SpanExt, S},
+    value::{Value, TIMESTAMP_FORMAT},
+};
+use chrono::{NaiveDate, TimeZone};
+use std::{cmp::Ordering, convert::TryFrom, iter};
+
+/// The `CAST` SQL function.
+///
+/// The first argument is the value to convert. The remaining arguments are the
+/// type modifiers, e.g. `VARCHAR(10)` is compiled as `Cast::Char` with
+/// arguments `(value, 10)`.
+#[derive(Debug)]
+pub enum Cast {
+    /// Converts to a signed integer with the given number of bits
+    /// (`TINYINT`, `SMALLINT`, `MEDIUMINT`, `INT`, `BIGINT`).
+    Int(u32),
+    /// Converts to an unsigned integer with the given number of bits
+    /// (`INT UNSIGNED`, `UNSIGNED`).
+    Unsigned(u32),
+    /// Converts to an exact decimal with optional precision and scale
+    /// (`DECIMAL(p, s)`).
+    Decimal,
+    /// Converts to a floating point number (`FLOAT`, `DOUBLE`).
+    Float,
+    /// Converts to a string, truncated to the optional number of characters
+    /// (`VARCHAR(n)`, `CHAR(n)`).
+    Char,
+    /// Converts to a byte string, truncated to the optional number of bytes
+    /// (`BINARY(n)`).
+    Binary,
+    /// Converts to a timestamp (`TIMESTAMP`).
+    Timestamp,
+    /// Converts to a timestamp truncated to midnight (`DATE`).
+    Date,
+    /// Converts to a boolean (`BOOLEAN`).
+    Boolean,
+    /// Converts to JSON (`JSON`).
+    Json,
+}
+
+impl Cast {
+    /// Gets the SQL type name.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Int(8) => "TINYINT",
+            Self::Int(16) => "SMALLINT",
+            Self::Int(24) => "MEDIUMINT",
+            Self::Int(32) => "INT",
+            Self::Int(64) => "BIGINT",
+            Self::Int(_) => "INTEGER",
+            Self::Unsigned(8) => "TINYINT UNSIGNED",
+            Self::Unsigned(16) => "SMALLINT UNSIGNED",
+            Self::Unsigned(24) => "MEDIUMINT UNSIGNED",
+            Self::Unsigned(32) => "INT UNSIGNED",
+            Self::Unsigned(_) => "UNSIGNED",
+            Self::Decimal => "DECIMAL",
+            Self::Float => "DOUBLE",
+            Self::Char => "VARCHAR",
+            Self::Binary => "BINARY",
+            Self::Timestamp => "TIMESTAMP",
+            Self::Date => "DATE",
+            Self::Boolean => "BOOLEAN",
+            Self::Json => "JSON",
+        }
+    }
+
+    /// Creates the error for values which cannot be converted.
+    fn invalid(&self, value: &Value) -> Error {
+        Error::InvalidArguments(format!("cannot cast {} as {}", value, self.name()))
+    }
+
+    /// Creates the error for values which are out of range of the type.
+    fn overflow(&self, value: &Value) -> Error {
+        Error::IntegerOverflow(format!("CAST({} AS {})", value, self.name()))
+    }
+
+    /// Converts a value into a number, parsing strings if needed.
+    fn to_number(&self, value: &Value) -> Result<Number, Error> {
+        match value {
+            Value::Number(n) => Ok(*n),
+            Value::Bytes(b) => b
+                .as_str()
+                .and_then(|s| Number::parse(s.trim()))
+                .ok_or_else(|| self.invalid(value)),
+            Value::Json(json) => match &**json {
+                Json::Number(n) => Ok(*n),
+                Json::Bool(b) => Ok((*b).into()),
+                _ => Err(self.invalid(value)),
+            },
+            _ => Err(self.invalid(value)),
+        }
+    }
+
+    /// Parses a string into a timestamp in the configured time zone.
+    ///
+    /// Both the full timestamp format and a date alone are accepted.
+    fn parse_timestamp(&self, ctx: &CompileContext, value: &Value) -> Result<Value, Error> {
+        let input = match value {
+            Value::Bytes(b) => b.as_str().ok_or_else(|| self.invalid(value))?.trim(),
+            _ => return Err(self.invalid(value)),
+        };
+        let tz = ctx.time_zone.clone();
+        let timestamp = match tz.datetime_from_str(input, TIMESTAMP_FORMAT) {
+            Ok(timestamp) => timestamp,
+            Err(e) => NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| tz.from_local_datetime(&date.and_hms(0, 0, 0)).earliest())
+                .ok_or(e)?,
+        };
+        Ok(Value::Timestamp(timestamp.naive_utc(), tz))
+    }
+
+    /// Converts the value into the target type.
+    fn convert(&self, ctx: &CompileContext, value: &Value, modifiers: &[i128]) -> Result<Value, Error> {
+        if *value == Value::Null {
+            return Ok(Value::Null);
+        }
+        Ok(match self {
+            Self::Int(bits) => {
+                let n = i128::try_from(self.to_number(value)?.trunc()).map_err(|_| self.overflow(value))?;
+                let max = i128::MAX >> (128 - bits);
+                if n < !max || n > max {
+                    return Err(self.overflow(value));
+                }
+                n.into()
+            }
+            Self::Unsigned(bits) => {
+                let n = u64::try_from(self.to_number(value)?.trunc()).map_err(|_| self.overflow(value))?;
+                if n > u64::MAX >> (64 - bits) {
+                    return Err(self.overflow(value));
+                }
+                n.into()
+            }
+            Self::Decimal => {
+                let precision = modifiers.first().copied().unwrap_or(MAX_DECIMAL_SCALE.into());
+                let scale = modifiers.get(1).copied().unwrap_or(0);
+                let (precision, scale) = match (u8::try_from(precision), u8::try_from(scale)) {
+                    (Ok(p), Ok(s)) if 0 < p && p <= MAX_DECIMAL_SCALE && s <= p => (p, s),
+                    _ => {
+                        return Err(Error::InvalidArguments(format!(
+                            "invalid precision and scale DECIMAL({}, {})",
+                            precision, scale
+                        )))
+                    }
+                };
+                let mantissa = self
+                    .to_number(value)?
+                    .to_decimal(scale)
+                    .map_err(|_| self.overflow(value))?;
+                if mantissa.unsigned_abs() >= 10_u128.pow(precision.into()) {
+                    return Err(self.overflow(value));
+                }
+                Number::from_decimal(mantissa, scale)
+                    .map_err(|_| self.overflow(value))?
+                    .into()
+            }
+            Self::Float => Value::from_finite_f64(f64::from(self.to_number(value)?)),
+            Self::Char | Self::Binary => {
+                let mut result = match Value::sql_concat(iter::once(value))? {
+                    Value::Bytes(b) => b,
+                    _ => ByteString::default(),
+                };
+                if let Some(length) = modifiers.first() {
+                    let length = usize::try_from(*length).unwrap_or(0);
+                    let length = if let Self::Char = self {
+                        result.char_range(0..length).end
+                    } else {
+                        length
+                    };
+                    if length < result.len() {
+                        result.truncate(length);
+                    }
+                }
+                result.into()
+            }
+            Self::Timestamp => match value {
+                Value::Timestamp(..) => value.clone(),
+                _ => self.parse_timestamp(ctx, value)?,
+            },
+            Self::Date => {
+                let (timestamp, tz) = match value {
+                    Value::Timestamp(timestamp, tz) => (*timestamp, tz.clone()),
+                    _ => match self.parse_timestamp(ctx, value)? {
+                        Value::Timestamp(timestamp, tz) => (timestamp, tz),
+                        _ => unreachable!(),
+                    },
+                };
+                let date = tz.from_utc_datetime(&timestamp).naive_local().date();
+                let midnight = tz
+                    .from_local_datetime(&date.and_hms(0, 0, 0))
+                    .earliest()
+                    .ok_or_else(|| self.invalid(value))?;
+                Value::Timestamp(midnight.naive_utc(), tz)
+            }
+            Self::Boolean => match value {
+                Value::Number(n) => (n.sql_sign() != Ordering::Equal).into(),
+                Value::Bytes(b) => match b.as_str().map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+                    Some("true" | "t" | "yes" | "y" | "on" | "1") => true.into(),
+                    Some("false" | "f" | "no" | "n" | "off" | "0") => false.into(),
+                    _ => return Err(self.invalid(value)),
+                },
+                Value::Json(json) => match &**json {
+                    Json::Bool(b) => (*b).into(),
+                    _ => return Err(self.invalid(value)),
+                },
+                _ => return Err(self.invalid(value)),
+            },
+            Self::Json => Json::from_value(value)?.into(),
+        })
+    }
+}
+
+impl Function for Cast {
+    fn compile(&self, ctx: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let mut it = args.into_iter();
+        let value = it.next().ok_or_else(|| Error::NotEnoughArguments.span(span))?;
+        let modifiers = it
+            .map(|arg| i128::try_from(arg.inner).span_err(arg.span))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(C::Constant(self.convert(ctx, &value.inner, &modifiers).span_err(span)?))
+    }
+}
+
+#[test]
+fn test_cast_errors() {
+    let ctx = CompileContext::new(0);
+    let overflow_cases: &[(Cast, Value, &[i128])] = &[
+        (Cast::Int(8), 128.into(), &[]),
+        (Cast::Int(8), Value::from_finite_f64(-129.5), &[]),
+        (Cast::Int(64), Value::from(u64::MAX), &[]),
+        (Cast::Unsigned(64), Value::from(-1), &[]),
+        (Cast::Unsigned(32), Value::from(1_u64 << 32), &[]),
+        (Cast::Int(24), Value::from(1 << 23), &[]),
+        (Cast::Decimal, Value::from_finite_f64(999.995), &[5, 2]),
+        (Cast::Int(32), Value::from_finite_f64(1e300), &[]),
+    ];
+    for (cast, value, modifiers) in overflow_cases {
+        match cast.convert(&ctx, value, modifiers) {
+            Err(Error::IntegerOverflow(_)) => {}
+            r => panic!("CAST({} AS {}) should overflow, got {:?}", value, cast.name(), r),
+        }
+    }
+
+    let invalid_cases: &[(Cast, Value, &[i128])] = &[
+        (Cast::Int(32), "abc".to_owned().into(), &[]),
+        (Cast::Boolean, "maybe".to_owned().into(), &[]),
+        (Cast::Timestamp, "2020-13-45".to_owned().into(), &[]),
+        (Cast::Decimal, 1.into(), &[2, 3]),
+        (Cast::Char, Value::Array(vec![Value::Null].into()), &[]),
+    ];
+    for (cast, value, modifiers) in invalid_cases {
+        assert!(
+            cast.convert(&ctx, value, modifiers).is_err(),
+            "CAST({} AS {}) should fail",
+            value,
+            cast.name()
+        );
+    }
+
+    assert_eq!(
+        Cast::Int(8).convert(&ctx, &Value::from_finite_f64(-128.9), &[]).ok(),
+        Some(Value::from(-128))
+    );
+}
@@ -1,14 +1,20 @@
-//! Encoding and decoding functions.
+//! Encoding, decoding and hashing functions.
 
 use data_encoding::{Encoding, BASE64, BASE64URL_NOPAD};
 use data_encoding_macro::new_encoding;
+use hmac::Mac;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::hash::Hasher;
 
-use super::{args_1, Arguments, Function};
+use super::{args_1, args_2, args_3, Arguments, Function};
 use crate::{
     bytes::ByteString,
     error::Error,
     eval::{CompileContext, C},
-    span::{ResultExt, Span, S},
+    span::{ResultExt, Span, SpanExt, S},
+    types::{Signature, Types},
 };
 
 const HEX_ENCODING: Encoding = new_encoding! {
@@ -48,6 +54,10 @@ impl Function for Decode {
         let decoded = self.encoding.decode(encoded.as_bytes()).span_err(span)?;
         Ok(C::Constant(decoded.into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::BYTES)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -75,4 +85,160 @@ impl Function for Encode {
         let encoded = self.encoding.encode(decoded.as_bytes());
         Ok(C::Constant(encoded.into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::BYTES)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// Cryptographic hash algorithms.
+#[derive(Debug, Copy, Clone)]
+enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Parses the algorithm name.
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match &*name.to_ascii_lowercase() {
+            "md5" => Self::Md5,
+            "sha1" => Self::Sha1,
+            "sha256" => Self::Sha256,
+            "sha512" => Self::Sha512,
+            _ => return None,
+        })
+    }
+
+    /// Computes the digest of the input.
+    fn digest(self, input: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Md5 => Md5::digest(input).to_vec(),
+            Self::Sha1 => Sha1::digest(input).to_vec(),
+            Self::Sha256 => Sha256::digest(input).to_vec(),
+            Self::Sha512 => Sha512::digest(input).to_vec(),
+        }
+    }
+
+    /// Computes the HMAC of the message using this algorithm.
+    fn hmac(self, key: &[u8], msg: &[u8]) -> Vec<u8> {
+        fn compute<M: Mac + hmac::digest::KeyInit>(key: &[u8], msg: &[u8]) -> Vec<u8> {
+            let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+            mac.update(msg);
+            mac.finalize().into_bytes().to_vec()
+        }
+
+        match self {
+            Self::Md5 => compute::<hmac::Hmac<Md5>>(key, msg),
+            Self::Sha1 => compute::<hmac::Hmac<Sha1>>(key, msg),
+            Self::Sha256 => compute::<hmac::Hmac<Sha256>>(key, msg),
+            Self::Sha512 => compute::<hmac::Hmac<Sha512>>(key, msg),
+        }
+    }
+}
+
+/// The cryptographic hash SQL functions.
+#[derive(Debug)]
+pub struct Hash {
+    algorithm: HashAlgorithm,
+}
+
+/// The `md5` SQL function.
+pub const MD5: Hash = Hash {
+    algorithm: HashAlgorithm::Md5,
+};
+/// The `sha1` SQL function.
+pub const SHA1: Hash = Hash {
+    algorithm: HashAlgorithm::Sha1,
+};
+/// The `sha256` SQL function.
+pub const SHA256: Hash = Hash {
+    algorithm: HashAlgorithm::Sha256,
+};
+/// The `sha512` SQL function.
+pub const SHA512: Hash = Hash {
+    algorithm: HashAlgorithm::Sha512,
+};
+
+impl Function for Hash {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let input = args_1::<ByteString>(span, args, None)?;
+        Ok(C::Constant(self.algorithm.digest(input.as_bytes()).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::BYTES)
+    }
+}
+
+/// The `hmac` SQL function.
+#[derive(Debug)]
+pub struct Hmac;
+
+impl Function for Hmac {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (key, msg, algorithm) =
+            args_3::<ByteString, ByteString, String>(span, args, None, None, Some("sha256".to_owned()))?;
+        let algorithm = HashAlgorithm::from_name(&algorithm)
+            .ok_or_else(|| Error::InvalidArguments(format!("unsupported hash algorithm '{}'", algorithm)).span(span))?;
+        Ok(C::Constant(algorithm.hmac(key.as_bytes(), msg.as_bytes()).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES, Types::BYTES], Types::BYTES).optional_after(2)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `crc32` SQL function.
+#[derive(Debug)]
+pub struct Crc32;
+
+impl Function for Crc32 {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let input = args_1::<ByteString>(span, args, None)?;
+        Ok(C::Constant(crc32fast::hash(input.as_bytes()).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::NUMBER)
+    }
+}
+
+/// The `xxhash64` SQL function.
+#[derive(Debug)]
+pub struct XxHash64;
+
+impl Function for XxHash64 {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, seed) = args_2::<ByteString, u64>(span, args, None, Some(0))?;
+        let mut hasher = twox_hash::XxHash64::with_seed(seed);
+        hasher.write(input.as_bytes());
+        Ok(C::Constant(hasher.finish().into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::NUMBER], Types::NUMBER).optional_after(1)
+    }
+}
+
+/// The `murmur3` SQL function.
+#[derive(Debug)]
+pub struct Murmur3;
+
+impl Function for Murmur3 {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, seed) = args_2::<ByteString, u32>(span, args, None, Some(0))?;
+        let hash = murmur3::murmur3_32(&mut input.as_bytes(), seed).expect("reading from a slice never fails");
+        Ok(C::Constant(hash.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::NUMBER], Types::NUMBER).optional_after(1)
+    }
 }
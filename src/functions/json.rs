@@ -0,0 +1,107 @@
+//! JSON functions.
+
+use super::{args_1, require, Arguments, Function};
+use crate::{
+    error::Error,
+    eval::{CompileContext, C},
+    json::Json,
+    span::{ResultExt, Span, S},
+    types::{Signature, Types},
+    value::Value,
+};
+use std::convert::TryFrom;
+
+/// Converts the arguments into a list of key-value pairs.
+fn into_pairs(span: Span, args: Arguments) -> Result<Vec<(String, Json)>, S<Error>> {
+    require(span, args.len() % 2 == 0, || {
+        "expecting an even number of arguments for key-value pairs".to_owned()
+    })?;
+    let mut it = args.into_iter();
+    let mut pairs = Vec::with_capacity(it.len() / 2);
+    while let (Some(key), Some(value)) = (it.next(), it.next()) {
+        let key = String::try_from(key.inner).span_err(key.span)?;
+        let value = Json::from_value(&value.inner).span_err(value.span)?;
+        pairs.push((key, value));
+    }
+    Ok(pairs)
+}
+
+//------------------------------------------------------------------------------
+
+/// The `json_object` SQL function.
+#[derive(Debug)]
+pub struct JsonObject;
+
+impl Function for JsonObject {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let mut object = Json::Object(Vec::new());
+        for (key, value) in into_pairs(span, args)? {
+            object.insert(key, value);
+        }
+        Ok(C::Constant(object.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::JSON).optional_after(0).variadic()
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `json_array` SQL function.
+#[derive(Debug)]
+pub struct JsonArray;
+
+impl Function for JsonArray {
+    fn compile(&self, _: &CompileContext, _: Span, args: Arguments) -> Result<C, S<Error>> {
+        let items = args
+            .into_iter()
+            .map(|arg| Json::from_value(&arg.inner).span_err(arg.span))
+            .collect::<Result<_, _>>()?;
+        Ok(C::Constant(Json::Array(items).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::JSON).optional_after(0).variadic()
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `json_build` SQL function.
+#[derive(Debug)]
+pub struct JsonBuild;
+
+impl Function for JsonBuild {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let mut object = Json::Object(Vec::new());
+        for (path, value) in into_pairs(span, args)? {
+            object.insert_path(&path, value).span_err(span)?;
+        }
+        Ok(C::Constant(object.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::JSON).optional_after(0).variadic()
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `to_json` SQL function.
+#[derive(Debug)]
+pub struct ToJson;
+
+impl Function for ToJson {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let value = args_1::<S<Value>>(span, args, None)?;
+        Ok(C::Constant(match value.inner {
+            Value::Null => Value::Null,
+            v => Json::from_value(&v).span_err(value.span)?.into(),
+        }))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::JSON.or(Types::NULL))
+    }
+}
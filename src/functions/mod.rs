@@ -2,16 +2,19 @@
 
 use crate::{
     error::Error,
-    eval::{CompileContext, C},
+    eval::{CompileContext, Lazy, C},
     span::{ResultExt, Span, SpanExt, S},
+    types::Signature,
     value::Value,
 };
 
 use std::{convert::TryFrom, fmt::Debug};
 
 pub mod array;
+pub mod cast;
 pub mod codec;
 pub mod debug;
+pub mod json;
 pub mod ops;
 pub mod rand;
 pub mod string;
@@ -24,6 +27,24 @@ pub type Arguments = smallvec::SmallVec<[S<Value>; 2]>;
 pub trait Function: Sync + Debug {
     /// Compiles or evaluates this function taking the provided arguments.
     fn compile(&self, ctx: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>>;
+
+    /// Returns how this function should be evaluated when some arguments are
+    /// not constant.
+    ///
+    /// By default (`None`), all arguments are evaluated before calling
+    /// `compile`. Functions which may not need every argument can return a
+    /// [`Lazy`] strategy to evaluate the arguments on demand instead.
+    fn lazy(&self) -> Option<Lazy> {
+        None
+    }
+
+    /// Returns the signature of this function, which is used to check the
+    /// arguments at compile time.
+    ///
+    /// By default, any number of arguments of any type are accepted.
+    fn signature(&self) -> Signature {
+        Signature::ANY
+    }
 }
 
 trait TryFromSpannedValue: Sized {
@@ -1,14 +1,17 @@
 //! Numerical and logical functions.
 
-use super::{args_1, args_2, iter_args, Arguments, Function};
+use super::{args_1, args_2, args_3, iter_args, Arguments, Function};
 use crate::{
+    bytes::ByteString,
     error::Error,
-    eval::{CompileContext, C},
-    number::Number,
-    span::{ResultExt, Span, S},
+    eval::{CompileContext, Lazy, C},
+    number::{Number, NumberError},
+    span::{ResultExt, Span, SpanExt, S},
+    types::{Signature, Types},
     value::Value,
 };
-use std::cmp::Ordering;
+use regex::bytes::Regex;
+use std::{cmp::Ordering, convert::TryFrom};
 
 //------------------------------------------------------------------------------
 
@@ -21,6 +24,10 @@ impl Function for Neg {
         let inner = args_1::<Number>(span, args, None)?;
         Ok(C::Constant(inner.neg().into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -86,6 +93,10 @@ impl Function for Compare {
             panic!("should have exactly 2 arguments");
         }
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY, Types::ANY], Types::NULLABLE_BOOL)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -111,6 +122,181 @@ impl Function for Identical {
             panic!("should have exactly 2 arguments");
         }
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY, Types::ANY], Types::NUMBER)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// Converts a nullable boolean into a value, negating it if required.
+fn predicate_result(result: Option<bool>, negated: bool) -> C {
+    C::Constant(result.map(|b| b != negated).into())
+}
+
+/// The `IN` and `NOT IN` SQL functions.
+///
+/// The first argument is the value to search for, and the remaining arguments
+/// are the list of candidates.
+#[derive(Debug)]
+pub struct In {
+    /// Whether the result is negated (`NOT IN`).
+    negated: bool,
+}
+
+/// The `IN` SQL function.
+pub const IN: In = In { negated: false };
+/// The `NOT IN` SQL function.
+pub const NOT_IN: In = In { negated: true };
+
+impl Function for In {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (needle, candidates) = args.split_first().expect("should have at least 1 argument");
+        let mut result = Some(false);
+        for candidate in candidates {
+            match needle.inner.sql_cmp(&candidate.inner).span_err(span)? {
+                Some(Ordering::Equal) => {
+                    result = Some(true);
+                    break;
+                }
+                Some(_) => {}
+                None => result = None,
+            }
+        }
+        Ok(predicate_result(result, self.negated))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY, Types::ANY], Types::NULLABLE_BOOL)
+            .optional_after(1)
+            .variadic()
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `BETWEEN` and `NOT BETWEEN` SQL functions.
+#[derive(Debug)]
+pub struct Between {
+    /// Whether the result is negated (`NOT BETWEEN`).
+    negated: bool,
+}
+
+/// The `BETWEEN` SQL function.
+pub const BETWEEN: Between = Between { negated: false };
+/// The `NOT BETWEEN` SQL function.
+pub const NOT_BETWEEN: Between = Between { negated: true };
+
+impl Function for Between {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        if let [value, low, high] = &*args {
+            let ge_low = value.inner.sql_cmp(&low.inner).span_err(span)?;
+            let le_high = value.inner.sql_cmp(&high.inner).span_err(span)?;
+            let result = match (ge_low, le_high) {
+                (Some(Ordering::Less), _) | (_, Some(Ordering::Greater)) => Some(false),
+                (Some(_), Some(_)) => Some(true),
+                _ => None,
+            };
+            Ok(predicate_result(result, self.negated))
+        } else {
+            panic!("should have exactly 3 arguments");
+        }
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY, Types::ANY, Types::ANY], Types::NULLABLE_BOOL)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `LIKE` and `NOT LIKE` SQL functions.
+///
+/// The arguments are the input, the pattern and the optional escape character
+/// (defaults to a backslash).
+#[derive(Debug)]
+pub struct Like {
+    /// Whether the result is negated (`NOT LIKE`).
+    negated: bool,
+}
+
+/// The `LIKE` SQL function.
+pub const LIKE: Like = Like { negated: false };
+/// The `NOT LIKE` SQL function.
+pub const NOT_LIKE: Like = Like { negated: true };
+
+/// Converts a `LIKE` pattern into an anchored regex.
+fn like_to_regex(pattern: &str, escape: char) -> Result<Regex, Error> {
+    let mut regex = String::with_capacity(pattern.len() + 8);
+    regex.push_str("(?s)^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let literal = match c {
+            _ if c == escape => chars.next().ok_or_else(|| {
+                Error::InvalidArguments(format!("LIKE pattern '{}' ends with the escape character", pattern))
+            })?,
+            '%' => {
+                regex.push_str(".*");
+                continue;
+            }
+            '_' => {
+                regex.push('.');
+                continue;
+            }
+            _ => c,
+        };
+        regex.push_str(&regex::escape(literal.encode_utf8(&mut [0; 4])));
+    }
+    regex.push('$');
+    Ok(Regex::new(&regex)?)
+}
+
+impl Function for Like {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        if args.iter().any(|arg| arg.inner == Value::Null) {
+            return Ok(C::Constant(Value::Null));
+        }
+        let (input, pattern, escape) =
+            args_3::<ByteString, String, String>(span, args, None, None, Some("\\".to_owned()))?;
+        let mut escape_chars = escape.chars();
+        let escape = escape_chars
+            .next()
+            .filter(|_| escape_chars.next().is_none())
+            .ok_or_else(|| Error::InvalidArguments(format!("ESCAPE '{}' must be a single character", escape)))
+            .span_err(span)?;
+        let regex = like_to_regex(&pattern, escape).span_err(span)?;
+        Ok(predicate_result(Some(regex.is_match(input.as_bytes())), self.negated))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(
+            &[Types::NULLABLE_BYTES, Types::NULLABLE_BYTES, Types::NULLABLE_BYTES],
+            Types::NULLABLE_BOOL,
+        )
+        .optional_after(2)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The regex match `~` SQL function.
+#[derive(Debug)]
+pub struct Match;
+
+impl Function for Match {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        if args.iter().any(|arg| arg.inner == Value::Null) {
+            return Ok(C::Constant(Value::Null));
+        }
+        let (input, pattern) = args_2::<ByteString, String>(span, args, None, None)?;
+        let regex = Regex::new(&pattern).span_err(span)?;
+        Ok(C::Constant(regex.is_match(input.as_bytes()).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NULLABLE_BYTES, Types::NULLABLE_BYTES], Types::NULLABLE_BOOL)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -124,6 +310,10 @@ impl Function for Not {
         let inner = args_1::<Option<bool>>(span, args, None)?;
         Ok(C::Constant(inner.map(|b| !b).into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NULLABLE_BOOL], Types::NULLABLE_BOOL)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -137,6 +327,10 @@ impl Function for BitNot {
         let inner = args_1::<i128>(span, args, None)?;
         Ok(C::Constant((!inner).into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -168,6 +362,14 @@ impl Function for Logic {
         }
         Ok(C::Constant(result.into()))
     }
+
+    fn lazy(&self) -> Option<Lazy> {
+        Some(Lazy::Logic(self.identity))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NULLABLE_BOOL], Types::NULLABLE_BOOL).variadic()
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -185,6 +387,11 @@ pub enum Arith {
     FloatDiv,
 }
 
+/// The types accepted by addition and subtraction.
+const ADDABLE: Types = Types::NUMBER.or(Types::TIMESTAMP).or(Types::INTERVAL);
+/// The types accepted by multiplication and division.
+const SCALABLE: Types = Types::NUMBER.or(Types::INTERVAL);
+
 impl Function for Arith {
     fn compile(&self, _: &CompileContext, _: Span, args: Arguments) -> Result<C, S<Error>> {
         let func = match self {
@@ -203,6 +410,13 @@ impl Function for Arith {
         });
         Ok(C::Constant(result?.expect("at least 1 argument")))
     }
+
+    fn signature(&self) -> Signature {
+        match self {
+            Self::Add | Self::Sub => Signature::new(&[ADDABLE], ADDABLE).variadic(),
+            Self::Mul | Self::FloatDiv => Signature::new(&[SCALABLE], SCALABLE).variadic(),
+        }
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -231,6 +445,74 @@ impl Function for Bitwise {
         let result = iter_args::<i128>(args).try_fold(init, |a, b| b.map(|bb| func(a, bb)))?;
         Ok(C::Constant(result.into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER], Types::NUMBER).variadic()
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The bit-shift (`<<`, `>>`) SQL functions.
+#[derive(Debug)]
+pub enum Shift {
+    /// Shift left (`<<`)
+    Left,
+    /// Arithmetic shift right (`>>`)
+    Right,
+}
+
+/// Shifts an integer left by the given number of bits (right if negative).
+fn shift_left(value: i128, bits: i128) -> Result<i128, NumberError> {
+    if bits < 0 {
+        return shift_right(value, bits.checked_neg().unwrap_or(i128::MAX));
+    }
+    match u32::try_from(bits) {
+        Ok(b) if b < 128 => {
+            let result = value << b;
+            if result >> b == value {
+                Ok(result)
+            } else {
+                Err(NumberError::Overflow)
+            }
+        }
+        _ if value == 0 => Ok(0),
+        _ => Err(NumberError::Overflow),
+    }
+}
+
+/// Shifts an integer right by the given number of bits (left if negative),
+/// preserving the sign.
+fn shift_right(value: i128, bits: i128) -> Result<i128, NumberError> {
+    if bits < 0 {
+        return shift_left(value, bits.checked_neg().unwrap_or(i128::MAX));
+    }
+    Ok(value >> u32::try_from(bits).unwrap_or(127).min(127))
+}
+
+impl Function for Shift {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let mut it = iter_args::<Option<i128>>(args);
+        let mut result = it.next().expect("at least 1 argument")?;
+        for bits in it {
+            let bits = bits?;
+            result = match (result, bits) {
+                (Some(value), Some(bits)) => {
+                    let (result, op) = match self {
+                        Self::Left => (shift_left(value, bits), "<<"),
+                        Self::Right => (shift_right(value, bits), ">>"),
+                    };
+                    Some(result.map_err(|_| Error::IntegerOverflow(format!("{} {} {}", value, op, bits)).span(span))?)
+                }
+                _ => None,
+            };
+        }
+        Ok(C::Constant(result.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NULLABLE_NUMBER], Types::NULLABLE_NUMBER).variadic()
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -264,6 +546,10 @@ impl Function for Extremum {
         }
         Ok(C::Constant(res))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::ANY).optional_after(0).variadic()
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -274,14 +560,12 @@ pub struct Round;
 
 impl Function for Round {
     fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
-        let (value, digits) = args_2::<f64, i32>(span, args, None, Some(0))?;
-        let scale = 10.0_f64.powi(digits);
-        let result = if scale.is_finite() {
-            (value * scale).round() / scale
-        } else {
-            value
-        };
-        Ok(C::Constant(Value::from_finite_f64(result)))
+        let (value, digits) = args_2::<Number, i32>(span, args, None, Some(0))?;
+        Ok(C::Constant(value.round(digits).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER).optional_after(1)
     }
 }
 
@@ -296,6 +580,10 @@ impl Function for Div {
         let (n, d) = args_2::<Value, Value>(span, args, None, None)?;
         Ok(C::Constant(n.sql_div(&d).span_err(span)?))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 /// The `mod` SQL function.
@@ -307,6 +595,10 @@ impl Function for Mod {
         let (n, d) = args_2::<Value, Value>(span, args, None, None)?;
         Ok(C::Constant(n.sql_rem(&d).span_err(span)?))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -324,6 +616,54 @@ impl Function for Coalesce {
             .unwrap_or(Value::Null);
         Ok(C::Constant(res))
     }
+
+    fn lazy(&self) -> Option<Lazy> {
+        Some(Lazy::Coalesce)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::ANY).optional_after(0).variadic()
+    }
+}
+
+/// The `nullif` SQL function.
+#[derive(Debug)]
+pub struct NullIf;
+
+impl Function for NullIf {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (value, other) = args_2::<Value, S<Value>>(span, args, None, None)?;
+        let is_eq = value.sql_cmp(&other.inner).span_err(other.span)? == Some(Ordering::Equal);
+        Ok(C::Constant(if is_eq { Value::Null } else { value }))
+    }
+
+    fn lazy(&self) -> Option<Lazy> {
+        Some(Lazy::NullIf)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY, Types::ANY], Types::ANY)
+    }
+}
+
+/// The `if` SQL function.
+#[derive(Debug)]
+pub struct If;
+
+impl Function for If {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (cond, then, otherwise) = args_3::<S<Value>, Value, Value>(span, args, None, None, None)?;
+        let is_true = cond.inner.is_sql_true().span_err(cond.span)?;
+        Ok(C::Constant(if is_true { then } else { otherwise }))
+    }
+
+    fn lazy(&self) -> Option<Lazy> {
+        Some(Lazy::If)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NULLABLE_BOOL, Types::ANY, Types::ANY], Types::ANY)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -336,4 +676,222 @@ impl Function for Last {
     fn compile(&self, _: &CompileContext, _: Span, mut args: Arguments) -> Result<C, S<Error>> {
         Ok(C::Constant(args.pop().expect("at least one expression").inner))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ANY], Types::ANY).variadic()
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// Converts the result of a numerical computation into a value.
+fn number_result(
+    span: Span,
+    result: Result<Number, NumberError>,
+    expr: impl FnOnce() -> String,
+) -> Result<C, S<Error>> {
+    match result {
+        Ok(n) => Ok(C::Constant(n.into())),
+        Err(NumberError::NaN) => Err(Error::NotANumber(expr()).span(span)),
+        Err(NumberError::Overflow) => Err(Error::NumberOutOfRange(expr()).span(span)),
+    }
+}
+
+/// The integer-preserving numerical (`abs`, `floor`, `ceil`, `trunc`, `sign`)
+/// SQL functions.
+#[derive(Debug)]
+pub struct Integral {
+    /// The function to compute the result.
+    func: fn(Number) -> Number,
+}
+
+/// The `abs` SQL function.
+pub const ABS: Integral = Integral { func: Number::abs };
+/// The `floor` SQL function.
+pub const FLOOR: Integral = Integral { func: Number::floor };
+/// The `ceil` SQL function.
+pub const CEIL: Integral = Integral { func: Number::ceil };
+/// The `trunc` SQL function.
+pub const TRUNC: Integral = Integral { func: Number::trunc };
+/// The `sign` SQL function.
+pub const SIGN: Integral = Integral {
+    func: |n| Number::from(n.sql_sign() as i8),
+};
+
+impl Function for Integral {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let value = args_1::<Option<Number>>(span, args, None)?;
+        Ok(C::Constant(value.map(self.func).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NULLABLE_NUMBER], Types::NULLABLE_NUMBER)
+    }
+}
+
+/// The floating point math (`sqrt`, `exp`, `ln`, `sin`, etc.) SQL functions.
+#[derive(Debug)]
+pub struct FloatMath {
+    /// Name of the function.
+    name: &'static str,
+    /// The function to compute the result.
+    func: fn(f64) -> f64,
+}
+
+macro_rules! declare_float_math {
+    ($($(#[$meta:meta])* $const_name:ident = $name:literal => $func:expr;)+) => {
+        $(
+            $(#[$meta])*
+            pub const $const_name: FloatMath = FloatMath { name: $name, func: $func };
+        )+
+    };
+}
+
+declare_float_math! {
+    /// The `sqrt` SQL function.
+    SQRT = "sqrt" => f64::sqrt;
+    /// The `cbrt` SQL function.
+    CBRT = "cbrt" => f64::cbrt;
+    /// The `exp` SQL function.
+    EXP = "exp" => f64::exp;
+    /// The `ln` SQL function.
+    LN = "ln" => f64::ln;
+    /// The `log2` SQL function.
+    LOG2 = "log2" => f64::log2;
+    /// The `log10` SQL function.
+    LOG10 = "log10" => f64::log10;
+    /// The `sin` SQL function.
+    SIN = "sin" => f64::sin;
+    /// The `cos` SQL function.
+    COS = "cos" => f64::cos;
+    /// The `tan` SQL function.
+    TAN = "tan" => f64::tan;
+    /// The `asin` SQL function.
+    ASIN = "asin" => f64::asin;
+    /// The `acos` SQL function.
+    ACOS = "acos" => f64::acos;
+    /// The `atan` SQL function.
+    ATAN = "atan" => f64::atan;
+    /// The `degrees` SQL function.
+    DEGREES = "degrees" => f64::to_degrees;
+    /// The `radians` SQL function.
+    RADIANS = "radians" => f64::to_radians;
+}
+
+impl Function for FloatMath {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        match args_1::<Option<f64>>(span, args, None)? {
+            None => Ok(C::Constant(Value::Null)),
+            Some(v) => number_result(span, Number::try_from((self.func)(v)), || {
+                format!("{}({})", self.name, v)
+            }),
+        }
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NULLABLE_NUMBER], Types::NULLABLE_NUMBER)
+    }
+}
+
+/// The `atan2` SQL function.
+#[derive(Debug)]
+pub struct Atan2;
+
+impl Function for Atan2 {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        Ok(C::Constant(
+            match args_2::<Option<f64>, Option<f64>>(span, args, None, None)? {
+                (Some(y), Some(x)) => Value::from_finite_f64(y.atan2(x)),
+                _ => Value::Null,
+            },
+        ))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(
+            &[Types::NULLABLE_NUMBER, Types::NULLABLE_NUMBER],
+            Types::NULLABLE_NUMBER,
+        )
+    }
+}
+
+/// The `power` SQL function.
+#[derive(Debug)]
+pub struct Power;
+
+impl Function for Power {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        match args_2::<Option<Number>, Option<Number>>(span, args, None, None)? {
+            (Some(base), Some(exponent)) => {
+                number_result(span, base.pow(exponent), || format!("power({}, {})", base, exponent))
+            }
+            _ => Ok(C::Constant(Value::Null)),
+        }
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(
+            &[Types::NULLABLE_NUMBER, Types::NULLABLE_NUMBER],
+            Types::NULLABLE_NUMBER,
+        )
+    }
+}
+
+/// The `pi` SQL function.
+#[derive(Debug)]
+pub struct Pi;
+
+impl Function for Pi {
+    fn compile(&self, _: &CompileContext, _: Span, _: Arguments) -> Result<C, S<Error>> {
+        Ok(C::Constant(Value::from_finite_f64(std::f64::consts::PI)))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[], Types::NUMBER)
+    }
+}
+
+#[test]
+fn test_math_errors() {
+    let ctx = CompileContext::new(0);
+    let span = Span::default();
+    let compile = |function: &dyn Function, args: &[Value]| {
+        function
+            .compile(&ctx, span, args.iter().map(|v| v.clone().no_span()).collect())
+            .map_err(|e| e.inner.to_string())
+    };
+
+    assert_eq!(
+        compile(&SQRT, &[Value::from(-1)]).unwrap_err(),
+        "result of 'sqrt(-1)' is not a number"
+    );
+    assert_eq!(
+        compile(&Power, &[Value::from(-8), Value::from_finite_f64(0.5)]).unwrap_err(),
+        "result of 'power(-8, 0.5)' is not a number"
+    );
+    assert_eq!(
+        compile(&LN, &[Value::from(0)]).unwrap_err(),
+        "result of 'ln(0)' is out of range"
+    );
+    assert_eq!(
+        compile(&EXP, &[Value::from(1000)]).unwrap_err(),
+        "result of 'exp(1000)' is out of range"
+    );
+    assert_eq!(
+        compile(&Power, &[Value::from(10), Value::from(400)]).unwrap_err(),
+        "result of 'power(10, 400)' is out of range"
+    );
+
+    assert_eq!(
+        compile(&Shift::Left, &[Value::from(1), Value::from(127)]).unwrap_err(),
+        "integer '1 << 127' is too big"
+    );
+    assert_eq!(
+        compile(&Shift::Right, &[Value::from(1), Value::from(-127)]).unwrap_err(),
+        "integer '1 >> -127' is too big"
+    );
+    assert!(matches!(
+        compile(&Shift::Right, &[Value::from(-1), Value::from(-126)]),
+        Ok(C::Constant(Value::Number(n))) if n.is_identical(Number::from(-1_i128 << 126))
+    ));
 }
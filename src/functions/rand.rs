@@ -4,8 +4,9 @@ use super::{args_1, args_2, args_3, require, Arguments, Function};
 use crate::{
     error::Error,
     eval::{CompileContext, C},
-    number::Number,
+    number::{Number, MAX_DECIMAL_SCALE},
     span::{ResultExt, Span, SpanExt, S},
+    types::{Signature, Types},
     value::Value,
 };
 use std::{convert::TryFrom, sync::Arc};
@@ -39,10 +40,18 @@ macro_rules! impl_rand_range {
 
 impl Function for Range {
     impl_rand_range!("rand.range", <, new);
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 impl Function for RangeInclusive {
     impl_rand_range!("rand.range_inclusive", <=, new_inclusive);
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -67,10 +76,47 @@ macro_rules! impl_rand_uniform {
 
 impl Function for Uniform {
     impl_rand_uniform!(<, new);
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 impl Function for UniformInclusive {
     impl_rand_uniform!(<=, new_inclusive);
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `rand.decimal` SQL function.
+#[derive(Debug)]
+pub struct Decimal;
+
+impl Function for Decimal {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (lower, upper, scale) = args_3::<Number, Number, u8>(span, args, None, None, None)?;
+        require(span, scale <= MAX_DECIMAL_SCALE, || {
+            format!("scale ({}) must not exceed {}", scale, MAX_DECIMAL_SCALE)
+        })?;
+        let overflow = || Error::IntegerOverflow(format!("rand.decimal({}, {}, {})", lower, upper, scale)).span(span);
+        let lower_mantissa = lower.to_decimal(scale).map_err(|_| overflow())?;
+        let upper_mantissa = upper.to_decimal(scale).map_err(|_| overflow())?;
+        require(span, lower_mantissa <= upper_mantissa, || {
+            format!("assertion failed: {} <= {}", lower, upper)
+        })?;
+        Ok(C::RandDecimal(
+            rand_distr::Uniform::new_inclusive(lower_mantissa, upper_mantissa),
+            scale,
+        ))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -90,6 +136,10 @@ impl Function for Zipf {
             .span(span)
         })?))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -106,6 +156,10 @@ impl Function for LogNormal {
             |e| Error::InvalidArguments(format!("standard deviation ({}) {}", std_dev, e)).span(span),
         )?))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER, Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -121,6 +175,10 @@ impl Function for Bool {
             Error::InvalidArguments(format!("probability ({}) {}", p, e)).span(span)
         })?))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::NUMBER], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -145,24 +203,40 @@ impl Function for FiniteF32 {
     fn compile(&self, _: &CompileContext, _: Span, _: Arguments) -> Result<C, S<Error>> {
         Ok(C::RandFiniteF32(rand_distr::Uniform::new(0, 0xff00_0000)))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[], Types::NUMBER)
+    }
 }
 
 impl Function for FiniteF64 {
     fn compile(&self, _: &CompileContext, _: Span, _: Arguments) -> Result<C, S<Error>> {
         Ok(C::RandFiniteF64(rand_distr::Uniform::new(0, 0xffe0_0000_0000_0000)))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[], Types::NUMBER)
+    }
 }
 
 impl Function for U31Timestamp {
     fn compile(&self, _: &CompileContext, _: Span, _: Arguments) -> Result<C, S<Error>> {
         Ok(C::RandU31Timestamp(rand_distr::Uniform::new(1, 0x8000_0000)))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[], Types::TIMESTAMP)
+    }
 }
 
 impl Function for Uuid {
     fn compile(&self, _: &CompileContext, _: Span, _: Arguments) -> Result<C, S<Error>> {
         Ok(C::RandUuid)
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[], Types::BYTES)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -177,6 +251,10 @@ impl Function for Regex {
         let generator = compile_regex_generator(&regex, &flags, max_repeat).span_err(span)?;
         Ok(C::RandRegex(generator))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES, Types::NUMBER], Types::BYTES).optional_after(1)
+    }
 }
 
 fn compile_regex_generator(regex: &str, flags: &str, max_repeat: u32) -> Result<rand_regex::Regex, Error> {
@@ -210,4 +288,8 @@ impl Function for Shuffle {
         let array = args_1::<Arc<[Value]>>(span, args, None)?;
         Ok(C::RandShuffle(array))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ARRAY], Types::ARRAY)
+    }
 }
@@ -1,14 +1,42 @@
 //! String functions.
 
-use super::{args_1, args_3, args_4, Arguments, Function};
+use super::{args_1, args_2, args_3, args_4, require, Arguments, Function};
 use crate::{
     bytes::ByteString,
     error::Error,
     eval::{CompileContext, C},
-    span::{Span, SpanExt, S},
+    span::{ResultExt, Span, SpanExt, S},
+    types::{Signature, Types},
     value::Value,
 };
-use std::{convert::TryInto, isize, ops::Range};
+use memchr::{memchr, memmem};
+use regex::bytes::{Regex, RegexBuilder};
+use std::{
+    convert::{TryFrom, TryInto},
+    isize, iter,
+    ops::Range,
+    str::from_utf8,
+    sync::Arc,
+};
+
+//------------------------------------------------------------------------------
+
+/// The maximum length in bytes of a string produced by `repeat`, `lpad` and
+/// `rpad`.
+pub const MAX_STRING_LENGTH: usize = 1 << 26;
+
+/// Checks that a string of the given length in bytes does not exceed
+/// [`MAX_STRING_LENGTH`].
+fn check_string_length(length: u128) -> Result<(), Error> {
+    if length > MAX_STRING_LENGTH as u128 {
+        Err(Error::StringTooLong {
+            length,
+            max: MAX_STRING_LENGTH,
+        })
+    } else {
+        Ok(())
+    }
+}
 
 //------------------------------------------------------------------------------
 
@@ -50,6 +78,30 @@ impl Unit {
             Self::Characters => input.char_len(),
         }
     }
+
+    /// Computes the byte length of the first `count` units of the input.
+    fn prefix_len(self, input: &ByteString, count: usize) -> usize {
+        match self {
+            Self::Octets => count.min(input.len()),
+            Self::Characters => input.char_range(0..count).end,
+        }
+    }
+
+    /// Converts a byte index into the number of units before it.
+    fn index_of(self, input: &ByteString, byte_index: usize) -> usize {
+        match self {
+            Self::Octets => byte_index,
+            Self::Characters => input.char_index(byte_index),
+        }
+    }
+
+    /// Splits the input into a list of units.
+    fn split(self, input: &ByteString) -> Vec<&[u8]> {
+        match self {
+            Self::Octets => input.as_bytes().chunks(1).collect(),
+            Self::Characters => input.chars().collect(),
+        }
+    }
 }
 
 #[test]
@@ -116,6 +168,64 @@ fn test_parse_sql_range() {
     assert_eq!(Unit::Characters.parse_sql_range(&b, 4, 0), 4..4);
 }
 
+#[test]
+fn test_string_too_long() {
+    use smallvec::smallvec;
+
+    let ctx = CompileContext::new(0);
+    let span = Span::default();
+    let a = || Value::from("a".to_owned()).no_span();
+
+    let cases: Vec<(&dyn Function, Arguments)> = vec![
+        (&Repeat, smallvec![a(), Value::from_finite_f64(9e18).no_span()]),
+        (&Repeat, smallvec![a(), Value::from(MAX_STRING_LENGTH + 1).no_span()]),
+        (
+            &LPad(Unit::Characters),
+            smallvec![a(), Value::from(isize::MAX).no_span()],
+        ),
+        (
+            &RPad(Unit::Octets),
+            smallvec![a(), Value::from(MAX_STRING_LENGTH + 1).no_span()],
+        ),
+        (
+            &Format(Unit::Characters),
+            smallvec![
+                Value::from("%1000000000000d".to_owned()).no_span(),
+                Value::from(1).no_span()
+            ],
+        ),
+        (
+            &Format(Unit::Characters),
+            smallvec![
+                Value::from("%.999999999d".to_owned()).no_span(),
+                Value::from(1).no_span()
+            ],
+        ),
+        (
+            &Format(Unit::Octets),
+            smallvec![
+                Value::from("%99999999999999999999999999999999999999999s".to_owned()).no_span(),
+                a()
+            ],
+        ),
+    ];
+    for (function, args) in cases {
+        match function.compile(&ctx, span, args) {
+            Err(e) => assert!(matches!(
+                e.inner,
+                Error::StringTooLong {
+                    max: MAX_STRING_LENGTH,
+                    ..
+                }
+            )),
+            Ok(_) => panic!("{:?} should fail", function),
+        }
+    }
+
+    let args = smallvec![a(), Value::from(MAX_STRING_LENGTH).no_span()];
+    assert!(Repeat.compile(&ctx, span, args).is_ok());
+}
+
 //------------------------------------------------------------------------------
 
 /// The `substring` SQL function.
@@ -137,6 +247,10 @@ impl Function for Substring {
         }
         Ok(C::Constant(input.into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::NUMBER, Types::NULLABLE_NUMBER], Types::BYTES).optional_after(2)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -154,6 +268,10 @@ impl Function for CharLength {
         let input = args_1::<ByteString>(span, args, None)?;
         Ok(C::Constant(input.char_len().into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::NUMBER)
+    }
 }
 
 impl Function for OctetLength {
@@ -161,6 +279,10 @@ impl Function for OctetLength {
         let input = args_1::<ByteString>(span, args, None)?;
         Ok(C::Constant(input.len().into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::NUMBER)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -181,6 +303,14 @@ impl Function for Overlay {
         input.splice(range, placing);
         Ok(C::Constant(input.into()))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(
+            &[Types::BYTES, Types::BYTES, Types::NUMBER, Types::NULLABLE_NUMBER],
+            Types::BYTES,
+        )
+        .optional_after(3)
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -189,6 +319,13 @@ impl Function for Overlay {
 #[derive(Debug)]
 pub struct Concat;
 
+/// The types which can be concatenated, i.e. everything except arrays.
+const CONCATENABLE: Types = Types::NULLABLE_BYTES
+    .or(Types::NUMBER)
+    .or(Types::TIMESTAMP)
+    .or(Types::INTERVAL)
+    .or(Types::JSON);
+
 impl Function for Concat {
     fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
         match Value::sql_concat(args.iter().map(|arg| &arg.inner)) {
@@ -196,4 +333,693 @@ impl Function for Concat {
             Err(e) => Err(e.span(span)),
         }
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[CONCATENABLE], Types::NULLABLE_BYTES)
+            .optional_after(0)
+            .variadic()
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The case conversion (`upper`, `lower`, `initcap`) SQL functions.
+#[derive(Debug)]
+pub enum ChangeCase {
+    /// Converts all letters to upper case (`upper`).
+    Upper,
+    /// Converts all letters to lower case (`lower`).
+    Lower,
+    /// Converts the first letter of each word to upper case and the rest to
+    /// lower case (`initcap`).
+    InitCap,
+}
+
+impl Function for ChangeCase {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let input = args_1::<ByteString>(span, args, None)?;
+        Ok(C::Constant(
+            match self {
+                Self::Upper => input.to_uppercase(),
+                Self::Lower => input.to_lowercase(),
+                Self::InitCap => input.to_titlecase(),
+            }
+            .into(),
+        ))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::BYTES)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `trim` SQL function.
+#[derive(Debug)]
+pub struct Trim(
+    /// The string unit used by the function.
+    pub Unit,
+);
+
+/// The `ltrim` SQL function.
+#[derive(Debug)]
+pub struct LTrim(
+    /// The string unit used by the function.
+    pub Unit,
+);
+
+/// The `rtrim` SQL function.
+#[derive(Debug)]
+pub struct RTrim(
+    /// The string unit used by the function.
+    pub Unit,
+);
+
+/// Computes the total length of the initial units which are found in the set.
+fn trimmed_len<'a>(units: impl Iterator<Item = &'a [u8]>, set: &[&[u8]]) -> usize {
+    units.take_while(|u| set.contains(u)).map(<[u8]>::len).sum()
+}
+
+fn trim(unit: Unit, span: Span, args: Arguments, leading: bool, trailing: bool) -> Result<C, S<Error>> {
+    let (mut input, characters) =
+        args_2::<ByteString, ByteString>(span, args, None, Some(ByteString::from(" ".to_owned())))?;
+    let (init_len, tail_len) = {
+        let set = unit.split(&characters);
+        let units = unit.split(&input);
+        (
+            if leading {
+                trimmed_len(units.iter().copied(), &set)
+            } else {
+                0
+            },
+            if trailing {
+                trimmed_len(units.iter().rev().copied(), &set)
+            } else {
+                0
+            },
+        )
+    };
+    input.truncate(input.len() - tail_len);
+    input.drain_init(init_len);
+    Ok(C::Constant(input.into()))
+}
+
+impl Function for Trim {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        trim(self.0, span, args, true, true)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES], Types::BYTES).optional_after(1)
+    }
+}
+
+impl Function for LTrim {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        trim(self.0, span, args, true, false)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES], Types::BYTES).optional_after(1)
+    }
+}
+
+impl Function for RTrim {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        trim(self.0, span, args, false, true)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES], Types::BYTES).optional_after(1)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `lpad` SQL function.
+#[derive(Debug)]
+pub struct LPad(
+    /// The string unit used by the function.
+    pub Unit,
+);
+
+/// The `rpad` SQL function.
+#[derive(Debug)]
+pub struct RPad(
+    /// The string unit used by the function.
+    pub Unit,
+);
+
+fn pad(unit: Unit, span: Span, args: Arguments, is_left: bool) -> Result<C, S<Error>> {
+    let (mut input, length, fill) =
+        args_3::<ByteString, isize, ByteString>(span, args, None, None, Some(ByteString::from(" ".to_owned())))?;
+    let length = usize::try_from(length).unwrap_or(0);
+    let input_len = unit.length_of(&input);
+    if input_len >= length {
+        input.truncate(unit.prefix_len(&input, length));
+        return Ok(C::Constant(input.into()));
+    }
+
+    let fill = unit.split(&fill);
+    if let Some(max_unit_len) = fill.iter().map(|u| u.len()).max() {
+        check_string_length((length - input_len) as u128 * max_unit_len as u128 + input.len() as u128)
+            .span_err(span)?;
+    }
+
+    let mut padding = Vec::new();
+    for u in fill.iter().cycle().take(length - input_len) {
+        padding.extend_from_slice(u);
+    }
+    let mut padding = ByteString::from(padding);
+    Ok(C::Constant(
+        if is_left {
+            padding.extend_byte_string(&input);
+            padding
+        } else {
+            input.extend_byte_string(&padding);
+            input
+        }
+        .into(),
+    ))
+}
+
+impl Function for LPad {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        pad(self.0, span, args, true)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::NUMBER, Types::BYTES], Types::BYTES).optional_after(2)
+    }
+}
+
+impl Function for RPad {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        pad(self.0, span, args, false)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::NUMBER, Types::BYTES], Types::BYTES).optional_after(2)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `repeat` SQL function.
+#[derive(Debug)]
+pub struct Repeat;
+
+impl Function for Repeat {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, count) = args_2::<ByteString, isize>(span, args, None, None)?;
+        let count = usize::try_from(count).unwrap_or(0);
+        check_string_length(input.len() as u128 * count as u128).span_err(span)?;
+        Ok(C::Constant(input.repeat(count).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::NUMBER], Types::BYTES)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `replace` SQL function.
+#[derive(Debug)]
+pub struct Replace;
+
+impl Function for Replace {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, from, to) = args_3::<ByteString, ByteString, ByteString>(span, args, None, None, None)?;
+        Ok(C::Constant(input.replace(from.as_bytes(), to.as_bytes()).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES, Types::BYTES], Types::BYTES)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `reverse` SQL function.
+#[derive(Debug)]
+pub struct Reverse(
+    /// The string unit used by the function.
+    pub Unit,
+);
+
+impl Function for Reverse {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let input = args_1::<ByteString>(span, args, None)?;
+        let mut result = Vec::with_capacity(input.len());
+        for u in self.0.split(&input).into_iter().rev() {
+            result.extend_from_slice(u);
+        }
+        Ok(C::Constant(result.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::BYTES)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// The `position` SQL function.
+#[derive(Debug)]
+pub struct Position(
+    /// The string unit used by the function.
+    pub Unit,
+);
+
+impl Function for Position {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (needle, haystack) = args_2::<ByteString, ByteString>(span, args, None, None)?;
+        let position = haystack
+            .find(needle.as_bytes())
+            .map_or(0, |i| self.0.index_of(&haystack, i) + 1);
+        Ok(C::Constant(position.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES], Types::NUMBER)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// Splits the input by a non-empty delimiter.
+fn split_by<'a>(input: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut result = Vec::new();
+    let mut last = 0;
+    for pos in memmem::find_iter(input, delimiter) {
+        result.push(&input[last..pos]);
+        last = pos + delimiter.len();
+    }
+    result.push(&input[last..]);
+    result
+}
+
+/// The `split_part` SQL function.
+#[derive(Debug)]
+pub struct SplitPart;
+
+impl Function for SplitPart {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, delimiter, n) = args_3::<ByteString, ByteString, isize>(span, args, None, None, None)?;
+        require(span, n != 0, || "field position must not be zero".to_owned())?;
+        let fields = if delimiter.is_empty() {
+            vec![input.as_bytes()]
+        } else {
+            split_by(input.as_bytes(), delimiter.as_bytes())
+        };
+        let index = if n > 0 {
+            Some(n.unsigned_abs() - 1)
+        } else {
+            fields.len().checked_sub(n.unsigned_abs())
+        };
+        let field = index.and_then(|i| fields.get(i)).map_or(Vec::new(), |f| f.to_vec());
+        Ok(C::Constant(field.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES, Types::NUMBER], Types::BYTES)
+    }
+}
+
+/// The `string_to_array` SQL function.
+#[derive(Debug)]
+pub struct StringToArray(
+    /// The string unit used by the function, when splitting without a delimiter.
+    pub Unit,
+);
+
+impl Function for StringToArray {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, delimiter, null_string) =
+            args_3::<ByteString, S<Value>, S<Value>>(span, args, None, None, Some(Value::Null.span(span)))?;
+        let null_string = match null_string.inner {
+            Value::Null => None,
+            v => Some(ByteString::try_from(v).span_err(null_string.span)?),
+        };
+        let fields = match delimiter.inner {
+            _ if input.is_empty() => Vec::new(),
+            Value::Null => self.0.split(&input),
+            v => {
+                let delimiter = ByteString::try_from(v).span_err(delimiter.span)?;
+                if delimiter.is_empty() {
+                    vec![input.as_bytes()]
+                } else {
+                    split_by(input.as_bytes(), delimiter.as_bytes())
+                }
+            }
+        };
+        let array = fields
+            .into_iter()
+            .map(|field| {
+                if null_string.as_ref().map_or(false, |n| n.as_bytes() == field) {
+                    Value::Null
+                } else {
+                    field.to_vec().into()
+                }
+            })
+            .collect();
+        Ok(C::Constant(Value::Array(array)))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::ANY, Types::ANY], Types::ARRAY).optional_after(2)
+    }
+}
+
+/// The `array_to_string` SQL function.
+#[derive(Debug)]
+pub struct ArrayToString;
+
+/// Joins all elements of a (possibly nested) array into a string.
+fn join_array(
+    result: &mut ByteString,
+    array: &[Value],
+    delimiter: &ByteString,
+    null_string: Option<&ByteString>,
+    is_first: &mut bool,
+) -> Result<(), Error> {
+    for item in array {
+        let item = match item {
+            Value::Array(inner) => {
+                join_array(result, inner, delimiter, null_string, is_first)?;
+                continue;
+            }
+            Value::Null => match null_string {
+                Some(s) => s.clone(),
+                None => continue,
+            },
+            v => match Value::sql_concat(iter::once(v))? {
+                Value::Bytes(b) => b,
+                _ => continue,
+            },
+        };
+        if !*is_first {
+            result.extend_byte_string(delimiter);
+        }
+        *is_first = false;
+        result.extend_byte_string(&item);
+    }
+    Ok(())
+}
+
+impl Function for ArrayToString {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (array, delimiter, null_string) =
+            args_3::<Arc<[Value]>, ByteString, S<Value>>(span, args, None, None, Some(Value::Null.span(span)))?;
+        let null_string = match null_string.inner {
+            Value::Null => None,
+            v => Some(ByteString::try_from(v).span_err(null_string.span)?),
+        };
+        let mut result = ByteString::default();
+        join_array(&mut result, &array, &delimiter, null_string.as_ref(), &mut true).span_err(span)?;
+        Ok(C::Constant(result.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::ARRAY, Types::BYTES, Types::ANY], Types::BYTES).optional_after(2)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// Compiles a regex for pattern matching.
+fn compile_regex(pattern: &str, flags: &str) -> Result<Regex, Error> {
+    let mut builder = RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'o' => builder.octal(true),
+            'a' => builder.unicode(false),
+            'u' => builder.unicode(true),
+            'x' => builder.ignore_whitespace(true),
+            'i' => builder.case_insensitive(true),
+            'm' => builder.multi_line(true),
+            's' => builder.dot_matches_new_line(true),
+            'U' => builder.swap_greed(true),
+            _ => return Err(Error::UnknownRegexFlag(flag)),
+        };
+    }
+    Ok(builder.build()?)
+}
+
+/// The `regexp_like` SQL function.
+#[derive(Debug)]
+pub struct RegexpLike;
+
+impl Function for RegexpLike {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, pattern, flags) =
+            args_3::<ByteString, String, String>(span, args, None, None, Some(String::new()))?;
+        let regex = compile_regex(&pattern, &flags).span_err(span)?;
+        Ok(C::Constant(regex.is_match(input.as_bytes()).into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES, Types::BYTES], Types::NUMBER).optional_after(2)
+    }
+}
+
+/// The `regexp_replace` SQL function.
+#[derive(Debug)]
+pub struct RegexpReplace;
+
+impl Function for RegexpReplace {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let (input, pattern, replacement, mut flags) =
+            args_4::<ByteString, String, ByteString, String>(span, args, None, None, None, Some(String::new()))?;
+        let is_global = flags.contains('g');
+        if is_global {
+            flags.retain(|c| c != 'g');
+        }
+        let regex = compile_regex(&pattern, &flags).span_err(span)?;
+        let result = if is_global {
+            regex.replace_all(input.as_bytes(), replacement.as_bytes())
+        } else {
+            regex.replace(input.as_bytes(), replacement.as_bytes())
+        };
+        Ok(C::Constant(result.into_owned().into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::BYTES, Types::BYTES, Types::BYTES], Types::BYTES).optional_after(3)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// A parsed printf-style conversion specification,
+/// `%[position$][flags][width][.precision]conversion`.
+#[derive(Default, Debug)]
+struct FormatSpec {
+    /// The 1-based position of the argument to format.
+    position: Option<usize>,
+    /// Whether to align the result to the left (`-`).
+    left_align: bool,
+    /// Whether to pad numbers with zeros instead of spaces (`0`).
+    zero_pad: bool,
+    /// The sign to show before non-negative numbers (`+` or ` `).
+    positive_sign: &'static str,
+    /// Minimum width of the result, in the string unit.
+    width: usize,
+    /// The precision of floating point numbers, the minimum number of digits
+    /// of integers, or the maximum length of strings.
+    precision: Option<usize>,
+    /// The conversion character.
+    conversion: u8,
+}
+
+impl FormatSpec {
+    /// Parses a conversion specification after the `%`. Returns the spec and
+    /// the number of bytes consumed.
+    fn parse(input: &[u8]) -> Result<(Self, usize), Error> {
+        let incomplete = || Error::InvalidArguments("incomplete format specification".to_owned());
+        let read_number = |i: &mut usize| -> Option<u128> {
+            let start = *i;
+            while input.get(*i).map_or(false, u8::is_ascii_digit) {
+                *i += 1;
+            }
+            if start == *i {
+                return None;
+            }
+            // the digits can only fail to parse by overflowing, and such a number is rejected by
+            // `check_string_length` anyway.
+            Some(from_utf8(&input[start..*i]).ok()?.parse().unwrap_or(u128::MAX))
+        };
+        // the width and precision are bounded like other string lengths, so that
+        // `%1000000000000d` cannot allocate an enormous padding.
+        let read_length = |i: &mut usize| -> Result<usize, Error> {
+            let length = read_number(i).unwrap_or(0);
+            check_string_length(length)?;
+            Ok(usize::try_from(length).expect("checked against MAX_STRING_LENGTH"))
+        };
+
+        let mut spec = Self::default();
+        let mut i = 0;
+        if let Some(position) = read_number(&mut i) {
+            if input.get(i) == Some(&b'$') && position > 0 {
+                spec.position = Some(usize::try_from(position).unwrap_or(usize::MAX));
+                i += 1;
+            } else {
+                i = 0;
+            }
+        } else {
+            i = 0;
+        }
+
+        loop {
+            match input.get(i).ok_or_else(incomplete)? {
+                b'-' => spec.left_align = true,
+                b'0' => spec.zero_pad = true,
+                b'+' => spec.positive_sign = "+",
+                b' ' if spec.positive_sign.is_empty() => spec.positive_sign = " ",
+                b' ' => {}
+                _ => break,
+            }
+            i += 1;
+        }
+
+        if input[i].is_ascii_digit() {
+            spec.width = read_length(&mut i)?;
+        }
+        if input.get(i) == Some(&b'.') {
+            i += 1;
+            spec.precision = Some(read_length(&mut i)?);
+        }
+
+        spec.conversion = *input.get(i).ok_or_else(incomplete)?;
+        Ok((spec, i + 1))
+    }
+
+    /// Writes the `body` into `output`, padded to the required width.
+    fn write_padded(&self, output: &mut Vec<u8>, sign: &str, body: &[u8], body_len: usize) {
+        let padding = self.width.saturating_sub(sign.len() + body_len);
+        if self.left_align {
+            output.extend_from_slice(sign.as_bytes());
+            output.extend_from_slice(body);
+            output.resize(output.len() + padding, b' ');
+        } else if self.zero_pad && self.conversion != b's' && self.conversion != b'L' {
+            output.extend_from_slice(sign.as_bytes());
+            output.resize(output.len() + padding, b'0');
+            output.extend_from_slice(body);
+        } else {
+            output.resize(output.len() + padding, b' ');
+            output.extend_from_slice(sign.as_bytes());
+            output.extend_from_slice(body);
+        }
+    }
+
+    /// Formats a value according to this specification.
+    fn write(&self, output: &mut Vec<u8>, unit: Unit, value: &Value) -> Result<(), Error> {
+        match (self.conversion, value) {
+            (b's', Value::Null) => self.write_string(output, unit, ByteString::default()),
+            (b's', v) => match Value::sql_concat(iter::once(v))? {
+                Value::Bytes(b) => self.write_string(output, unit, b),
+                _ => unreachable!(),
+            },
+            (b'L', v) => self.write_string(output, unit, v.to_string().into()),
+            (_, Value::Null) => self.write_padded(output, "", b"", 0),
+            (b'd' | b'i' | b'x' | b'X' | b'o' | b'b', v) => {
+                let n = i128::try_from(v.clone())?;
+                let sign = if n < 0 { "-" } else { self.positive_sign };
+                let n = n.unsigned_abs();
+                let mut digits = match self.conversion {
+                    b'x' => format!("{:x}", n),
+                    b'X' => format!("{:X}", n),
+                    b'o' => format!("{:o}", n),
+                    b'b' => format!("{:b}", n),
+                    _ => n.to_string(),
+                };
+                if let Some(precision) = self.precision {
+                    if digits.len() < precision {
+                        digits.insert_str(0, &"0".repeat(precision - digits.len()));
+                    }
+                }
+                self.write_padded(output, sign, digits.as_bytes(), digits.len());
+            }
+            (b'f' | b'F' | b'e' | b'E', v) => {
+                let n = f64::try_from(v.clone())?;
+                let sign = if n.is_sign_negative() { "-" } else { self.positive_sign };
+                let n = n.abs();
+                let precision = self.precision.unwrap_or(6);
+                let digits = match self.conversion {
+                    b'e' | b'E' if n.is_finite() => {
+                        let s = format!("{:.*e}", precision, n);
+                        let (mantissa, exponent) = s.split_at(s.find('e').unwrap());
+                        let exponent = exponent[1..].parse::<i32>().unwrap();
+                        let e = if self.conversion == b'e' { 'e' } else { 'E' };
+                        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+                        format!("{}{}{}{:02}", mantissa, e, exponent_sign, exponent.abs())
+                    }
+                    _ => format!("{:.*}", precision, n),
+                };
+                self.write_padded(output, sign, digits.as_bytes(), digits.len());
+            }
+            (c, _) => {
+                return Err(Error::InvalidArguments(format!(
+                    "unknown format conversion '{}'",
+                    char::from(c).escape_default()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Formats a string according to this specification.
+    fn write_string(&self, output: &mut Vec<u8>, unit: Unit, mut s: ByteString) {
+        if let Some(precision) = self.precision {
+            s.truncate(unit.prefix_len(&s, precision));
+        }
+        let len = unit.length_of(&s);
+        self.write_padded(output, "", s.as_bytes(), len);
+    }
+}
+
+/// The `format` SQL function.
+#[derive(Debug)]
+pub struct Format(
+    /// The string unit used by the function when computing widths.
+    pub Unit,
+);
+
+impl Function for Format {
+    fn compile(&self, _: &CompileContext, span: Span, args: Arguments) -> Result<C, S<Error>> {
+        let mut args = args.into_iter();
+        let format = args.next().ok_or_else(|| Error::NotEnoughArguments.span(span))?;
+        let format = ByteString::try_from(format.inner).span_err(format.span)?;
+        let values = args.collect::<Vec<_>>();
+
+        let input = format.as_bytes();
+        let mut output = Vec::with_capacity(input.len());
+        let mut next_index = 0;
+        let mut i = 0;
+        while let Some(offset) = memchr(b'%', &input[i..]) {
+            output.extend_from_slice(&input[i..(i + offset)]);
+            i += offset + 1;
+            let (spec, consumed) = FormatSpec::parse(&input[i..]).span_err(span)?;
+            i += consumed;
+            if spec.conversion == b'%' {
+                output.push(b'%');
+                continue;
+            }
+            let index = spec.position.map_or(next_index, |p| p - 1);
+            next_index = index + 1;
+            let value = values.get(index).ok_or_else(|| Error::NotEnoughArguments.span(span))?;
+            spec.write(&mut output, self.0, &value.inner).span_err(value.span)?;
+        }
+        output.extend_from_slice(&input[i..]);
+
+        Ok(C::Constant(output.into()))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES, Types::ANY], Types::BYTES)
+            .optional_after(1)
+            .variadic()
+    }
 }
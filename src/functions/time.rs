@@ -5,6 +5,7 @@ use crate::{
     error::Error,
     eval::{CompileContext, C},
     span::{ResultExt, Span, S},
+    types::{Signature, Types},
     value::{Value, TIMESTAMP_FORMAT},
 };
 
@@ -24,6 +25,10 @@ impl Function for Timestamp {
             .naive_utc();
         Ok(C::Constant(Value::Timestamp(timestamp, tz)))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::TIMESTAMP)
+    }
 }
 
 /// The `timestamp with time zone` SQL function
@@ -47,4 +52,8 @@ impl Function for TimestampWithTimeZone {
             .naive_utc();
         Ok(C::Constant(Value::Timestamp(timestamp, tz)))
     }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[Types::BYTES], Types::TIMESTAMP)
+    }
 }
@@ -0,0 +1,755 @@
+//! High-level API for generating data from a template without the CLI.
+
+use crate::{
+    de::from_row,
+    error::Error,
+    eval::{CompileContext, Row, Schema, State, Table},
+    format::{Format, Options, SqlFormat},
+    parser::{QName, Template},
+    rng::{RngName, Seed},
+    span::{Registry, ResultExt, SpanExt, S},
+    value::Value,
+    writer::{self, Writer},
+};
+use chrono::{NaiveDateTime, Utc};
+use futures::stream::Stream;
+use hyper::body::Bytes;
+use rand::{rngs::OsRng, Rng};
+use rand_hc::Hc128Rng;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Write},
+    marker::PhantomData,
+    mem,
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+/// Compiles the tables and the global expressions of a parsed template.
+pub(crate) fn compile_template(
+    template: Template,
+    ctx: &CompileContext,
+) -> Result<(Vec<Table>, Option<Row>), S<Error>> {
+    let tables = template
+        .tables
+        .into_iter()
+        .map(|t| ctx.compile_table(t))
+        .collect::<Result<Vec<_>, _>>()?;
+    let global_row = if template.global_exprs.is_empty() {
+        None
+    } else {
+        Some(ctx.compile_row(template.global_exprs)?)
+    };
+    Ok((tables, global_row))
+}
+
+/// Evaluates the global expressions, if any, using an RNG seeded from `seeding_rng`.
+///
+/// The RNG of the global expressions and then those of every file are all seeded from the same
+/// `seeding_rng` in this order. Both the CLI and [`Generator`] go through this function, so they
+/// produce the same data given the same seed.
+pub(crate) fn eval_global_row(
+    global_row: Option<Row>,
+    ctx: CompileContext,
+    rng: RngName,
+    seeding_rng: &mut Hc128Rng,
+) -> Result<CompileContext, S<Error>> {
+    Ok(if let Some(row) = global_row {
+        let mut state = State::new(0, rng.create(seeding_rng), ctx);
+        row.eval(&mut state)?;
+        state.into_compile_context()
+    } else {
+        ctx
+    })
+}
+
+/// Builder of a [`Generator`].
+#[derive(Debug, Clone)]
+pub struct GeneratorBuilder {
+    template: String,
+    seed: Option<Seed>,
+    rng: RngName,
+    time_zone: String,
+    zoneinfo: PathBuf,
+    now: Option<NaiveDateTime>,
+    qualified: bool,
+    total_count: u64,
+    rows_count: u32,
+}
+
+impl GeneratorBuilder {
+    /// Sets the seed. If not specified, the seed will be obtained from the system entropy.
+    #[must_use]
+    pub fn seed(mut self, seed: Seed) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the random number generator. The default is HC-128.
+    #[must_use]
+    pub fn rng(mut self, rng: RngName) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Sets the time zone used to parse and format timestamps. The default is UTC.
+    #[must_use]
+    pub fn time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.time_zone = time_zone.into();
+        self
+    }
+
+    /// Sets the path to the tz database. The default is `/usr/share/zoneinfo`.
+    #[must_use]
+    pub fn zoneinfo(mut self, zoneinfo: impl Into<PathBuf>) -> Self {
+        self.zoneinfo = zoneinfo.into();
+        self
+    }
+
+    /// Sets the timestamp reported by `current_timestamp`, in UTC. The default is the time when
+    /// the generator is built.
+    #[must_use]
+    pub fn now(mut self, now: NaiveDateTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Sets whether [`Generator::write_to()`] uses the qualified table names.
+    #[must_use]
+    pub fn qualified(mut self, qualified: bool) -> Self {
+        self.qualified = qualified;
+        self
+    }
+
+    /// Sets the number of rows written by [`Generator::write_to()`]. The default is 1.
+    #[must_use]
+    pub fn total_count(mut self, total_count: u64) -> Self {
+        self.total_count = total_count;
+        self
+    }
+
+    /// Sets the maximum number of rows per INSERT statement written by
+    /// [`Generator::write_to()`]. The default is 1.
+    #[must_use]
+    pub fn rows_count(mut self, rows_count: u32) -> Self {
+        self.rows_count = rows_count.max(1);
+        self
+    }
+
+    /// Parses and compiles the template, and evaluates its global expressions.
+    pub fn build(self, span_registry: &mut Registry) -> Result<Generator, S<Error>> {
+        let template = Template::parse(&self.template, None, &[], None, span_registry)?;
+        let mut ctx = CompileContext::new(template.variables_count);
+        ctx.zoneinfo = self.zoneinfo;
+        ctx.time_zone = ctx.parse_time_zone(&self.time_zone).no_span_err()?;
+        ctx.current_timestamp = self.now.unwrap_or_else(|| Utc::now().naive_utc());
+        let (tables, global_row) = compile_template(template, &ctx)?;
+
+        let mut seeding_rng = self.seed.unwrap_or_else(|| OsRng.gen()).make_rng();
+        let ctx = eval_global_row(global_row, ctx, self.rng, &mut seeding_rng)?;
+        // same as the first data file generated by the CLI.
+        let state = State::new(1, self.rng.create(&mut seeding_rng), ctx);
+
+        Ok(Generator {
+            tables,
+            state,
+            qualified: self.qualified,
+            total_count: self.total_count,
+            rows_count: self.rows_count,
+        })
+    }
+}
+
+/// Generates data from a template, in the same way as the first file generated by the CLI.
+///
+/// ```
+/// use dbgen::{format::{Options, SqlFormat}, generator::Generator, span::Registry};
+///
+/// let mut registry = Registry::default();
+/// let generator = Generator::builder("CREATE TABLE t (a INT {{ rownum * 2 }});")
+///     .total_count(3)
+///     .rows_count(2)
+///     .build(&mut registry)
+///     .unwrap();
+///
+/// let rows = generator.rows("t").take(2).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(rows, vec![vec![2.into()], vec![4.into()]]);
+///
+/// let mut output = Vec::new();
+/// generator.write_to(&mut output, &SqlFormat(Options::default())).unwrap();
+/// assert_eq!(output, b"INSERT INTO t VALUES\n(2),\n(4);\nINSERT INTO t VALUES\n(6);\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Generator {
+    tables: Vec<Table>,
+    state: State,
+    qualified: bool,
+    total_count: u64,
+    rows_count: u32,
+}
+
+impl Generator {
+    /// Creates a builder of a generator from the content of a template.
+    pub fn builder(template: impl Into<String>) -> GeneratorBuilder {
+        GeneratorBuilder {
+            template: template.into(),
+            seed: None,
+            rng: RngName::Hc128,
+            time_zone: "UTC".to_owned(),
+            zoneinfo: PathBuf::from("/usr/share/zoneinfo"),
+            now: None,
+            qualified: false,
+            total_count: 1,
+            rows_count: 1,
+        }
+    }
+
+    /// Returns an endless iterator of the rows of a table.
+    ///
+    /// All tables of the template are generated together, so the rows are the same as those
+    /// written by [`Generator::write_to()`] for this table. Rows dropped by `WHERE` are skipped.
+    /// If the table does not exist, or no rows of the table are generated after many attempts
+    /// (e.g. `WHERE` rejects every row), the iterator yields a single error.
+    pub fn rows(&self, table: &str) -> Rows {
+        let index = self.table_index(table);
+        let env = writer::Env::new(self.tables.clone(), self.state.clone(), false, |_| {
+            Ok(RowCollector::default())
+        })
+        .expect("creating a row collector never fails");
+        Rows {
+            table: index.map(|index| (index, env)),
+            error: index
+                .is_none()
+                .then(|| Error::UnknownTable { name: table.to_owned() }.no_span()),
+            format: SqlFormat(Options::default()),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Returns an endless iterator of the rows of a table, each deserialized into `T`.
+    ///
+    /// The rows are the same as those returned by [`Generator::rows()`]. See
+    /// [`de::from_row()`](crate::de::from_row) for how the values are converted.
+    ///
+    /// ```
+    /// use dbgen::{generator::Generator, span::Registry};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// struct User {
+    ///     id: u32,
+    ///     name: String,
+    ///     score: Option<f64>,
+    /// }
+    ///
+    /// let mut registry = Registry::default();
+    /// let generator = Generator::builder(
+    ///     "CREATE TABLE users (
+    ///         id INT {{ rownum }},
+    ///         name TEXT {{ 'user' || rownum }},
+    ///         score DOUBLE {{ CASE WHEN rownum = 1 THEN NULL ELSE rownum * 1.5 END }}
+    ///     );",
+    /// )
+    /// .build(&mut registry)
+    /// .unwrap();
+    ///
+    /// let users = generator
+    ///     .deserialize_rows::<User>("users")
+    ///     .take(2)
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     users,
+    ///     vec![
+    ///         User { id: 1, name: "user1".to_owned(), score: None },
+    ///         User { id: 2, name: "user2".to_owned(), score: Some(3.0) },
+    ///     ]
+    /// );
+    /// ```
+    pub fn deserialize_rows<T: DeserializeOwned>(&self, table: &str) -> DeserializeRows<T> {
+        let column_names = self
+            .schema(table)
+            .map_or_else(Vec::new, |schema| schema.unescaped_column_names().collect());
+        DeserializeRows {
+            rows: self.rows(table),
+            column_names,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the schema of a table, or `None` if the table does not exist.
+    pub fn schema(&self, table: &str) -> Option<Schema> {
+        let index = self.table_index(table)?;
+        Some(self.tables[index].schema(self.qualified))
+    }
+
+    /// Finds the index of a table by name.
+    fn table_index(&self, table: &str) -> Option<usize> {
+        let name = QName::parse(table).ok()?;
+        self.tables
+            .iter()
+            .position(|t| t.name.unique_name() == name.unique_name())
+    }
+
+    /// Writes the data of every table into the output.
+    ///
+    /// The INSERT statements of all tables are written to the same output one after another.
+    pub fn write_to(&self, mut output: impl Write, format: &dyn Format) -> Result<(), S<Error>> {
+        let mut env = self.buffer_env(format)?;
+        let mut remaining = self.total_count;
+        while write_step(&mut env, format, &mut output, &mut remaining, self.rows_count)? {}
+        output.flush().map_err(|e| Error::WriteOutput(e).no_span())
+    }
+
+    /// Returns a stream of the same data written by [`Generator::write_to()`].
+    ///
+    /// The data are generated only when the stream is polled, one INSERT statement of every table
+    /// at a time, so a slow consumer also slows down the generation. Generation runs inside
+    /// `poll_next()`, so consider a small [`rows_count`](GeneratorBuilder::rows_count) or a
+    /// blocking thread when the stream is driven by an async runtime.
+    ///
+    /// After an error is returned, the stream ends.
+    ///
+    /// ```
+    /// use dbgen::{format::{Options, SqlFormat}, generator::Generator, span::Registry};
+    /// use futures::{executor::block_on_stream, StreamExt};
+    ///
+    /// let mut registry = Registry::default();
+    /// let generator = Generator::builder("CREATE TABLE t (a INT {{ rownum }});")
+    ///     .total_count(3)
+    ///     .build(&mut registry)
+    ///     .unwrap();
+    ///
+    /// let stream = generator.stream(Box::new(SqlFormat(Options::default())));
+    /// let chunks = block_on_stream(stream).collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(chunks.concat(), b"INSERT INTO t VALUES\n(1);\nINSERT INTO t VALUES\n(2);\nINSERT INTO t VALUES\n(3);\n");
+    /// ```
+    pub fn stream(&self, format: Box<dyn Format + Send>) -> DataStream {
+        let sink = StreamSink::default();
+        let (env, error) = match self.buffer_env(&*format) {
+            Ok(env) => (Some(env), None),
+            Err(e) => (None, Some(e)),
+        };
+        DataStream {
+            env,
+            error,
+            format,
+            encoder: Some(Box::new(sink.clone())),
+            sink,
+            remaining: self.total_count,
+            rows_count: self.rows_count,
+        }
+    }
+
+    /// Creates the environment which writes every table into its own memory buffer.
+    fn buffer_env(&self, format: &dyn Format) -> Result<writer::Env<BufferWriter>, S<Error>> {
+        writer::Env::new(self.tables.clone(), self.state.clone(), self.qualified, |table| {
+            let mut w = BufferWriter::default();
+            w.write_file_header(format, &table.schema(self.qualified))?;
+            Ok(w)
+        })
+    }
+}
+
+/// Generates the next INSERT statement of every table, and then moves the content buffered for
+/// every table into the output. Returns `false` if all rows have already been generated.
+fn write_step(
+    env: &mut writer::Env<BufferWriter>,
+    format: &dyn Format,
+    output: &mut dyn Write,
+    remaining: &mut u64,
+    rows_count: u32,
+) -> Result<bool, S<Error>> {
+    let has_rows = *remaining > 0;
+    if has_rows {
+        let rows_count = (*remaining).min(rows_count.into());
+        for _ in 0..rows_count {
+            env.write_row(format)?;
+        }
+        env.write_trailer(format)?;
+        *remaining -= rows_count;
+    }
+    for (_, w) in env.tables() {
+        output.write_all(&w.0).map_err(|e| Error::WriteOutput(e).no_span())?;
+        w.0.clear();
+    }
+    Ok(has_rows)
+}
+
+/// Stream of the generated data, created by [`Generator::stream()`].
+pub struct DataStream {
+    /// The environment generating all tables. This is `None` after the stream ended.
+    env: Option<writer::Env<BufferWriter>>,
+    /// The error to be returned once by the stream.
+    error: Option<S<Error>>,
+    format: Box<dyn Format + Send>,
+    /// The writer of the generated data, which writes into `sink` directly or through an encoder.
+    /// This is dropped after all data are generated, to let the encoder finish.
+    encoder: Option<Box<dyn Write + Send>>,
+    sink: StreamSink,
+    remaining: u64,
+    rows_count: u32,
+}
+
+impl fmt::Debug for DataStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataStream")
+            .field("env", &self.env)
+            .field("error", &self.error)
+            .field("remaining", &self.remaining)
+            .field("rows_count", &self.rows_count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DataStream {
+    /// Passes the generated data through an encoder before they are yielded, e.g. for
+    /// compression.
+    ///
+    /// The `create` function receives the sink which the encoder should write into. The encoder
+    /// is dropped after all data are written, so it should finish its output when dropped, like
+    /// `flate2::write::GzEncoder` does.
+    ///
+    /// This must be called before the stream is polled.
+    pub fn encoder<F>(mut self, create: F) -> Result<Self, S<Error>>
+    where
+        F: FnOnce(StreamSink) -> io::Result<Box<dyn Write + Send>>,
+    {
+        let encoder = create(self.sink.clone()).map_err(|e| Error::WriteOutput(e).no_span())?;
+        self.encoder = Some(encoder);
+        Ok(self)
+    }
+
+    /// Generates data until some output is available.
+    fn next_chunk(&mut self) -> Result<Option<Bytes>, S<Error>> {
+        loop {
+            let (Some(env), Some(encoder)) = (&mut self.env, &mut self.encoder) else {
+                return self.error.take().map_or(Ok(None), Err);
+            };
+            match write_step(env, &*self.format, encoder, &mut self.remaining, self.rows_count) {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.env = None;
+                    let mut encoder = self.encoder.take().expect("encoder exists");
+                    encoder.flush().map_err(|e| Error::WriteOutput(e).no_span())?;
+                }
+                Err(e) => {
+                    self.env = None;
+                    self.encoder = None;
+                    return Err(e);
+                }
+            }
+            let chunk = self.sink.take();
+            if !chunk.is_empty() {
+                return Ok(Some(chunk.into()));
+            }
+        }
+    }
+}
+
+impl Stream for DataStream {
+    type Item = Result<Bytes, S<Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().next_chunk().transpose())
+    }
+}
+
+/// The writer receiving the data yielded by a [`DataStream`].
+#[derive(Debug, Clone, Default)]
+pub struct StreamSink(Arc<Mutex<Vec<u8>>>);
+
+impl StreamSink {
+    /// Takes out the data written so far.
+    fn take(&self) -> Vec<u8> {
+        mem::take(&mut *self.0.lock().expect("sink is not poisoned"))
+    }
+}
+
+impl Write for StreamSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("sink is not poisoned").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Iterator of the rows of a table, created by [`Generator::rows()`].
+#[derive(Debug)]
+pub struct Rows {
+    /// The index of the table, and the environment generating all tables. This is `None` after
+    /// an error.
+    table: Option<(usize, writer::Env<RowCollector>)>,
+    /// The error to be returned once by the iterator.
+    error: Option<S<Error>>,
+    format: SqlFormat,
+    pending: VecDeque<Vec<Value>>,
+}
+
+/// The maximum number of consecutive rows of the root tables generated by [`Rows`] without
+/// producing any row of the requested table, before giving up with an error.
+const MAX_EMPTY_ATTEMPTS: u32 = 1 << 16;
+
+impl Iterator for Rows {
+    type Item = Result<Vec<Value>, S<Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut attempts = 0;
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Some(Ok(row));
+            }
+            let (index, env) = match &mut self.table {
+                Some((index, env)) => (*index, env),
+                None => return self.error.take().map(Err),
+            };
+            if attempts == MAX_EMPTY_ATTEMPTS {
+                let (table, _) = env.tables().nth(index).expect("table index is valid");
+                let name = table.name.table_name(false).to_owned();
+                self.table = None;
+                return Some(Err(Error::NoRowsGenerated { name, attempts }.no_span()));
+            }
+            attempts += 1;
+            if let Err(e) = env.write_row(&self.format) {
+                self.table = None;
+                return Some(Err(e));
+            }
+            for (i, (_, w)) in env.tables().enumerate() {
+                if i == index {
+                    self.pending.extend(w.rows.drain(..));
+                } else {
+                    w.rows.clear();
+                }
+            }
+        }
+    }
+}
+
+/// Iterator of the deserialized rows of a table, created by [`Generator::deserialize_rows()`].
+#[derive(Debug)]
+pub struct DeserializeRows<T> {
+    rows: Rows,
+    column_names: Vec<String>,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Iterator for DeserializeRows<T> {
+    type Item = Result<T, S<Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next()?;
+        Some(row.and_then(|row| from_row(&self.column_names, &row).no_span_err()))
+    }
+}
+
+/// Writer collecting the values of every row.
+#[derive(Debug, Default)]
+struct RowCollector {
+    rows: Vec<Vec<Value>>,
+}
+
+impl Writer for RowCollector {
+    fn write_value(&mut self, _: &dyn Format, value: &Value) -> Result<(), S<Error>> {
+        if let Some(row) = self.rows.last_mut() {
+            row.push(value.clone());
+        }
+        Ok(())
+    }
+    fn write_file_header(&mut self, _: &dyn Format, _: &Schema) -> Result<(), S<Error>> {
+        Ok(())
+    }
+    fn write_header(&mut self, _: &dyn Format, _: &Schema) -> Result<(), S<Error>> {
+        self.rows.push(Vec::new());
+        Ok(())
+    }
+    fn write_value_header(&mut self, _: &dyn Format, _: &str) -> Result<(), S<Error>> {
+        Ok(())
+    }
+    fn write_value_separator(&mut self, _: &dyn Format) -> Result<(), S<Error>> {
+        Ok(())
+    }
+    fn write_row_separator(&mut self, _: &dyn Format) -> Result<(), S<Error>> {
+        self.rows.push(Vec::new());
+        Ok(())
+    }
+    fn write_trailer(&mut self, _: &dyn Format) -> Result<(), S<Error>> {
+        Ok(())
+    }
+}
+
+/// Writer formatting the rows into a memory buffer.
+#[derive(Debug, Default)]
+struct BufferWriter(Vec<u8>);
+
+impl Writer for BufferWriter {
+    fn write_value(&mut self, format: &dyn Format, value: &Value) -> Result<(), S<Error>> {
+        format
+            .write_value(&mut self.0, value)
+            .map_err(|e| Error::WriteOutput(e).no_span())
+    }
+    fn write_file_header(&mut self, format: &dyn Format, schema: &Schema) -> Result<(), S<Error>> {
+        format
+            .write_file_header(&mut self.0, schema)
+            .map_err(|e| Error::WriteOutput(e).no_span())
+    }
+    fn write_header(&mut self, format: &dyn Format, schema: &Schema) -> Result<(), S<Error>> {
+        format
+            .write_header(&mut self.0, schema)
+            .map_err(|e| Error::WriteOutput(e).no_span())
+    }
+    fn write_value_header(&mut self, format: &dyn Format, column: &str) -> Result<(), S<Error>> {
+        format
+            .write_value_header(&mut self.0, column)
+            .map_err(|e| Error::WriteOutput(e).no_span())
+    }
+    fn write_value_separator(&mut self, format: &dyn Format) -> Result<(), S<Error>> {
+        format
+            .write_value_separator(&mut self.0)
+            .map_err(|e| Error::WriteOutput(e).no_span())
+    }
+    fn write_row_separator(&mut self, format: &dyn Format) -> Result<(), S<Error>> {
+        format
+            .write_row_separator(&mut self.0)
+            .map_err(|e| Error::WriteOutput(e).no_span())
+    }
+    fn write_trailer(&mut self, format: &dyn Format) -> Result<(), S<Error>> {
+        format
+            .write_trailer(&mut self.0)
+            .map_err(|e| Error::WriteOutput(e).no_span())
+    }
+}
+
+#[test]
+fn test_same_as_cli() {
+    use crate::value::TIMESTAMP_FORMAT;
+    use std::str::FromStr;
+
+    let mut registry = Registry::default();
+    let generator = Generator::builder(
+        "{{ @base := rand.range(100, 200) }}
+        CREATE TABLE t (
+            a INT {{ @base + rownum }},
+            b TEXT {{ rand.regex('[a-z]{4}') }},
+            c TIMESTAMP {{ current_timestamp }}
+        );",
+    )
+    .seed(Seed::from_str("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef").unwrap())
+    .rng(RngName::ChaCha12)
+    .now(NaiveDateTime::parse_from_str("2020-01-02 03:04:05", TIMESTAMP_FORMAT).unwrap())
+    .total_count(5)
+    .rows_count(2)
+    .build(&mut registry)
+    .unwrap();
+
+    // generated by `dbgen -N 5 -R 5 -r 2 -s 0123…cdef --rng chacha12 --now '2020-01-02 03:04:05'`.
+    let mut output = Vec::new();
+    generator.write_to(&mut output, &SqlFormat(Options::default())).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "INSERT INTO t VALUES
+(137, 'wuff', '2020-01-02 03:04:05'),
+(138, 'aqoe', '2020-01-02 03:04:05');
+INSERT INTO t VALUES
+(139, 'suwo', '2020-01-02 03:04:05'),
+(140, 'yvzi', '2020-01-02 03:04:05');
+INSERT INTO t VALUES
+(141, 'csoi', '2020-01-02 03:04:05');
+"
+    );
+
+    let rows = generator.rows("T").take(5).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(rows[4][0], Value::from(141));
+    assert_eq!(rows[4][1], Value::from("csoi".to_owned()));
+
+    let mut unknown = generator.rows("u");
+    assert_eq!(
+        unknown.next().unwrap().unwrap_err().inner.to_string(),
+        "unknown table u"
+    );
+    assert!(unknown.next().is_none());
+}
+
+#[test]
+fn test_rows_never_generated() {
+    let mut registry = Registry::default();
+    let generator = Generator::builder("CREATE TABLE t (a INT {{ rownum }} {{ WHERE col(a) < 0 }});")
+        .build(&mut registry)
+        .unwrap();
+    let mut rows = generator.rows("t");
+    assert_eq!(
+        rows.next().unwrap().unwrap_err().inner.to_string(),
+        "no rows of table t are generated after 65536 attempts"
+    );
+    assert!(rows.next().is_none());
+
+    let generator = Generator::builder(
+        "CREATE TABLE u (b INT {{ rownum }});
+        /*{{ for each row of u generate 0 rows of v }}*/
+        CREATE TABLE v (c INT {{ subrownum }});",
+    )
+    .build(&mut registry)
+    .unwrap();
+    let mut rows = generator.rows("v");
+    assert_eq!(
+        rows.next().unwrap().unwrap_err().inner.to_string(),
+        "no rows of table v are generated after 65536 attempts"
+    );
+    assert!(rows.next().is_none());
+    assert_eq!(generator.rows("u").nth(99).unwrap().unwrap(), vec![Value::from(100)]);
+}
+
+#[test]
+fn test_stream() {
+    use futures::executor::block_on_stream;
+
+    let mut registry = Registry::default();
+    let generator = Generator::builder("CREATE TABLE t (a INT {{ rownum }}); CREATE TABLE u (b INT {{ -rownum }});")
+        .total_count(5)
+        .rows_count(2)
+        .build(&mut registry)
+        .unwrap();
+    let format = SqlFormat(Options::default());
+
+    let mut expected = Vec::new();
+    generator.write_to(&mut expected, &format).unwrap();
+    let stream = generator.stream(Box::new(SqlFormat(Options::default())));
+    fn assert_send_static<T: Send + 'static>(_: &T) {}
+    assert_send_static(&stream);
+    let chunks = block_on_stream(stream).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks.concat(), expected);
+
+    #[cfg(feature = "cli")]
+    {
+        use crate::cli::CompressionName;
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let stream = generator
+            .stream(Box::new(format))
+            .encoder(|sink| CompressionName::Gzip.wrap(sink, 6, 1))
+            .unwrap();
+        let compressed = block_on_stream(stream).collect::<Result<Vec<_>, _>>().unwrap().concat();
+        let mut decompressed = Vec::new();
+        GzDecoder::new(&*compressed).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, expected);
+    }
+
+    let generator = Generator::builder(
+        "CREATE TABLE t (a INT {{ CASE WHEN rownum = 2 THEN debug.panic(rownum) ELSE rownum END }});",
+    )
+    .total_count(5)
+    .build(&mut registry)
+    .unwrap();
+    let mut stream = block_on_stream(generator.stream(Box::new(SqlFormat(Options::default()))));
+    assert_eq!(&*stream.next().unwrap().unwrap(), b"INSERT INTO t VALUES\n(1);\n");
+    assert!(matches!(stream.next().unwrap().unwrap_err().inner, Error::Panic { .. }));
+    assert!(stream.next().is_none());
+}
@@ -0,0 +1,218 @@
+//! JSON values.
+
+use crate::{
+    error::Error,
+    number::Number,
+    value::{Value, TIMESTAMP_FORMAT},
+};
+use chrono::TimeZone;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::{convert::TryFrom, fmt};
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    /// The JSON `null`.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A number.
+    Number(Number),
+    /// A string.
+    String(String),
+    /// An array.
+    Array(Vec<Json>),
+    /// An object. The keys are kept in insertion order.
+    Object(Vec<(String, Json)>),
+}
+
+impl Default for Json {
+    fn default() -> Self {
+        Self::Null
+    }
+}
+
+impl Json {
+    /// Converts an SQL value into JSON.
+    ///
+    /// * NULL becomes `null`.
+    /// * Booleans and numbers are kept as is.
+    /// * Strings must be valid UTF-8.
+    /// * Timestamps and intervals are converted to strings, the same as `||`.
+    /// * Arrays are converted recursively.
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Null => Self::Null,
+            Value::Number(n) => match n.as_bool() {
+                Some(b) => Self::Bool(b),
+                None => Self::Number(*n),
+            },
+            Value::Bytes(b) => match b.as_str() {
+                Some(s) => Self::String(s.to_owned()),
+                None => {
+                    return Err(Error::InvalidArguments(format!(
+                        "cannot convert binary string {} to JSON",
+                        value
+                    )));
+                }
+            },
+            Value::Timestamp(timestamp, tz) => {
+                Self::String(tz.from_utc_datetime(timestamp).format(TIMESTAMP_FORMAT).to_string())
+            }
+            Value::Interval(interval) => Self::String(format!("INTERVAL {} MICROSECOND", interval)),
+            Value::Array(array) => Self::Array(array.iter().map(Self::from_value).collect::<Result<_, _>>()?),
+            Value::Json(json) => (**json).clone(),
+        })
+    }
+
+    /// Inserts a key-value pair into an object.
+    ///
+    /// If the key already exists, its value will be replaced in place.
+    /// Otherwise, the pair is appended to the end. Does nothing if this JSON
+    /// value is not an object.
+    pub fn insert(&mut self, key: String, value: Self) {
+        if let Self::Object(entries) = self {
+            if let Some(entry) = entries.iter_mut().find(|(k, _)| *k == key) {
+                entry.1 = value;
+            } else {
+                entries.push((key, value));
+            }
+        }
+    }
+
+    /// Gets a mutable reference to the value of a key in an object, inserting
+    /// an empty object if the key does not exist.
+    ///
+    /// Returns `None` if this JSON value is not an object.
+    fn entry_or_insert_object(&mut self, key: &str) -> Option<&mut Self> {
+        if let Self::Object(entries) = self {
+            let index = if let Some(index) = entries.iter().position(|(k, _)| k == key) {
+                index
+            } else {
+                entries.push((key.to_owned(), Self::Object(Vec::new())));
+                entries.len() - 1
+            };
+            Some(&mut entries[index].1)
+        } else {
+            None
+        }
+    }
+
+    /// Inserts a value into a nested object following the dot-separated path,
+    /// creating the intermediate objects as needed.
+    pub fn insert_path(&mut self, path: &str, value: Self) -> Result<(), Error> {
+        let mut target = self;
+        let mut components = path.split('.');
+        let last = components.next_back().unwrap_or_default();
+        for component in components {
+            target = target.entry_or_insert_object(component).ok_or_else(|| {
+                Error::InvalidArguments(format!(
+                    "cannot insert '{}' into a JSON value which is not an object",
+                    path
+                ))
+            })?;
+        }
+        if let Self::Object(_) = target {
+            target.insert(last.to_owned(), value);
+            Ok(())
+        } else {
+            Err(Error::InvalidArguments(format!(
+                "cannot insert '{}' into a JSON value which is not an object",
+                path
+            )))
+        }
+    }
+}
+
+/// Writes a string as a quoted JSON string.
+fn write_json_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{0}'..='\u{1f}' => "",
+            _ => continue,
+        };
+        f.write_str(&s[start..i])?;
+        if escaped.is_empty() {
+            write!(f, "\\u{:04x}", u32::from(c))?;
+        } else {
+            f.write_str(escaped)?;
+        }
+        start = i + c.len_utf8();
+    }
+    f.write_str(&s[start..])?;
+    f.write_str("\"")
+}
+
+impl fmt::Display for Json {
+    /// Writes the compact JSON text.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Number(n) => n.write(f, "true", "false"),
+            Self::String(s) => write_json_string(f, s),
+            Self::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    item.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+            Self::Object(entries) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write_json_string(f, key)?;
+                    f.write_str(":")?;
+                    value.fmt(f)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Number(n) => n.serialize(serializer),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Self::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl TryFrom<Value> for Json {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Self::from_value(&value)
+    }
+}
@@ -31,16 +31,24 @@ pub mod bytes;
 pub mod bytes_stream;
 #[cfg(feature = "cli")]
 pub mod cli;
+#[cfg(feature = "cli")]
+pub mod compress;
+pub mod de;
 pub mod error;
 pub mod eval;
 pub mod format;
 pub mod functions;
+pub mod generator;
+pub mod json;
 pub mod lexctr;
 pub mod number;
 pub mod parser;
+pub mod rng;
+#[cfg(feature = "cli")]
 pub mod s3;
 #[cfg(feature = "cli")]
 pub mod schemagen_cli;
 pub mod span;
+pub mod types;
 pub mod value;
 pub mod writer;
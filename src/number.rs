@@ -1,6 +1,10 @@
 //! Number.
 
 use numcmp::NumCmp;
+use serde::{
+    de::{Error as DeError, Visitor},
+    ser::{Serialize, Serializer},
+};
 use std::{cmp::Ordering, convert::TryFrom, fmt, io, u64};
 
 /// Implementation of a number.
@@ -12,10 +16,34 @@ enum N {
     I(i128),
     /// A finite floating-point number.
     F(f64),
+    /// An exact decimal number, represented as `mantissa / 10^scale`.
+    D(i128, u8),
+}
+
+/// The maximum scale (number of digits after the decimal point) of an exact
+/// decimal number.
+pub const MAX_DECIMAL_SCALE: u8 = 38;
+
+/// Computes 10 to the power of `exp`, which must not exceed `MAX_DECIMAL_SCALE`.
+fn pow10(exp: u8) -> i128 {
+    10_i128.pow(exp.into())
+}
+
+/// Divides two integers, rounding half away from zero.
+///
+/// Returns `None` if the divisor is zero or the result overflows.
+fn div_round(n: i128, d: i128) -> Option<i128> {
+    let q = n.checked_div(d)?;
+    let r = n % d;
+    if r.unsigned_abs() >= d.unsigned_abs() - r.unsigned_abs() {
+        q.checked_add(if (n < 0) == (d < 0) { 1 } else { -1 })
+    } else {
+        Some(q)
+    }
 }
 
 /// The error returned in numerical arithmetics.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum NumberError {
     /// Computation result overflows the range.
@@ -66,6 +94,7 @@ impl From<Number> for f64 {
             N::B(false) => 0.0,
             N::I(v) => v as _,
             N::F(v) => v,
+            N::D(m, s) => m as f64 / 10.0_f64.powi(s.into()),
         }
     }
 }
@@ -79,6 +108,7 @@ macro_rules! impl_try_from_number_for_integer {
                     N::B(v) => Ok(v.into()),
                     N::I(v) => Self::try_from(v).map_err(|_| NumberError::Overflow),
                     N::F(v) if Self::min_value() as f64 <= v && v <= Self::max_value() as f64 => Ok(v as _),
+                    N::D(m, s) => Self::try_from(m / pow10(s)).map_err(|_| NumberError::Overflow),
                     _ => Err(NumberError::Overflow),
                 }
             }
@@ -93,6 +123,48 @@ impl_try_from_number_for_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, i
 #[derive(Copy, Clone, Debug)]
 pub struct Number(N);
 
+impl Serialize for Number {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            N::B(v) => serializer.serialize_bool(v),
+            N::I(v) => {
+                if let Ok(v) = i64::try_from(v) {
+                    serializer.serialize_i64(v)
+                } else if let Ok(v) = u64::try_from(v) {
+                    serializer.serialize_u64(v)
+                } else {
+                    serializer.serialize_i128(v)
+                }
+            }
+            N::F(v) => serializer.serialize_f64(v),
+            N::D(..) => serializer.serialize_f64(f64::from(*self)),
+        }
+    }
+}
+
+impl Number {
+    /// Passes this number to a serde visitor.
+    ///
+    /// Integers and decimals with integral values are visited as integers. Other decimals are
+    /// visited as strings, so that their exact values are kept.
+    pub(crate) fn visit<'de, V: Visitor<'de>, E: DeError>(self, visitor: V) -> Result<V::Value, E> {
+        let v = match self.0 {
+            N::B(v) => return visitor.visit_bool(v),
+            N::I(v) => v,
+            N::F(v) => return visitor.visit_f64(v),
+            N::D(m, s) if m % pow10(s) == 0 => m / pow10(s),
+            N::D(..) => return visitor.visit_str(&self.to_string()),
+        };
+        if let Ok(v) = i64::try_from(v) {
+            visitor.visit_i64(v)
+        } else if let Ok(v) = u64::try_from(v) {
+            visitor.visit_u64(v)
+        } else {
+            visitor.visit_i128(v)
+        }
+    }
+}
+
 impl fmt::Display for Number {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.write(f, "1", "0")
@@ -106,11 +178,144 @@ impl Number {
         Self(N::F(v))
     }
 
+    /// Creates an exact decimal number with value `mantissa / 10^scale`.
+    pub fn from_decimal(mantissa: i128, scale: u8) -> Result<Self, NumberError> {
+        if scale <= MAX_DECIMAL_SCALE {
+            Ok(Self(N::D(mantissa, scale)))
+        } else {
+            Err(NumberError::Overflow)
+        }
+    }
+
+    /// Converts this number into the mantissa of a decimal number with the
+    /// given scale, rounding half away from zero.
+    ///
+    /// Floating point numbers are rounded using their shortest decimal
+    /// representation, so `2.675` becomes `268` at scale 2.
+    pub fn to_decimal(self, scale: u8) -> Result<i128, NumberError> {
+        if scale > MAX_DECIMAL_SCALE {
+            return Err(NumberError::Overflow);
+        }
+        match self.try_as_decimal().or_else(|v| shortest_decimal(v).ok_or(v)) {
+            Ok((m, s)) if s <= scale => m.checked_mul(pow10(scale - s)),
+            Ok((m, s)) => div_round(m, pow10(s - scale)),
+            Err(v) => {
+                let v = (v * 10.0_f64.powi(scale.into())).round();
+                Self::try_from(v).ok().and_then(|n| i128::try_from(n).ok())
+            }
+        }
+        .ok_or(NumberError::Overflow)
+    }
+
+    /// Parses a decimal number from a string.
+    ///
+    /// Integers are parsed as integers, and numbers with a decimal point or an
+    /// exponent are parsed as exact decimals when they fit, otherwise as
+    /// floating point numbers. Returns `None` if the string is not a valid
+    /// number or is out of range.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (mantissa_str, exp) = match s.find(&['e', 'E'][..]) {
+            Some(i) => (&s[..i], s[i + 1..].parse::<i32>().ok()?),
+            None => (s, 0),
+        };
+        let digits = mantissa_str.trim_start_matches(&['+', '-'][..]);
+        let (int_part, frac_part) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..]),
+            None => (digits, ""),
+        };
+        let is_valid = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+        if mantissa_str.len() - digits.len() > 1
+            || int_part.len() + frac_part.len() == 0
+            || !is_valid(int_part)
+            || !is_valid(frac_part)
+        {
+            return None;
+        }
+        if int_part.len() == digits.len() && exp == 0 {
+            if let Ok(v) = mantissa_str.trim_start_matches('+').parse() {
+                return Some(Self(N::I(v)));
+            }
+        }
+        let exact = (|| {
+            let mut mantissa = format!("{}{}", int_part, frac_part).parse::<i128>().ok()?;
+            if mantissa_str.starts_with('-') {
+                mantissa = -mantissa;
+            }
+            let scale = i32::try_from(frac_part.len()).ok()? - exp;
+            if scale < 0 {
+                let shift = u8::try_from(-scale).ok().filter(|e| *e <= MAX_DECIMAL_SCALE)?;
+                Some(Self(N::D(mantissa.checked_mul(pow10(shift))?, 0)))
+            } else {
+                let scale = u8::try_from(scale).ok().filter(|e| *e <= MAX_DECIMAL_SCALE)?;
+                Some(Self(N::D(mantissa, scale)))
+            }
+        })();
+        exact.or_else(|| Self::try_from(s.parse::<f64>().ok()?).ok())
+    }
+
     fn try_as_i128(self) -> Result<i128, f64> {
         match self.0 {
             N::B(v) => Ok(v.into()),
-            N::I(v) => Ok(v),
+            N::I(v) | N::D(v, 0) => Ok(v),
             N::F(v) => Err(v),
+            N::D(..) => Err(f64::from(self)),
+        }
+    }
+
+    fn try_as_decimal(self) -> Result<(i128, u8), f64> {
+        match self.0 {
+            N::B(v) => Ok((v.into(), 0)),
+            N::I(v) => Ok((v, 0)),
+            N::F(v) => Err(v),
+            N::D(m, s) => Ok((m, s)),
+        }
+    }
+
+    fn is_decimal(self) -> bool {
+        matches!(self.0, N::D(..))
+    }
+
+    /// Checks whether two numbers have the same representation, unlike `==`
+    /// which considers `1`, `1.0` and `TRUE` equal.
+    pub(crate) fn is_identical(self, other: Self) -> bool {
+        match (self.0, other.0) {
+            (N::B(a), N::B(b)) => a == b,
+            (N::I(a), N::I(b)) => a == b,
+            (N::F(a), N::F(b)) => a.to_bits() == b.to_bits(),
+            (N::D(a, s), N::D(b, t)) => a == b && s == t,
+            _ => false,
+        }
+    }
+
+    /// Converts both numbers into decimals.
+    ///
+    /// A floating point number is only converted (using its shortest decimal
+    /// representation) when the other number is an exact decimal.
+    fn try_as_decimal_pair(self, other: Self) -> Option<((i128, u8), (i128, u8))> {
+        let convert = |n: Self, o: Self| match n.try_as_decimal() {
+            Ok(d) => Some(d),
+            Err(v) if o.is_decimal() => shortest_decimal(v),
+            Err(_) => None,
+        };
+        Some((convert(self, other)?, convert(other, self)?))
+    }
+
+    /// Converts both numbers into decimals of the same scale, returning the
+    /// two mantissas and the common scale.
+    fn try_align_decimal(self, other: Self) -> Option<(i128, i128, u8)> {
+        let ((a, sa), (b, sb)) = self.try_as_decimal_pair(other)?;
+        Some(match sa.cmp(&sb) {
+            Ordering::Less => (a.checked_mul(pow10(sb - sa))?, b, sb),
+            Ordering::Equal => (a, b, sa),
+            Ordering::Greater => (a, b.checked_mul(pow10(sa - sb))?, sa),
+        })
+    }
+
+    /// Returns the boolean value if this number is a boolean.
+    pub fn as_bool(self) -> Option<bool> {
+        match self.0 {
+            N::B(v) => Some(v),
+            _ => None,
         }
     }
 
@@ -124,6 +329,7 @@ impl Number {
                 let mut output = ryu::Buffer::new();
                 sink.write_str(output.format_finite(v))
             }
+            N::D(m, s) => write_decimal(sink, m, s),
         }
     }
 
@@ -137,6 +343,7 @@ impl Number {
                 let mut output = ryu::Buffer::new();
                 sink.write_all(output.format_finite(v).as_bytes())
             }
+            N::D(m, s) => write!(sink, "{}", Self(N::D(m, s))),
         }
     }
 
@@ -146,6 +353,7 @@ impl Number {
             N::B(v) => v.cmp(&false),
             N::I(v) => v.cmp(&0),
             N::F(v) => v.partial_cmp(&0.0).unwrap_or(Ordering::Equal),
+            N::D(m, _) => m.cmp(&0),
         }
     }
 
@@ -156,6 +364,11 @@ impl Number {
                 return Ok(Self(N::I(c)));
             }
         }
+        if let Some((a, b, scale)) = self.try_align_decimal(other) {
+            if let Some(c) = a.checked_add(b) {
+                return Ok(Self(N::D(c, scale)));
+            }
+        }
         Self::try_from(f64::from(self) + f64::from(other))
     }
 
@@ -166,6 +379,11 @@ impl Number {
                 return Self(N::I(c));
             }
         }
+        if let N::D(m, s) = self.0 {
+            if let Some(c) = m.checked_neg() {
+                return Self(N::D(c, s));
+            }
+        }
         Self::from_finite_f64(-f64::from(self))
     }
 
@@ -176,6 +394,11 @@ impl Number {
                 return Ok(Self(N::I(c)));
             }
         }
+        if let Some((a, b, scale)) = self.try_align_decimal(other) {
+            if let Some(c) = a.checked_sub(b) {
+                return Ok(Self(N::D(c, scale)));
+            }
+        }
         Self::try_from(f64::from(self) - f64::from(other))
     }
 
@@ -186,6 +409,14 @@ impl Number {
                 return Ok(Self(N::I(c)));
             }
         }
+        if let Some(((a, sa), (b, sb))) = self.try_as_decimal_pair(other) {
+            let scale = sa + sb;
+            if scale <= MAX_DECIMAL_SCALE {
+                if let Some(c) = a.checked_mul(b) {
+                    return Ok(Self(N::D(c, scale)));
+                }
+            }
+        }
         Self::try_from(f64::from(self) * f64::from(other))
     }
 
@@ -196,6 +427,11 @@ impl Number {
                 return Ok(Self(N::I(c)));
             }
         }
+        if let Some((a, b, _)) = self.try_align_decimal(other) {
+            if let Some(c) = a.checked_div(b) {
+                return Ok(Self(N::I(c)));
+            }
+        }
 
         let denominator = f64::from(other);
         if denominator == 0.0 {
@@ -218,6 +454,13 @@ impl Number {
                 _ => return Ok(Self(N::I(a % b))),
             }
         }
+        if let Some((a, b, scale)) = self.try_align_decimal(other) {
+            match b {
+                0 => {}
+                -1 => return Ok(Self(N::D(0, scale))),
+                _ => return Ok(Self(N::D(a % b, scale))),
+            }
+        }
 
         let denominator = f64::from(other);
         if denominator == 0.0 {
@@ -228,8 +471,25 @@ impl Number {
         }
     }
 
-    /// Divides this number with another number using floating point arithmetic.
+    /// Divides this number with another number.
+    ///
+    /// If either number is an exact decimal, the result is a decimal with 6
+    /// more digits after the decimal point than the operands. Otherwise, the
+    /// division is performed using floating point arithmetic.
     pub fn float_div(self, other: Self) -> Result<Self, NumberError> {
+        if self.is_decimal() || other.is_decimal() {
+            if let Some(((a, sa), (b, sb))) = self.try_as_decimal_pair(other) {
+                let scale = (sa.max(sb) + 6).min(MAX_DECIMAL_SCALE);
+                if let Some(c) = (scale + sb)
+                    .checked_sub(sa)
+                    .filter(|e| *e <= MAX_DECIMAL_SCALE)
+                    .and_then(|e| a.checked_mul(pow10(e)))
+                    .and_then(|n| div_round(n, b))
+                {
+                    return Ok(Self(N::D(c, scale)));
+                }
+            }
+        }
         let a = f64::from(self);
         let b = f64::from(other);
         if b == 0.0 {
@@ -238,11 +498,177 @@ impl Number {
             Self::try_from(a / b)
         }
     }
+
+    /// Rounds this number to the given number of digits after the decimal
+    /// point. Negative digits round to the left of the decimal point.
+    ///
+    /// Exact decimals are rounded half away from zero and stay exact. All other
+    /// numbers are rounded as floating point numbers.
+    #[must_use]
+    pub fn round(self, digits: i32) -> Self {
+        if let N::D(m, s) = self.0 {
+            if digits >= s.into() {
+                return self;
+            }
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            let (shift, scale) = if digits < 0 {
+                (i64::from(s) - i64::from(digits), 0)
+            } else {
+                (i64::from(s) - i64::from(digits), digits as u8)
+            };
+            let rounded = u8::try_from(shift)
+                .ok()
+                .filter(|e| *e <= MAX_DECIMAL_SCALE)
+                .and_then(|e| div_round(m, pow10(e)))
+                .unwrap_or(0);
+            if digits >= 0 {
+                return Self(N::D(rounded, scale));
+            }
+            if let Some(c) = u8::try_from(-i64::from(digits))
+                .ok()
+                .filter(|e| *e <= MAX_DECIMAL_SCALE)
+                .and_then(|e| rounded.checked_mul(pow10(e)))
+            {
+                return Self(N::D(c, 0));
+            }
+        }
+
+        let value = f64::from(self);
+        let scale = 10.0_f64.powi(digits);
+        let result = if scale.is_finite() {
+            (value * scale).round() / scale
+        } else {
+            value
+        };
+        Self::from_finite_f64(result)
+    }
+
+    /// Computes the absolute value of this number.
+    #[must_use]
+    pub fn abs(self) -> Self {
+        match self.0 {
+            N::B(v) => Self(N::I(v.into())),
+            N::I(v) => v.checked_abs().map_or_else(|| self.neg(), |a| Self(N::I(a))),
+            N::F(v) => Self(N::F(v.abs())),
+            N::D(m, s) => m.checked_abs().map_or_else(|| self.neg(), |a| Self(N::D(a, s))),
+        }
+    }
+
+    /// Rounds this number to an integer using the given functions.
+    ///
+    /// The `adjust` function takes the truncated quotient and remainder of an
+    /// exact decimal and returns the rounded integer.
+    fn to_integral(self, float_fn: fn(f64) -> f64, adjust: fn(i128, i128) -> i128) -> Self {
+        match self.0 {
+            N::B(v) => Self(N::I(v.into())),
+            N::I(_) => self,
+            N::F(v) => Self(N::F(float_fn(v))),
+            N::D(m, s) => {
+                let divisor = pow10(s);
+                Self(N::D(adjust(m / divisor, m % divisor), 0))
+            }
+        }
+    }
+
+    /// Rounds this number to the largest integer not greater than itself.
+    #[must_use]
+    pub fn floor(self) -> Self {
+        self.to_integral(f64::floor, |q, r| if r < 0 { q - 1 } else { q })
+    }
+
+    /// Rounds this number to the smallest integer not less than itself.
+    #[must_use]
+    pub fn ceil(self) -> Self {
+        self.to_integral(f64::ceil, |q, r| if r > 0 { q + 1 } else { q })
+    }
+
+    /// Rounds this number to an integer towards zero.
+    #[must_use]
+    pub fn trunc(self) -> Self {
+        self.to_integral(f64::trunc, |q, _| q)
+    }
+
+    /// Raises this number to the power of another number.
+    ///
+    /// If the exponent is a non-negative integer and the base is not a
+    /// floating point number, the result is computed exactly when possible.
+    pub fn pow(self, exponent: Self) -> Result<Self, NumberError> {
+        if !matches!(self.0, N::F(_)) {
+            if let Some(mut e) = exponent.try_as_i128().ok().and_then(|e| u32::try_from(e).ok()) {
+                let mut base = self;
+                let mut result = Self(N::I(1));
+                while e > 0 {
+                    if e & 1 != 0 {
+                        result = result.mul(base)?;
+                    }
+                    e >>= 1;
+                    if e > 0 {
+                        base = base.mul(base)?;
+                    }
+                }
+                return Ok(result);
+            }
+        }
+        Self::try_from(f64::from(self).powf(f64::from(exponent)))
+    }
+}
+
+/// Converts a finite floating point number into a decimal using its shortest
+/// representation which round-trips, e.g. `0.1` becomes `(1, 1)`.
+fn shortest_decimal(v: f64) -> Option<(i128, u8)> {
+    let mut output = ryu::Buffer::new();
+    let repr = output.format_finite(v);
+    let (digits, exp) = match repr.find('e') {
+        Some(i) => (&repr[..i], repr[i + 1..].parse::<i32>().ok()?),
+        None => (repr, 0),
+    };
+    let digits = if digits.contains('.') {
+        digits.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        digits
+    };
+    let frac_len = digits.find('.').map_or(0, |i| digits.len() - i - 1);
+    let mantissa = digits.replace('.', "").parse::<i128>().ok()?;
+    let scale = i32::try_from(frac_len).ok()? - exp;
+    if scale < 0 {
+        let shift = u8::try_from(-scale).ok().filter(|e| *e <= MAX_DECIMAL_SCALE)?;
+        Some((mantissa.checked_mul(pow10(shift))?, 0))
+    } else {
+        let scale = u8::try_from(scale).ok().filter(|e| *e <= MAX_DECIMAL_SCALE)?;
+        Some((mantissa, scale))
+    }
+}
+
+/// Writes an exact decimal number.
+fn write_decimal<W: fmt::Write>(sink: &mut W, mantissa: i128, scale: u8) -> fmt::Result {
+    if mantissa < 0 {
+        sink.write_str("-")?;
+    }
+    let digits = mantissa.unsigned_abs().to_string();
+    let scale = usize::from(scale);
+    if scale == 0 {
+        return sink.write_str(&digits);
+    }
+    if digits.len() <= scale {
+        sink.write_str("0.")?;
+        for _ in digits.len()..scale {
+            sink.write_str("0")?;
+        }
+        sink.write_str(&digits)
+    } else {
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        sink.write_str(int_part)?;
+        sink.write_str(".")?;
+        sink.write_str(frac_part)
+    }
 }
 
 macro_rules! impl_partial_ord_method {
     ($(fn $fn_name:ident(...) -> $ret:ty = $method:ident;)+) => {
         $(fn $fn_name(&self, other: &Self) -> $ret {
+            if let Some((a, b, _)) = self.try_align_decimal(*other) {
+                return a.$method(b);
+            }
             match (self.try_as_i128(), other.try_as_i128()) {
                 (Ok(a), Ok(b)) => a.$method(b),
                 (Ok(a), Err(b)) => a.$method(b),
@@ -361,6 +787,22 @@ mod tests {
         assert_eq!(Number::from(true), Number::from_finite_f64(1.0));
     }
 
+    #[test]
+    fn test_is_identical() {
+        assert!(Number::from(1).is_identical(Number::from(1_u64)));
+        assert!(Number::from_finite_f64(2.5).is_identical(Number::from_finite_f64(2.5)));
+        assert!(Number::from_decimal(25, 1)
+            .unwrap()
+            .is_identical(Number::from_decimal(25, 1).unwrap()));
+
+        assert!(!Number::from(1).is_identical(Number::from_finite_f64(1.0)));
+        assert!(!Number::from(1).is_identical(Number::from(true)));
+        assert!(!Number::from_finite_f64(0.0).is_identical(Number::from_finite_f64(-0.0)));
+        assert!(!Number::from_decimal(25, 1)
+            .unwrap()
+            .is_identical(Number::from_decimal(250, 2).unwrap()));
+    }
+
     #[test]
     fn test_add() {
         assert_eq!(Number::from(3).add(Number::from(4)), Ok(Number::from(7)));
@@ -483,4 +925,122 @@ mod tests {
         assert_eq!(Number::from(true).rem(Number::from(false)), Err(NumberError::NaN));
         assert_eq!(Number::from(false).rem(Number::from(true)), Ok(Number::from(0)));
     }
+
+    #[test]
+    fn test_decimal() {
+        let d = |m, s| Number::from_decimal(m, s).unwrap();
+        assert_eq!(d(12345, 2).to_string(), "123.45");
+        assert_eq!(d(-5, 3).to_string(), "-0.005");
+        assert_eq!(d(7, 0).to_string(), "7");
+        assert_eq!(Number::from_decimal(1, 39), Err(NumberError::Overflow));
+
+        assert_eq!(d(10, 2).add(d(20, 2)).unwrap().to_string(), "0.30");
+        assert_eq!(d(10, 1).add(Number::from(2)).unwrap().to_string(), "3.0");
+        assert_eq!(d(105, 2).sub(d(1, 1)).unwrap().to_string(), "0.95");
+        assert_eq!(d(15, 1).mul(d(25, 2)).unwrap().to_string(), "0.375");
+        assert_eq!(d(1, 0).float_div(d(3, 0)).unwrap().to_string(), "0.333333");
+        assert_eq!(d(2, 0).float_div(Number::from(3)).unwrap().to_string(), "0.666667");
+        assert_eq!(d(75, 1).div(d(2, 0)).unwrap().to_string(), "3");
+        assert_eq!(d(75, 1).rem(d(2, 0)).unwrap().to_string(), "1.5");
+        assert_eq!(d(1, 2).float_div(d(0, 0)), Err(NumberError::NaN));
+        assert_eq!(d(-12345, 2).neg().to_string(), "123.45");
+
+        assert_eq!(d(12345, 3).round(2).to_string(), "12.35");
+        assert_eq!(d(-12345, 3).round(2).to_string(), "-12.35");
+        assert_eq!(d(12345, 3).round(5).to_string(), "12.345");
+        assert_eq!(d(12345, 3).round(-1).to_string(), "10");
+        assert_eq!(d(12345, 3).round(-9).to_string(), "0");
+
+        assert_eq!(d(150, 2), d(15, 1));
+        assert_eq!(d(300, 2), Number::from(3));
+        assert!(d(301, 2) > Number::from(3));
+        assert!(d(299, 2) < Number::from_finite_f64(2.995));
+        assert_eq!(d(i128::MAX, 0), Number::from(i128::MAX));
+
+        assert_eq!(Number::from_finite_f64(999.99).to_decimal(2), Ok(99999));
+        assert_eq!(Number::from_finite_f64(2.675).to_decimal(2), Ok(268));
+        assert_eq!(Number::from_finite_f64(1e-200).to_decimal(2), Ok(0));
+        assert_eq!(Number::from_finite_f64(1e300).to_decimal(2), Err(NumberError::Overflow));
+        assert_eq!(d(12345, 3).to_decimal(1), Ok(123));
+        assert_eq!(Number::from(3).to_decimal(4), Ok(30000));
+        assert_eq!(i64::try_from(d(-12345, 2)), Ok(-123));
+
+        let f = Number::from_finite_f64;
+        assert_eq!(d(1, 1).add(f(0.2)).unwrap().to_string(), "0.3");
+        assert_eq!(d(29724, 2).mul(f(0.0825)).unwrap().to_string(), "24.522300");
+        assert_eq!(f(0.1).add(f(0.2)).unwrap().to_string(), "0.30000000000000004");
+        assert_eq!(d(3, 1), f(0.3));
+        assert_eq!(shortest_decimal(1.5e3), Some((1500, 0)));
+        assert_eq!(shortest_decimal(-2.5e-3), Some((-25, 4)));
+        assert_eq!(shortest_decimal(1e-200), None);
+    }
+
+    #[test]
+    fn test_math() {
+        let d = |m, s| Number::from_decimal(m, s).unwrap();
+        assert_eq!(Number::from(-3).abs().to_string(), "3");
+        assert_eq!(
+            Number::from(i128::MIN).abs(),
+            Number::from_finite_f64(170141183460469231731687303715884105728.0)
+        );
+        assert_eq!(Number::from_finite_f64(-2.5).abs().to_string(), "2.5");
+        assert_eq!(d(-125, 2).abs().to_string(), "1.25");
+
+        assert_eq!(Number::from(7).floor().to_string(), "7");
+        assert_eq!(Number::from_finite_f64(-2.5).floor().to_string(), "-3.0");
+        assert_eq!(Number::from_finite_f64(-2.5).ceil().to_string(), "-2.0");
+        assert_eq!(Number::from_finite_f64(-2.5).trunc().to_string(), "-2.0");
+        assert_eq!(d(-125, 2).floor().to_string(), "-2");
+        assert_eq!(d(-125, 2).ceil().to_string(), "-1");
+        assert_eq!(d(125, 2).ceil().to_string(), "2");
+        assert_eq!(d(125, 2).trunc().to_string(), "1");
+        assert_eq!(Number::from(true).floor().to_string(), "1");
+
+        assert_eq!(Number::from(2).pow(Number::from(10)).unwrap().to_string(), "1024");
+        assert_eq!(Number::from(-3).pow(Number::from(3)).unwrap().to_string(), "-27");
+        assert_eq!(Number::from(7).pow(Number::from(0)).unwrap().to_string(), "1");
+        assert_eq!(d(15, 1).pow(Number::from(2)).unwrap().to_string(), "2.25");
+        assert_eq!(Number::from(2).pow(Number::from(-1)).unwrap().to_string(), "0.5");
+        assert_eq!(
+            Number::from(2).pow(Number::from(200)),
+            Number::try_from(2.0_f64.powi(200))
+        );
+        assert_eq!(
+            Number::from_finite_f64(4.0)
+                .pow(Number::from_finite_f64(0.5))
+                .unwrap()
+                .to_string(),
+            "2.0"
+        );
+        assert_eq!(
+            Number::from(-8).pow(Number::from_finite_f64(0.5)),
+            Err(NumberError::NaN)
+        );
+        assert_eq!(Number::from(10).pow(Number::from(400)), Err(NumberError::Overflow));
+    }
+
+    #[test]
+    fn test_parse() {
+        let p = |s| Number::parse(s).map(|n| n.to_string());
+        assert_eq!(p("123"), Some("123".to_owned()));
+        assert_eq!(p("-123"), Some("-123".to_owned()));
+        assert_eq!(p("+7"), Some("7".to_owned()));
+        assert_eq!(p("12.50"), Some("12.50".to_owned()));
+        assert_eq!(p("-.5"), Some("-0.5".to_owned()));
+        assert_eq!(p("5."), Some("5".to_owned()));
+        assert_eq!(p("1.5e3"), Some("1500".to_owned()));
+        assert_eq!(p("25e-3"), Some("0.025".to_owned()));
+        assert_eq!(p("1e300"), Some("1e300".to_owned()));
+        assert_eq!(
+            p("170141183460469231731687303715884105728"),
+            Some("1.7014118346046923e38".to_owned())
+        );
+        assert_eq!(p(""), None);
+        assert_eq!(p("."), None);
+        assert_eq!(p("--1"), None);
+        assert_eq!(p("1-"), None);
+        assert_eq!(p("abc"), None);
+        assert_eq!(p("1e"), None);
+        assert_eq!(p("1e400"), None);
+    }
 }
@@ -9,8 +9,18 @@ use crate::{
     value::Value,
 };
 
-use pest::{iterators::Pairs, Parser};
-use std::{collections::HashMap, mem, ops::Range};
+use pest::{
+    iterators::{Pair, Pairs},
+    Parser,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs::{canonicalize, read_to_string},
+    mem,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 mod derived {
     use pest_derive::Parser;
@@ -20,6 +30,10 @@ mod derived {
     pub(super) struct TemplateParser;
 }
 
+/// The maximum offset of `lag()`. Every table keeps this many previous rows
+/// at most, so the offset is limited to avoid running out of memory.
+const MAX_LAG_OFFSET: usize = 1 << 16;
+
 /// A schema-qualified name with quotation marks still intact.
 #[derive(Debug, Clone, Default)]
 pub struct QName {
@@ -151,6 +165,13 @@ fn unescape_into(res: &mut String, ident: &str, do_percent_escape: bool) {
     }
 }
 
+/// Removes the quotation marks of an identifier, or converts an unquoted identifier to lower case.
+pub(crate) fn unescape_ident(ident: &str) -> String {
+    let mut res = String::with_capacity(ident.len());
+    unescape_into(&mut res, ident, false);
+    res
+}
+
 /// One single table.
 #[derive(Debug, Clone, Default)]
 pub struct Table {
@@ -168,6 +189,16 @@ pub struct Table {
 
     /// The indices of the derived tables, and the number of rows to generate.
     pub derived: Vec<(usize, S<Expr>)>,
+
+    /// The number of previous rows needed by `lag()`.
+    pub lag_rows: usize,
+
+    /// The predicate of the `WHERE` or `RETRY UNTIL` directive.
+    pub filter: Option<S<Expr>>,
+
+    /// The maximum number of attempts of the `RETRY UNTIL` directive. If this
+    /// is `None`, the directive is `WHERE`, where failed rows are dropped.
+    pub retry_limit: Option<u64>,
 }
 
 /// A parsed template.
@@ -198,12 +229,25 @@ pub enum Expr {
     Value(Value),
     /// Symbol of a local variable `@x`.
     GetVariable(usize),
+    /// Reference to an earlier column of the current row `col(x)`.
+    GetColumn(usize),
+    /// Reference to a column of a previous row `lag(x, n, default)`.
+    Lag {
+        /// The column index.
+        column: usize,
+        /// The number of rows before the current row (at least 1).
+        offset: usize,
+        /// The value when there are not enough previous rows.
+        default: Box<S<Expr>>,
+    },
     /// A variable assignment expression `@x := y`.
     SetVariable(usize, Box<S<Expr>>),
     /// A function call.
     Function {
         /// The function.
         function: &'static dyn Function,
+        /// The function name or operator as written in the template.
+        name: Cow<'static, str>,
         /// Function arguments.
         args: Vec<S<Expr>>,
     },
@@ -230,8 +274,13 @@ fn is_ident_char(c: char) -> bool {
 
 impl Template {
     /// Parses a raw string into a structured template.
+    ///
+    /// The `path` is where the input was read from. It is used to resolve the
+    /// `INCLUDE` directives and to report errors. If `path` is `None`, the
+    /// included files are resolved relative to the current directory.
     pub fn parse(
         input: &str,
+        path: Option<&Path>,
         init_globals: &[String],
         override_schema: Option<&str>,
         span_registry: &mut Registry,
@@ -239,6 +288,17 @@ impl Template {
         let mut alloc = Allocator {
             override_schema: [None; 2],
             map: HashMap::new(),
+            variables_count: 0,
+            functions: HashMap::new(),
+            parameters: Vec::new(),
+            columns: None,
+            current_column: 0,
+            lag_rows: 0,
+            path: path.map(|p| p.display().to_string()),
+            include_stack: path
+                .map(|p| canonicalize(p).unwrap_or_else(|_| p.to_owned()))
+                .into_iter()
+                .collect(),
             span_registry,
         };
         if let Some(schema) = override_schema {
@@ -260,14 +320,10 @@ impl Template {
         let mut expected_child_name = None::<S<QName>>;
 
         for pair in pairs {
-            let span = pair.as_span();
             match pair.as_rule() {
-                Rule::EOI => {}
-                Rule::stmt => template.global_exprs.push(
-                    alloc
-                        .expr_binary_from_pairs(pair.into_inner())?
-                        .span(alloc.register(span)),
-                ),
+                Rule::EOI | Rule::include_directive | Rule::function_definition | Rule::stmt => {
+                    alloc.global_content_from_pair(pair, &mut template.global_exprs)?;
+                }
                 Rule::single_table => {
                     let table = alloc.table_from_pairs(pair.into_inner())?;
                     let table_name = table.name.unique_name();
@@ -302,7 +358,7 @@ impl Template {
             }
         }
 
-        template.variables_count = alloc.map.len();
+        template.variables_count = alloc.variables_count;
         Ok(template)
     }
 }
@@ -312,9 +368,33 @@ impl Template {
 struct Allocator<'a> {
     override_schema: [Option<&'a str>; 2],
     map: HashMap<String, usize>,
+    /// Total number of variables, including the hidden variables of function parameters.
+    variables_count: usize,
+    /// The user-defined functions.
+    functions: HashMap<String, UserFunction>,
+    /// The parameters of the function definition being parsed, and their variable indices.
+    parameters: Vec<(String, usize)>,
+    /// The names of all columns, if a table is being parsed.
+    columns: Option<Vec<String>>,
+    /// The index of the column being parsed.
+    current_column: usize,
+    /// The number of previous rows needed by `lag()` in the table being parsed.
+    lag_rows: usize,
+    /// The path of the file being parsed, if known.
+    path: Option<String>,
+    /// The canonical paths of the files being parsed, the innermost last.
+    include_stack: Vec<PathBuf>,
     span_registry: &'a mut Registry,
 }
 
+/// A user-defined function `FUNCTION f(a, b) := body`.
+struct UserFunction {
+    /// The variable indices storing the parameters.
+    parameters: Vec<usize>,
+    /// The function body.
+    body: S<Expr>,
+}
+
 #[derive(Default)]
 struct DependencyDirective {
     parent: S<QName>,
@@ -335,12 +415,84 @@ impl<'a> Allocator<'a> {
     fn allocate(&mut self, raw_var_name: &str) -> usize {
         let mut var_name = String::with_capacity(raw_var_name.len());
         unescape_into(&mut var_name, raw_var_name, false);
-        let count = self.map.len();
-        *self.map.entry(var_name).or_insert(count)
+        let count = &mut self.variables_count;
+        *self.map.entry(var_name).or_insert_with(|| {
+            *count += 1;
+            *count - 1
+        })
+    }
+
+    /// Allocates a hidden variable which cannot be referred by name.
+    fn allocate_hidden(&mut self) -> usize {
+        self.variables_count += 1;
+        self.variables_count - 1
     }
 
     fn register(&mut self, span: pest::Span<'_>) -> Span {
-        self.span_registry.register(span)
+        match &self.path {
+            Some(path) => self.span_registry.register_in_file(span, path),
+            None => self.span_registry.register(span),
+        }
+    }
+
+    /// Parses a global expression, function definition or `INCLUDE`
+    /// directive outside of the tables.
+    fn global_content_from_pair(
+        &mut self,
+        pair: Pair<'_, Rule>,
+        global_exprs: &mut Vec<S<Expr>>,
+    ) -> Result<(), S<Error>> {
+        let span = pair.as_span();
+        match pair.as_rule() {
+            Rule::EOI => {}
+            Rule::include_directive => self.include_from_pairs(pair.into_inner(), global_exprs)?,
+            Rule::function_definition => self.function_definition_from_pairs(pair.into_inner())?,
+            Rule::stmt => {
+                let expr = self.expr_binary_from_pairs(pair.into_inner())?;
+                global_exprs.push(expr.span(self.register(span)));
+            }
+            r => unreachable!("Unexpected rule {:?}", r),
+        }
+        Ok(())
+    }
+
+    /// Parses an `INCLUDE 'path'` directive, and appends the global
+    /// expressions and function definitions of the included file.
+    fn include_from_pairs(&mut self, pairs: Pairs<'_, Rule>, global_exprs: &mut Vec<S<Expr>>) -> Result<(), S<Error>> {
+        let pair = pairs.last().unwrap();
+        let span = self.register(pair.as_span());
+        let mut relative_path = String::new();
+        unescape_into(&mut relative_path, pair.as_str(), false);
+        let path = match self.include_stack.last().and_then(|p| p.parent()) {
+            Some(dir) => dir.join(relative_path),
+            None => PathBuf::from(relative_path),
+        };
+
+        let io_error = |action, source| {
+            Error::Io {
+                action,
+                path: path.clone(),
+                source,
+            }
+            .span(span)
+        };
+        let canonical_path = canonicalize(&path).map_err(|e| io_error("find included template", e))?;
+        if self.include_stack.contains(&canonical_path) {
+            return Err(Error::IncludeCycle { path }.span(span));
+        }
+        let input = read_to_string(&canonical_path).map_err(|e| io_error("read included template", e))?;
+        let display_path = path.display().to_string();
+        let pairs = TemplateParser::parse(Rule::include_file, &input)
+            .map_err(|e| Error::ParseTemplate(e.with_path(&display_path)).span(span))?;
+
+        let outer_path = self.path.replace(display_path);
+        self.include_stack.push(canonical_path);
+        let result = pairs
+            .into_iter()
+            .try_for_each(|pair| self.global_content_from_pair(pair, global_exprs));
+        self.include_stack.pop();
+        self.path = outer_path;
+        result
     }
 
     /// Creates a single table.
@@ -349,6 +501,8 @@ impl<'a> Allocator<'a> {
         let mut previous_end_line = 0;
         let mut column_name_range = 0..0;
         let mut column_name_is_expired = true;
+        let mut stmts = Vec::new();
+        let mut filter = None;
 
         for pair in pairs {
             let span = pair.as_span();
@@ -384,19 +538,78 @@ impl<'a> Allocator<'a> {
                     table.column_name_ranges.push(column_name_range);
                     column_name_is_expired = true;
                     column_name_range = 0..0;
-                    table.exprs.push(
-                        self.expr_binary_from_pairs(pair.into_inner())?
-                            .span(self.register(span)),
-                    );
+                    stmts.push(pair);
+                }
+                Rule::table_filter => {
+                    if filter.is_some() {
+                        return Err(Error::InvalidArguments(
+                            "a table can only have one WHERE or RETRY UNTIL directive".to_owned(),
+                        )
+                        .span(self.register(span)));
+                    }
+                    filter = Some(pair);
                 }
                 r => unreachable!("Unexpected rule {:?}", r),
             }
             previous_end_line = end_line;
         }
 
+        // all column names are known before parsing the expressions, so `col()` can tell a column
+        // not evaluated yet from an unknown one, and `lag()` can refer to any column.
+        let columns = table
+            .column_name_ranges
+            .iter()
+            .map(|range| {
+                let mut name = String::new();
+                unescape_into(&mut name, &table.content[range.clone()], false);
+                name
+            })
+            .collect();
+        self.columns = Some(columns);
+        self.lag_rows = 0;
+        for (index, pair) in stmts.into_iter().enumerate() {
+            self.current_column = index;
+            let span = pair.as_span();
+            table.exprs.push(
+                self.expr_binary_from_pairs(pair.into_inner())?
+                    .span(self.register(span)),
+            );
+        }
+        if let Some(pair) = filter {
+            // the predicate is evaluated after the whole row, so every column is available to `col()`.
+            self.current_column = table.exprs.len();
+            self.table_filter_from_pairs(&mut table, pair.into_inner())?;
+        }
+
+        self.columns = None;
+        table.lag_rows = self.lag_rows;
         Ok(table)
     }
 
+    /// Parses a table filter directive `WHERE p` or `RETRY UNTIL p MAX n`.
+    fn table_filter_from_pairs(&mut self, table: &mut Table, pairs: Pairs<'_, Rule>) -> Result<(), S<Error>> {
+        for pair in pairs {
+            let span = pair.as_span();
+            match pair.as_rule() {
+                Rule::kw_where | Rule::kw_until | Rule::kw_max => {}
+                Rule::kw_retry => table.retry_limit = Some(0),
+                Rule::expr => table.filter = Some(self.expr_from_pairs(pair.into_inner())?.span(self.register(span))),
+                Rule::number => match pair.as_str().parse() {
+                    Ok(n) if n > 0 => table.retry_limit = Some(n),
+                    _ => {
+                        return Err(Error::InvalidArguments(format!(
+                            "RETRY UNTIL limit must be a positive integer, got {}",
+                            pair.as_str()
+                        ))
+                        .span(self.register(span)))
+                    }
+                },
+                r => unreachable!("Unexpected rule {:?}", r),
+            }
+        }
+        Ok(())
+    }
+
     /// Parses a dependency directive.
     fn dependency_directive_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<DependencyDirective, S<Error>> {
         let mut res = DependencyDirective::default();
@@ -423,6 +636,139 @@ impl<'a> Allocator<'a> {
         Ok(res)
     }
 
+    /// Parses a user-defined function definition `FUNCTION f(a, b) := body`.
+    fn function_definition_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<(), S<Error>> {
+        let mut name = S::default();
+        let mut body = S::default();
+        let mut parameters = Vec::new();
+
+        for pair in pairs {
+            let span = pair.as_span();
+            match pair.as_rule() {
+                Rule::kw_function => {}
+                Rule::qname => {
+                    name = QName::from_pairs(pair.into_inner(), [None; 2]).span(self.register(span));
+                }
+                Rule::ident => {
+                    let mut param_name = String::with_capacity(span.as_str().len());
+                    unescape_into(&mut param_name, span.as_str(), false);
+                    if parameters.iter().any(|(n, _)| *n == param_name) {
+                        return Err(Error::InvalidArguments(format!("duplicate parameter '{}'", param_name))
+                            .span(self.register(span)));
+                    }
+                    let index = self.allocate_hidden();
+                    parameters.push((param_name, index));
+                }
+                Rule::stmt => {
+                    self.parameters = parameters;
+                    let result = self.expr_binary_from_pairs(pair.into_inner());
+                    parameters = mem::take(&mut self.parameters);
+                    body = result?.span(self.register(span));
+                }
+                r => unreachable!("Unexpected rule {:?}", r),
+            }
+        }
+
+        let unique_name = name.inner.unique_name();
+        if self.functions.contains_key(unique_name) || function_from_name(unique_name, None).is_ok() {
+            return Err(Error::DuplicateFunction {
+                name: name.inner.table_name(true).to_owned(),
+            }
+            .span(name.span));
+        }
+        self.functions.insert(
+            unique_name.to_owned(),
+            UserFunction {
+                parameters: parameters.into_iter().map(|(_, index)| index).collect(),
+                body,
+            },
+        );
+        Ok(())
+    }
+
+    /// Relocates the spans of an expression expanded from a user-defined
+    /// function called at `caller`, and replaces the parameter variables
+    /// according to `renames`.
+    fn relocate(&mut self, expr: &mut S<Expr>, caller: Span, renames: &[(usize, usize)]) {
+        let rename = |index: &mut usize| {
+            if let Some((_, new_index)) = renames.iter().find(|(old_index, _)| old_index == index) {
+                *index = *new_index;
+            }
+        };
+        expr.span = self.span_registry.register_expansion(expr.span, caller);
+        match &mut expr.inner {
+            Expr::RowNum | Expr::SubRowNum | Expr::CurrentTimestamp | Expr::Value(_) | Expr::GetColumn(_) => {}
+            Expr::GetVariable(index) => rename(index),
+            Expr::SetVariable(index, value) => {
+                rename(index);
+                self.relocate(value, caller, renames);
+            }
+            Expr::Lag { default: value, .. } => self.relocate(value, caller, renames),
+            Expr::Function { args, .. } => {
+                for arg in args {
+                    self.relocate(arg, caller, renames);
+                }
+            }
+            Expr::CaseValueWhen {
+                value,
+                conditions,
+                otherwise,
+            } => {
+                for e in value.iter_mut().chain(otherwise.iter_mut()) {
+                    self.relocate(e, caller, renames);
+                }
+                for (p, r) in conditions {
+                    self.relocate(p, caller, renames);
+                    self.relocate(r, caller, renames);
+                }
+            }
+        }
+    }
+
+    /// Expands a call to a user-defined function.
+    ///
+    /// Every call gets its own hidden parameter variables, so a nested call in
+    /// an argument cannot overwrite the parameters of the outer call. The
+    /// arguments are assigned to these variables first, so each argument is
+    /// evaluated exactly once, before evaluating the body.
+    fn expand_user_function(&mut self, name: &S<QName>, args: Vec<S<Expr>>) -> Result<Expr, S<Error>> {
+        let function = &self.functions[name.inner.unique_name()];
+        if function.parameters.len() != args.len() {
+            return Err(Error::InvalidArguments(format!(
+                "function {}() expects {} arguments, got {}",
+                name.inner.table_name(true),
+                function.parameters.len(),
+                args.len()
+            ))
+            .span(name.span));
+        }
+        let mut body = function.body.clone();
+        let renames = function.parameters.clone();
+        let renames = renames
+            .into_iter()
+            .map(|index| (index, self.allocate_hidden()))
+            .collect::<Vec<_>>();
+        let mut exprs = renames
+            .iter()
+            .zip(args)
+            .map(|((_, index), arg)| {
+                let span = arg.span;
+                Expr::SetVariable(*index, Box::new(arg)).span(span)
+            })
+            .collect::<Vec<_>>();
+        self.relocate(&mut body, name.span, &renames);
+        Ok(if exprs.is_empty() {
+            body.inner
+        } else {
+            exprs.push(body);
+            Expr::Function {
+                function: &functions::ops::Last,
+                name: ";".into(),
+                args: exprs,
+            }
+        })
+    }
+
     /// Creates a statement expression `a; b; c`.
     fn stmt_from_pairs(&mut self, mut pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
         self.expr_binary_from_pairs(pairs.next().unwrap().into_inner())
@@ -464,13 +810,22 @@ impl<'a> Allocator<'a> {
             let rule = pair.as_rule();
             let span = pair.as_span();
             match rule {
-                Rule::expr_bit_or | Rule::expr_bit_and | Rule::expr_and | Rule::expr_add | Rule::expr_mul => args.push(
+                Rule::expr_bit_or
+                | Rule::expr_bit_and
+                | Rule::expr_shift
+                | Rule::expr_and
+                | Rule::expr_add
+                | Rule::expr_mul => args.push(
                     self.expr_binary_from_pairs(pair.into_inner())?
                         .span(self.register(span)),
                 ),
                 Rule::expr_not => args.push(self.expr_not_from_pairs(pair.into_inner())?.span(self.register(span))),
                 Rule::expr_unary => args.push(self.expr_unary_from_pairs(pair.into_inner())?.span(self.register(span))),
                 Rule::expr => args.push(self.expr_from_pairs(pair.into_inner())?.span(self.register(span))),
+                Rule::predicate_in | Rule::predicate_between | Rule::predicate_like => {
+                    let lhs = args.pop().expect("predicate should follow an expression");
+                    args.push(self.expr_predicate_from_pairs(lhs, pair)?.span(self.register(span)));
+                }
                 Rule::kw_or
                 | Rule::kw_and
                 | Rule::is_not
@@ -481,6 +836,7 @@ impl<'a> Allocator<'a> {
                 | Rule::op_gt
                 | Rule::op_eq
                 | Rule::op_ne
+                | Rule::op_match
                 | Rule::op_add
                 | Rule::op_sub
                 | Rule::op_concat
@@ -489,11 +845,15 @@ impl<'a> Allocator<'a> {
                 | Rule::op_bit_and
                 | Rule::op_bit_or
                 | Rule::op_bit_xor
+                | Rule::op_shl
+                | Rule::op_shr
                 | Rule::op_semicolon => {
                     match op {
                         Some(o) if o != rule => {
+                            let (function, name) = function_from_rule(o);
                             args = vec![Expr::Function {
-                                function: function_from_rule(o),
+                                function,
+                                name: name.into(),
                                 args,
                             }
                             .span(self.register(span))];
@@ -507,8 +867,10 @@ impl<'a> Allocator<'a> {
         }
 
         Ok(if let Some(o) = op {
+            let (function, name) = function_from_rule(o);
             Expr::Function {
-                function: function_from_rule(o),
+                function,
+                name: name.into(),
                 args,
             }
         } else {
@@ -517,6 +879,41 @@ impl<'a> Allocator<'a> {
         })
     }
 
+    /// Creates a predicate expression `x [NOT] IN (...)`, `x [NOT] BETWEEN a AND b`
+    /// or `x [NOT] LIKE pattern [ESCAPE c]`.
+    fn expr_predicate_from_pairs(&mut self, lhs: S<Expr>, pair: Pair<'_, Rule>) -> Result<Expr, S<Error>> {
+        let rule = pair.as_rule();
+        let mut negated = false;
+        let mut args = vec![lhs];
+        for pair in pair.into_inner() {
+            let span = pair.as_span();
+            match pair.as_rule() {
+                Rule::kw_not => negated = true,
+                Rule::kw_in | Rule::kw_between | Rule::kw_and | Rule::kw_like | Rule::kw_escape => {}
+                Rule::expr => args.push(self.expr_from_pairs(pair.into_inner())?.span(self.register(span))),
+                Rule::expr_bit_or => args.push(
+                    self.expr_binary_from_pairs(pair.into_inner())?
+                        .span(self.register(span)),
+                ),
+                r => unreachable!("Unexpected rule {:?}", r),
+            }
+        }
+        let (function, name): (&'static dyn Function, _) = match (rule, negated) {
+            (Rule::predicate_in, false) => (&functions::ops::IN, "IN"),
+            (Rule::predicate_in, true) => (&functions::ops::NOT_IN, "NOT IN"),
+            (Rule::predicate_between, false) => (&functions::ops::BETWEEN, "BETWEEN"),
+            (Rule::predicate_between, true) => (&functions::ops::NOT_BETWEEN, "NOT BETWEEN"),
+            (Rule::predicate_like, false) => (&functions::ops::LIKE, "LIKE"),
+            (Rule::predicate_like, true) => (&functions::ops::NOT_LIKE, "NOT LIKE"),
+            (r, _) => unreachable!("Unexpected predicate rule {:?}", r),
+        };
+        Ok(Expr::Function {
+            function,
+            name: name.into(),
+            args,
+        })
+    }
+
     /// Creates a NOT expression `NOT NOT NOT x`.
     fn expr_not_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
         let mut has_not = false;
@@ -531,6 +928,7 @@ impl<'a> Allocator<'a> {
                     return Ok(if has_not {
                         Expr::Function {
                             function: &functions::ops::Not,
+                            name: "NOT".into(),
                             args: vec![expr.span(self.register(span))],
                         }
                     } else {
@@ -558,10 +956,16 @@ impl<'a> Allocator<'a> {
             Rule::expr_interval => self.expr_interval_from_pairs(pair.into_inner())?,
             Rule::expr_hex => self.expr_hex_from_pairs(pair.into_inner())?,
             Rule::expr_get_variable => self.expr_get_variable_from_pairs(pair.into_inner())?,
+            Rule::expr_column => self.expr_column_from_pairs(pair.into_inner())?,
+            Rule::expr_lag => self.expr_lag_from_pairs(pair.into_inner())?,
+            Rule::expr_running_sum => self.expr_running_sum_from_pairs(pair.into_inner())?,
+            Rule::expr_parameter => self.expr_parameter_from_pairs(pair.into_inner())?,
             Rule::expr_array => self.expr_array_from_pairs(pair.into_inner())?,
             Rule::expr_function => self.expr_function_from_pairs(pair.into_inner())?,
             Rule::expr_substring_function => self.expr_substring_from_pairs(pair.into_inner())?,
             Rule::expr_overlay_function => self.expr_overlay_from_pairs(pair.into_inner())?,
+            Rule::expr_position_function => self.expr_position_from_pairs(pair.into_inner())?,
+            Rule::expr_cast_function => self.expr_cast_from_pairs(pair.into_inner())?,
             Rule::expr_case_value_when => self.expr_case_value_when_from_pairs(pair.into_inner())?,
 
             Rule::number => match parse_number(pair.as_str()) {
@@ -579,27 +983,155 @@ impl<'a> Allocator<'a> {
         })
     }
 
+    /// Finds the index of a column of the table being parsed.
+    ///
+    /// If `is_earlier` is true, only the columns before the current one are
+    /// accepted.
+    fn find_column(&mut self, pair: &Pair<'_, Rule>, function: &str, is_earlier: bool) -> Result<usize, S<Error>> {
+        let span = self.register(pair.as_span());
+        let mut name = String::with_capacity(pair.as_str().len());
+        unescape_into(&mut name, pair.as_str(), false);
+        let columns = self.columns.as_ref().ok_or_else(|| {
+            Error::InvalidArguments(format!(
+                "{}() can only be used in the column expressions of a table",
+                function
+            ))
+            .span(span)
+        })?;
+        let candidates = if is_earlier {
+            &columns[..self.current_column]
+        } else {
+            columns
+        };
+        // searching from the end, so a repeated name refers to the closest column.
+        if let Some(index) = candidates.iter().rposition(|c| *c == name) {
+            Ok(index)
+        } else if columns.contains(&name) {
+            Err(Error::ColumnNotYetEvaluated { name }.span(span))
+        } else {
+            Err(Error::UnknownColumn { name }.span(span))
+        }
+    }
+
+    /// Creates a reference to an earlier column of the current row `col(x)`.
+    fn expr_column_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
+        let pair = pairs.last().unwrap();
+        Ok(Expr::GetColumn(self.find_column(&pair, "col", true)?))
+    }
+
+    /// Creates a reference to a column of a previous row `lag(x, n, default)`.
+    fn expr_lag_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
+        let mut column = 0;
+        let mut offset = 1;
+        let mut default = Expr::default().span(Span::default());
+
+        for pair in pairs {
+            let span = pair.as_span();
+            match pair.as_rule() {
+                Rule::kw_lag => {}
+                Rule::ident => column = self.find_column(&pair, "lag", false)?,
+                Rule::number => {
+                    offset = match pair.as_str().parse() {
+                        Ok(n) if n > 0 && n <= MAX_LAG_OFFSET => n,
+                        _ => {
+                            return Err(Error::InvalidArguments(format!(
+                                "lag() offset must be an integer between 1 and {}, got {}",
+                                MAX_LAG_OFFSET,
+                                pair.as_str()
+                            ))
+                            .span(self.register(span)))
+                        }
+                    };
+                }
+                Rule::expr => default = self.expr_from_pairs(pair.into_inner())?.span(self.register(span)),
+                r => unreachable!("Unexpected rule {:?}", r),
+            }
+        }
+
+        self.lag_rows = self.lag_rows.max(offset);
+        Ok(Expr::Lag {
+            column,
+            offset,
+            default: Box::new(default),
+        })
+    }
+
+    /// Creates a running sum expression `running_sum(x)`.
+    ///
+    /// This is expanded to `@sum := coalesce(@sum, 0) + coalesce(x, 0)` using a
+    /// hidden variable `@sum`.
+    fn expr_running_sum_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
+        let pair = pairs.last().unwrap();
+        let span = self.register(pair.as_span());
+        if self.columns.is_none() {
+            return Err(Error::InvalidArguments(
+                "running_sum() can only be used in the column expressions of a table".to_owned(),
+            )
+            .span(span));
+        }
+        let value = self.expr_from_pairs(pair.into_inner())?.span(span);
+        let index = self.allocate_hidden();
+        let coalesce_zero = |e: Expr| {
+            Expr::Function {
+                function: &functions::ops::Coalesce,
+                name: "coalesce".into(),
+                args: vec![e.span(span), Expr::Value(0.into()).span(span)],
+            }
+            .span(span)
+        };
+        let sum = Expr::Function {
+            function: &functions::ops::Arith::Add,
+            name: "+".into(),
+            args: vec![coalesce_zero(Expr::GetVariable(index)), coalesce_zero(value.inner)],
+        };
+        Ok(Expr::SetVariable(index, Box::new(sum.span(span))))
+    }
+
+    /// Creates a reference to a parameter of the user-defined function being defined.
+    fn expr_parameter_from_pairs(&mut self, mut pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
+        let pair = pairs.next().unwrap();
+        let mut name = String::with_capacity(pair.as_str().len());
+        unescape_into(&mut name, pair.as_str(), false);
+        if let Some((_, index)) = self.parameters.iter().find(|(n, _)| *n == name) {
+            Ok(Expr::GetVariable(*index))
+        } else {
+            Err(Error::UnknownIdentifier { name }.span(self.register(pair.as_span())))
+        }
+    }
+
     /// Creates a function call expression `x.y.z(a, b, c)`.
     fn expr_function_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
-        let mut function: &dyn Function = &functions::ops::Last;
+        use functions::string::Unit;
+
+        let mut name = S::default();
+        let mut unit = None;
         let mut args = Vec::new();
 
         for pair in pairs {
             let span = pair.as_span();
             match pair.as_rule() {
                 Rule::qname => {
-                    let q_name = QName::from_pairs(pair.into_inner(), [None; 2]);
-                    match function_from_name(q_name.unique_name()) {
-                        Ok(f) => function = f,
-                        Err(e) => return Err(e.span(self.register(span))),
-                    }
+                    name = QName::from_pairs(pair.into_inner(), [None; 2]).span(self.register(span));
                 }
+                Rule::kw_using => {}
+                Rule::kw_octets => unit = Some(Unit::Octets),
+                Rule::kw_characters => unit = Some(Unit::Characters),
                 Rule::expr => args.push(self.expr_from_pairs(pair.into_inner())?.span(self.register(span))),
                 r => unreachable!("Unexpected rule {:?}", r),
             }
         }
 
-        Ok(Expr::Function { function, args })
+        if unit.is_none() && self.functions.contains_key(name.inner.unique_name()) {
+            return self.expand_user_function(&name, args);
+        }
+        match function_from_name(name.inner.unique_name(), unit) {
+            Ok(function) => Ok(Expr::Function {
+                function,
+                name: name.inner.unique_name().to_owned().into(),
+                args,
+            }),
+            Err(e) => Err(e.span(name.span)),
+        }
     }
 
     /// Creates an array expression `ARRAY[a, b, c]`.
@@ -619,6 +1151,7 @@ impl<'a> Allocator<'a> {
 
         Ok(Expr::Function {
             function: &functions::array::Array,
+            name: "ARRAY".into(),
             args,
         })
     }
@@ -638,15 +1171,15 @@ impl<'a> Allocator<'a> {
 
     /// Creates any expression involving a unary operator `+x`, `-x`, `x[i]`, etc.
     fn expr_unary_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
-        let mut op_stack = Vec::<(&dyn Function, pest::Span<'_>)>::new();
+        let mut op_stack = Vec::<(&dyn Function, &'static str, pest::Span<'_>)>::new();
         let mut base = Expr::default();
         let mut base_span = pest::Span::new("", 0, 0).unwrap();
         for pair in pairs {
             let span = pair.as_span();
             match pair.as_rule() {
                 Rule::op_add => {}
-                Rule::op_sub => op_stack.push((&functions::ops::Neg, span)),
-                Rule::op_bit_not => op_stack.push((&functions::ops::BitNot, span)),
+                Rule::op_sub => op_stack.push((&functions::ops::Neg, "-", span)),
+                Rule::op_bit_not => op_stack.push((&functions::ops::BitNot, "~", span)),
                 Rule::expr_primary => {
                     base = self.expr_primary_from_pairs(pair.into_inner())?;
                     base_span = span;
@@ -654,6 +1187,7 @@ impl<'a> Allocator<'a> {
                 Rule::expr => {
                     base = Expr::Function {
                         function: &functions::array::Subscript,
+                        name: "[]".into(),
                         args: vec![
                             base.span(self.register(base_span)),
                             self.expr_from_pairs(pair.into_inner())?
@@ -662,13 +1196,19 @@ impl<'a> Allocator<'a> {
                     };
                     base_span = span;
                 }
+                Rule::sql_type => {
+                    let base_expr = base.span(self.register(base_span.clone()));
+                    base = self.expr_cast_from_type(base_expr, pair)?;
+                    base_span = pest::Position::span(&base_span.start_pos(), &span.end_pos());
+                }
                 r => unreachable!("Unexpected rule {:?}", r),
             }
         }
 
-        for (function, function_span) in op_stack.into_iter().rev() {
+        for (function, name, function_span) in op_stack.into_iter().rev() {
             base = Expr::Function {
                 function,
+                name: name.into(),
                 args: vec![base.span(self.register(base_span.clone()))],
             };
             base_span = pest::Position::span(&function_span.start_pos(), &base_span.end_pos());
@@ -718,16 +1258,19 @@ impl<'a> Allocator<'a> {
     /// Creates a `TIMESTAMP` expression.
     fn expr_timestamp_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
         let mut function: &dyn Function = &functions::time::Timestamp;
+        let mut name = "TIMESTAMP";
         for pair in pairs {
             match pair.as_rule() {
                 Rule::kw_timestamp => {}
                 Rule::kw_with | Rule::kw_time | Rule::kw_zone => {
                     function = &functions::time::TimestampWithTimeZone;
+                    name = "TIMESTAMP WITH TIME ZONE";
                 }
                 Rule::expr_primary => {
                     let span = pair.as_span();
                     return Ok(Expr::Function {
                         function,
+                        name: name.into(),
                         args: vec![self
                             .expr_primary_from_pairs(pair.into_inner())?
                             .span(self.register(span))],
@@ -740,6 +1283,75 @@ impl<'a> Allocator<'a> {
         unreachable!("Pairs exhausted without finding the inner expression");
     }
 
+    /// Creates a `CAST(x AS type)` expression.
+    fn expr_cast_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
+        let mut value = S::default();
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::kw_cast | Rule::kw_as => {}
+                Rule::expr => {
+                    let span = pair.as_span();
+                    value = self.expr_from_pairs(pair.into_inner())?.span(self.register(span));
+                }
+                Rule::sql_type => return self.expr_cast_from_type(value, pair),
+                r => unreachable!("Unexpected rule {:?}", r),
+            }
+        }
+
+        unreachable!("Pairs exhausted without finding the target type");
+    }
+
+    /// Creates an expression converting `value` into the SQL type `x::type`.
+    fn expr_cast_from_type(&mut self, value: S<Expr>, sql_type: Pair<'_, Rule>) -> Result<Expr, S<Error>> {
+        use functions::cast::Cast;
+
+        let type_span = self.register(sql_type.as_span());
+        let mut name = String::new();
+        let mut args = vec![value];
+        for pair in sql_type.into_inner() {
+            match pair.as_rule() {
+                Rule::sql_type_name | Rule::sql_type_suffix => {
+                    if !name.is_empty() {
+                        name.push(' ');
+                    }
+                    name.push_str(&pair.as_str().to_ascii_uppercase());
+                }
+                Rule::number => match parse_number(pair.as_str()) {
+                    Ok(v) => args.push(Expr::Value(v).span(self.register(pair.as_span()))),
+                    Err(e) => return Err(e.span(self.register(pair.as_span()))),
+                },
+                r => unreachable!("Unexpected rule {:?}", r),
+            }
+        }
+
+        let function: &'static Cast = match &*name {
+            "TINYINT" => &Cast::Int(8),
+            "SMALLINT" => &Cast::Int(16),
+            "MEDIUMINT" => &Cast::Int(24),
+            "INT" | "INTEGER" => &Cast::Int(32),
+            "BIGINT" | "SIGNED" | "SIGNED INTEGER" => &Cast::Int(64),
+            "TINYINT UNSIGNED" => &Cast::Unsigned(8),
+            "SMALLINT UNSIGNED" => &Cast::Unsigned(16),
+            "MEDIUMINT UNSIGNED" => &Cast::Unsigned(24),
+            "INT UNSIGNED" | "INTEGER UNSIGNED" => &Cast::Unsigned(32),
+            "UNSIGNED" | "UNSIGNED INTEGER" | "BIGINT UNSIGNED" => &Cast::Unsigned(64),
+            "DECIMAL" | "NUMERIC" => &Cast::Decimal,
+            "FLOAT" | "REAL" | "DOUBLE" | "DOUBLE PRECISION" => &Cast::Float,
+            "CHAR" | "VARCHAR" | "CHARACTER" | "CHARACTER VARYING" | "TEXT" | "STRING" => &Cast::Char,
+            "BINARY" | "VARBINARY" | "BLOB" | "BYTEA" | "BYTES" => &Cast::Binary,
+            "TIMESTAMP" | "DATETIME" => &Cast::Timestamp,
+            "DATE" => &Cast::Date,
+            "BOOLEAN" | "BOOL" => &Cast::Boolean,
+            "JSON" => &Cast::Json,
+            _ => return Err(Error::InvalidArguments(format!("unknown type {}", name)).span(type_span)),
+        };
+        Ok(Expr::Function {
+            function,
+            name: format!("CAST AS {}", name).into(),
+            args,
+        })
+    }
+
     /// Creates an `X` (hex) expression.
     fn expr_hex_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
         for pair in pairs {
@@ -749,6 +1361,7 @@ impl<'a> Allocator<'a> {
                     let span = pair.as_span();
                     return Ok(Expr::Function {
                         function: &functions::codec::DECODE_HEX,
+                        name: "X".into(),
                         args: vec![self
                             .expr_primary_from_pairs(pair.into_inner())?
                             .span(self.register(span))],
@@ -789,6 +1402,7 @@ impl<'a> Allocator<'a> {
 
         Ok(Expr::Function {
             function: &functions::ops::Arith::Mul,
+            name: "INTERVAL".into(),
             args: vec![expr, Expr::Value(Value::Interval(unit)).span(self.register(span))],
         })
     }
@@ -829,7 +1443,11 @@ impl<'a> Allocator<'a> {
         if let Some(length) = length {
             args.push(length);
         }
-        Ok(Expr::Function { function, args })
+        Ok(Expr::Function {
+            function,
+            name: "substring".into(),
+            args,
+        })
     }
 
     /// Creates an `overlay` function expression.
@@ -867,7 +1485,46 @@ impl<'a> Allocator<'a> {
         if let Some(length) = length {
             args.push(length);
         }
-        Ok(Expr::Function { function, args })
+        Ok(Expr::Function {
+            function,
+            name: "overlay".into(),
+            args,
+        })
+    }
+
+    /// Creates a `position` function expression.
+    fn expr_position_from_pairs(&mut self, pairs: Pairs<'_, Rule>) -> Result<Expr, S<Error>> {
+        use functions::string::{Position, Unit};
+
+        let mut function = &Position(Unit::Characters);
+        let mut needle = S::default();
+        let mut haystack = S::default();
+
+        for pair in pairs {
+            let rule = pair.as_rule();
+            match rule {
+                Rule::kw_position | Rule::kw_in | Rule::kw_using => {}
+                Rule::kw_octets => function = &Position(Unit::Octets),
+                Rule::kw_characters => function = &Position(Unit::Characters),
+                Rule::position_needle => {
+                    let span = pair.as_span();
+                    needle = self
+                        .expr_binary_from_pairs(pair.into_inner())?
+                        .span(self.register(span));
+                }
+                Rule::substring_input => {
+                    let span = pair.as_span();
+                    haystack = self.expr_group_from_pairs(pair.into_inner())?.span(self.register(span));
+                }
+                r => unreachable!("Unexpected rule {:?}", r),
+            }
+        }
+
+        Ok(Expr::Function {
+            function,
+            name: "position".into(),
+            args: vec![needle, haystack],
+        })
     }
 }
 
@@ -885,8 +1542,21 @@ fn parse_number(input: &str) -> Result<Value, Error> {
 }
 
 /// Obtains a function from its name.
-fn function_from_name(name: &str) -> Result<&'static dyn Function, Error> {
-    use functions::{array, codec, debug, ops, rand, string};
+///
+/// The `unit` is provided when the function call ends with a
+/// `USING CHARACTERS` or `USING OCTETS` clause.
+fn function_from_name(name: &str, unit: Option<functions::string::Unit>) -> Result<&'static dyn Function, Error> {
+    use functions::{array, codec, debug, json, ops, rand, string};
+
+    if let Some(function) = unit_function_from_name(name, unit.unwrap_or(string::Unit::Characters)) {
+        return Ok(function);
+    }
+    if unit.is_some() {
+        return Err(Error::InvalidArguments(format!(
+            "function {}() does not support the USING clause",
+            name
+        )));
+    }
 
     Ok(match name {
         "rand.regex" => &rand::Regex,
@@ -894,6 +1564,7 @@ fn function_from_name(name: &str) -> Result<&'static dyn Function, Error> {
         "rand.range_inclusive" => &rand::RangeInclusive,
         "rand.uniform" => &rand::Uniform,
         "rand.uniform_inclusive" => &rand::UniformInclusive,
+        "rand.decimal" => &rand::Decimal,
         "rand.zipf" => &rand::Zipf,
         "rand.log_normal" => &rand::LogNormal,
         "rand.bool" => &rand::Bool,
@@ -907,9 +1578,40 @@ fn function_from_name(name: &str) -> Result<&'static dyn Function, Error> {
         "round" => &ops::Round,
         "div" => &ops::Div,
         "mod" => &ops::Mod,
-        "char_length" | "character_length" => &string::CharLength,
-        "octet_length" => &string::OctetLength,
+        "abs" => &ops::ABS,
+        "floor" => &ops::FLOOR,
+        "ceil" | "ceiling" => &ops::CEIL,
+        "trunc" | "truncate" => &ops::TRUNC,
+        "sign" => &ops::SIGN,
+        "sqrt" => &ops::SQRT,
+        "cbrt" => &ops::CBRT,
+        "power" | "pow" => &ops::Power,
+        "exp" => &ops::EXP,
+        "ln" => &ops::LN,
+        "log2" => &ops::LOG2,
+        "log10" => &ops::LOG10,
+        "sin" => &ops::SIN,
+        "cos" => &ops::COS,
+        "tan" => &ops::TAN,
+        "asin" => &ops::ASIN,
+        "acos" => &ops::ACOS,
+        "atan" => &ops::ATAN,
+        "atan2" => &ops::Atan2,
+        "degrees" => &ops::DEGREES,
+        "radians" => &ops::RADIANS,
+        "pi" => &ops::Pi,
+        "upper" => &string::ChangeCase::Upper,
+        "lower" => &string::ChangeCase::Lower,
+        "initcap" => &string::ChangeCase::InitCap,
+        "repeat" => &string::Repeat,
+        "replace" => &string::Replace,
+        "split_part" => &string::SplitPart,
+        "array_to_string" => &string::ArrayToString,
+        "regexp_like" => &string::RegexpLike,
+        "regexp_replace" => &string::RegexpReplace,
         "coalesce" => &ops::Coalesce,
+        "nullif" => &ops::NullIf,
+        "if" => &ops::If,
         "generate_series" => &array::GenerateSeries,
         "debug.panic" => &debug::Panic,
         "from_hex" => &codec::DECODE_HEX,
@@ -917,32 +1619,78 @@ fn function_from_name(name: &str) -> Result<&'static dyn Function, Error> {
         "from_base64" | "from_base64url" => &codec::DECODE_BASE64,
         "to_base64" => &codec::ENCODE_BASE64,
         "to_base64url" => &codec::ENCODE_BASE64URL,
+        "md5" => &codec::MD5,
+        "sha1" => &codec::SHA1,
+        "sha256" => &codec::SHA256,
+        "sha512" => &codec::SHA512,
+        "hmac" => &codec::Hmac,
+        "crc32" => &codec::Crc32,
+        "xxhash64" => &codec::XxHash64,
+        "murmur3" => &codec::Murmur3,
+        "json_object" => &json::JsonObject,
+        "json_array" => &json::JsonArray,
+        "json_build" => &json::JsonBuild,
+        "to_json" => &json::ToJson,
         _ => return Err(Error::UnknownFunction),
     })
 }
 
+/// Obtains a string function which measures strings using the given unit.
+fn unit_function_from_name(name: &str, unit: functions::string::Unit) -> Option<&'static dyn Function> {
+    use functions::string::{self, Unit};
+
+    macro_rules! with_unit {
+        ($f:path) => {
+            match unit {
+                Unit::Characters => &$f(Unit::Characters),
+                Unit::Octets => &$f(Unit::Octets),
+            }
+        };
+    }
+
+    Some(match name {
+        "char_length" | "character_length" => match unit {
+            Unit::Characters => &string::CharLength,
+            Unit::Octets => &string::OctetLength,
+        },
+        "octet_length" => &string::OctetLength,
+        "trim" | "btrim" => with_unit!(string::Trim),
+        "ltrim" => with_unit!(string::LTrim),
+        "rtrim" => with_unit!(string::RTrim),
+        "lpad" => with_unit!(string::LPad),
+        "rpad" => with_unit!(string::RPad),
+        "reverse" => with_unit!(string::Reverse),
+        "string_to_array" => with_unit!(string::StringToArray),
+        "format" | "printf" => with_unit!(string::Format),
+        _ => return None,
+    })
+}
+
 /// Obtains a function from the parser rule.
-fn function_from_rule(rule: Rule) -> &'static dyn Function {
+fn function_from_rule(rule: Rule) -> (&'static dyn Function, &'static str) {
     match rule {
-        Rule::op_lt => &functions::ops::LT,
-        Rule::op_eq => &functions::ops::EQ,
-        Rule::op_gt => &functions::ops::GT,
-        Rule::op_le => &functions::ops::LE,
-        Rule::op_ne => &functions::ops::NE,
-        Rule::op_ge => &functions::ops::GE,
-        Rule::op_add => &functions::ops::Arith::Add,
-        Rule::op_sub => &functions::ops::Arith::Sub,
-        Rule::op_mul => &functions::ops::Arith::Mul,
-        Rule::op_float_div => &functions::ops::Arith::FloatDiv,
-        Rule::op_semicolon => &functions::ops::Last,
-        Rule::op_concat => &functions::string::Concat,
-        Rule::kw_is => &functions::ops::IS,
-        Rule::is_not => &functions::ops::IS_NOT,
-        Rule::kw_and => &functions::ops::AND,
-        Rule::kw_or => &functions::ops::OR,
-        Rule::op_bit_and => &functions::ops::Bitwise::And,
-        Rule::op_bit_or => &functions::ops::Bitwise::Or,
-        Rule::op_bit_xor => &functions::ops::Bitwise::Xor,
+        Rule::op_lt => (&functions::ops::LT, "<"),
+        Rule::op_eq => (&functions::ops::EQ, "="),
+        Rule::op_gt => (&functions::ops::GT, ">"),
+        Rule::op_le => (&functions::ops::LE, "<="),
+        Rule::op_ne => (&functions::ops::NE, "<>"),
+        Rule::op_ge => (&functions::ops::GE, ">="),
+        Rule::op_match => (&functions::ops::Match, "~"),
+        Rule::op_add => (&functions::ops::Arith::Add, "+"),
+        Rule::op_sub => (&functions::ops::Arith::Sub, "-"),
+        Rule::op_mul => (&functions::ops::Arith::Mul, "*"),
+        Rule::op_float_div => (&functions::ops::Arith::FloatDiv, "/"),
+        Rule::op_semicolon => (&functions::ops::Last, ";"),
+        Rule::op_concat => (&functions::string::Concat, "||"),
+        Rule::kw_is => (&functions::ops::IS, "IS"),
+        Rule::is_not => (&functions::ops::IS_NOT, "IS NOT"),
+        Rule::kw_and => (&functions::ops::AND, "AND"),
+        Rule::kw_or => (&functions::ops::OR, "OR"),
+        Rule::op_bit_and => (&functions::ops::Bitwise::And, "&"),
+        Rule::op_bit_or => (&functions::ops::Bitwise::Or, "|"),
+        Rule::op_bit_xor => (&functions::ops::Bitwise::Xor, "^"),
+        Rule::op_shl => (&functions::ops::Shift::Left, "<<"),
+        Rule::op_shr => (&functions::ops::Shift::Right, ">>"),
         r => unreachable!("Unexpected operator rule {:?}", r),
     }
 }
@@ -965,9 +1713,120 @@ fn test_parse_template_error() {
         "create table a (); {{ for each row of a generate 1 row of c }} create table b ();",
         "create table a (); {{ for each row of b generate 1 row of a }} create table b ();",
         "create table a (); {{ for each row of a generate (*) rows of b }} create table b ();",
+        "create table a ({{ x }});",
+        "{{ function f(x) := y }} create table a ();",
+        "{{ function f(x, x) := x }} create table a ();",
+        "{{ function f(x) := f(x) }} create table a ();",
+        "{{ function f() := 1 }} {{ function F() := 2 }} create table a ();",
+        "{{ function rand.range(a, b) := a }} create table a ();",
+        "{{ function f(x) := x }} create table a ({{ f(1, 2) }});",
+        "{{ function f(x) := x }} create table a ({{ x }});",
+        "create table a ({{ 1 }} {{ where true }} {{ where false }});",
+        "create table a ({{ 1 }} {{ retry until true max 0 }});",
+        "create table a ({{ 1 }} {{ retry until true }});",
+        "create table a ({{ 1 }} {{ where col(b) }});",
     ];
     for tc in &test_cases {
-        let res = Template::parse(tc, &[], None, &mut registry);
+        let res = Template::parse(tc, None, &[], None, &mut registry);
         assert!(res.is_err(), "unexpected for case {}:\n{:#?}", tc, res);
     }
 }
+
+#[test]
+fn test_column_reference_errors() {
+    let mut registry = Registry::default();
+    let test_cases = [
+        (
+            "create table a (x {{ col(y) }}, y {{ 1 }});",
+            "column 'y' is referenced before it is evaluated",
+        ),
+        (
+            "create table a (x {{ col(x) }});",
+            "column 'x' is referenced before it is evaluated",
+        ),
+        ("create table a (x {{ 1 }}, y {{ col(z) }});", "unknown column 'z'"),
+        (
+            "{{ col(x) }} create table a (x {{ 1 }});",
+            "col() can only be used in the column expressions of a table",
+        ),
+        ("create table a (x {{ lag(z) }});", "unknown column 'z'"),
+        (
+            "create table a (x {{ lag(x, 0) }});",
+            "lag() offset must be an integer between 1 and 65536, got 0",
+        ),
+        (
+            "create table a (x {{ lag(x, 65537) }});",
+            "lag() offset must be an integer between 1 and 65536, got 65537",
+        ),
+        (
+            "create table a (x {{ lag(x, 100000000000000000000) }});",
+            "lag() offset must be an integer between 1 and 65536, got 100000000000000000000",
+        ),
+        (
+            "{{ function f(v) := running_sum(v) }} create table a (x {{ f(1) }});",
+            "running_sum() can only be used in the column expressions of a table",
+        ),
+    ];
+    for (tc, expected) in &test_cases {
+        match Template::parse(tc, None, &[], None, &mut registry) {
+            Err(e) => assert_eq!(e.inner.to_string(), *expected, "case {}", tc),
+            Ok(t) => panic!("unexpected for case {}:\n{:#?}", tc, t),
+        }
+    }
+}
+
+#[test]
+fn test_user_function_error_span() {
+    let mut registry = Registry::default();
+    let template = Template::parse(
+        "{{ function f() := 1 + 'a' }}\n{{ function g() := f() }}\ncreate table a ({{ g() }});",
+        None,
+        &[],
+        None,
+        &mut registry,
+    )
+    .unwrap();
+    let ctx = crate::eval::CompileContext::new(template.variables_count);
+    let expr = template.tables[0].exprs[0].clone();
+    let err = ctx.compile(expr).unwrap_err();
+    let description = registry.describe(&err);
+    assert_eq!(description.matches("Called from:").count(), 2, "{}", description);
+    assert!(
+        description.contains("1 | {{ function f() := 1 + 'a' }}"),
+        "{}",
+        description
+    );
+    assert!(
+        description.contains("3 | create table a ({{ g() }});"),
+        "{}",
+        description
+    );
+}
+
+#[test]
+fn test_include() {
+    use std::fs::write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("main.sql");
+    write(dir.path().join("a.sql"), "{{ include 'sub/b.sql' }}").unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    write(dir.path().join("sub/b.sql"), "{{ function f() := 1 + 'a' }}").unwrap();
+    write(dir.path().join("sub/c.sql"), "{{ include '../d.sql' }}").unwrap();
+    write(dir.path().join("d.sql"), "{{ include 'sub/c.sql' }}").unwrap();
+
+    let mut registry = Registry::default();
+    let input = "{{ include 'a.sql' }}\ncreate table t ({{ f() }});";
+    let template = Template::parse(input, Some(&path), &[], None, &mut registry).unwrap();
+    let ctx = crate::eval::CompileContext::new(template.variables_count);
+    let err = ctx.compile(template.tables[0].exprs[0].clone()).unwrap_err();
+    let description = registry.describe(&err);
+    assert!(description.contains("sub/b.sql:1:24"), "{}", description);
+    assert!(description.contains("main.sql:2:20"), "{}", description);
+
+    let input = "{{ include 'd.sql' }}\ncreate table t ();";
+    match Template::parse(input, Some(&path), &[], None, &mut registry) {
+        Err(e) => assert!(matches!(e.inner, Error::IncludeCycle { .. }), "{:?}", e),
+        Ok(t) => panic!("unexpected {:#?}", t),
+    }
+}
@@ -0,0 +1,154 @@
+//! Random number generators and their seeds.
+
+use crate::error::Error;
+use data_encoding::{DecodeError, DecodeKind, HEXLOWER_PERMISSIVE};
+use dyn_clone::DynClone;
+use rand::{
+    distributions::{Distribution, Standard},
+    rngs::mock::StepRng,
+    Rng, RngCore, SeedableRng,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// Random number generator (RNG) seed.
+///
+/// This is represented as a 64-digit hex string and is supposed to seed the
+/// HC-128 RNG only.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Seed(<rand_hc::Hc128Rng as SeedableRng>::Seed);
+
+impl FromStr for Seed {
+    type Err = DecodeError;
+
+    /// Parses a 64-digit hex string into an RNG seed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut seed = Self::default();
+        if HEXLOWER_PERMISSIVE.decode_len(s.len())? != seed.0.len() {
+            return Err(DecodeError {
+                position: s.len(),
+                kind: DecodeKind::Length,
+            });
+        }
+        match HEXLOWER_PERMISSIVE.decode_mut(s.as_bytes(), &mut seed.0) {
+            Ok(_) => Ok(seed),
+            Err(e) => Err(e.error),
+        }
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&HEXLOWER_PERMISSIVE.encode(&self.0))
+    }
+}
+
+impl Distribution<Seed> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Seed {
+        Seed(self.sample(rng))
+    }
+}
+
+impl Serialize for Seed {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        HEXLOWER_PERMISSIVE.encode(&self.0).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Seed {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // FIXME: support deserialize to both `&'de str` and `String`.
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Seed {
+    /// Constructs a RNG from this seed.
+    pub fn make_rng(&self) -> rand_hc::Hc128Rng {
+        rand_hc::Hc128Rng::from_seed(self.0)
+    }
+}
+
+/// Names of random number generators supported by `dbgen`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "cli", serde(rename_all = "lowercase"))]
+pub enum RngName {
+    /// ChaCha12
+    ChaCha12,
+    /// ChaCha20
+    #[cfg_attr(feature = "cli", serde(alias = "chacha"))]
+    ChaCha20,
+    /// HC-128
+    Hc128,
+    /// ISAAC
+    Isaac,
+    /// ISAAC-64
+    Isaac64,
+    /// Xorshift
+    XorShift,
+    /// PCG32
+    Pcg32,
+    /// Mock RNG which steps by a constant.
+    Step,
+}
+
+impl FromStr for RngName {
+    type Err = Error;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "chacha12" => Self::ChaCha12,
+            "chacha" | "chacha20" => Self::ChaCha20,
+            "hc128" => Self::Hc128,
+            "isaac" => Self::Isaac,
+            "isaac64" => Self::Isaac64,
+            "xorshift" => Self::XorShift,
+            "pcg32" => Self::Pcg32,
+            "step" => Self::Step,
+            _ => {
+                return Err(Error::UnsupportedCliParameter {
+                    kind: "RNG",
+                    value: name.to_owned(),
+                });
+            }
+        })
+    }
+}
+
+impl RngName {
+    /// Creates an RNG engine given the name. The RNG engine instance will be seeded from `src`.
+    pub(crate) fn create(self, src: &mut rand_hc::Hc128Rng) -> Box<dyn RngCoreClone + Send> {
+        match self {
+            Self::ChaCha12 => Box::new(rand_chacha::ChaCha12Rng::from_seed(src.gen())),
+            Self::ChaCha20 => Box::new(rand_chacha::ChaCha20Rng::from_seed(src.gen())),
+            Self::Hc128 => Box::new(rand_hc::Hc128Rng::from_seed(src.gen())),
+            Self::Isaac => Box::new(rand_isaac::IsaacRng::from_seed(src.gen())),
+            Self::Isaac64 => Box::new(rand_isaac::Isaac64Rng::from_seed(src.gen())),
+            Self::XorShift => Box::new(rand_xorshift::XorShiftRng::from_seed(src.gen())),
+            Self::Pcg32 => Box::new(rand_pcg::Pcg32::from_seed(src.gen())),
+            Self::Step => Box::new(StepRng::new(src.next_u64(), src.next_u64() | 1)),
+        }
+    }
+}
+
+/// A cloneable `RngCore` trait object.
+pub trait RngCoreClone: RngCore + DynClone + Sync + Send {}
+
+dyn_clone::clone_trait_object!(RngCoreClone);
+
+impl RngCoreClone for rand_chacha::ChaCha12Rng {}
+
+impl RngCoreClone for rand_chacha::ChaCha20Rng {}
+
+impl RngCoreClone for rand_hc::Hc128Rng {}
+
+impl RngCoreClone for rand_isaac::IsaacRng {}
+
+impl RngCoreClone for rand_isaac::Isaac64Rng {}
+
+impl RngCoreClone for rand_xorshift::XorShiftRng {}
+
+impl RngCoreClone for rand_pcg::Pcg32 {}
+
+impl RngCoreClone for StepRng {}
@@ -15,27 +15,66 @@ impl Default for Span {
 }
 
 /// Registry of spans.
+///
+/// Each entry also records the span of the user-defined function call which
+/// the entry was expanded from, or the default span if it is not part of an
+/// expansion.
 #[derive(Default, Debug, Clone)]
-pub struct Registry(Vec<Error<Rule>>);
+pub struct Registry(Vec<(Error<Rule>, Span)>);
 
 impl Registry {
     /// Registers a span represented by a Pest span.
     pub fn register(&mut self, span: pest::Span<'_>) -> Span {
         let res = Span(self.0.len());
-        self.0.push(Error::new_from_span(
-            ErrorVariant::CustomError { message: "".to_owned() },
-            span,
+        self.0.push((
+            Error::new_from_span(ErrorVariant::CustomError { message: "".to_owned() }, span),
+            Span::default(),
         ));
         res
     }
 
+    /// Registers a span represented by a Pest span, which was parsed from the
+    /// file at `path`.
+    pub fn register_in_file(&mut self, span: pest::Span<'_>, path: &str) -> Span {
+        let res = Span(self.0.len());
+        self.0.push((
+            Error::new_from_span(ErrorVariant::CustomError { message: String::new() }, span).with_path(path),
+            Span::default(),
+        ));
+        res
+    }
+
+    /// Registers a copy of an existing span, which is expanded from a
+    /// user-defined function called at `caller`.
+    ///
+    /// If the span is itself expanded from another call, that call is
+    /// relocated as well, so the whole call chain can be reported.
+    pub fn register_expansion(&mut self, span: Span, caller: Span) -> Span {
+        let (error, inner_caller) = match self.0.get(span.0) {
+            Some(entry) => entry.clone(),
+            None => return caller,
+        };
+        let caller = if inner_caller == Span::default() {
+            caller
+        } else {
+            self.register_expansion(inner_caller, caller)
+        };
+        let res = Span(self.0.len());
+        self.0.push((error, caller));
+        res
+    }
+
     /// Describes a spanned error as a human-readable string.
     pub fn describe<E: std::error::Error + 'static>(&self, err: &S<E>) -> String {
         use std::fmt::Write;
         let mut buf = format!("Error: {}\n", err.inner);
 
-        if let Some(e) = self.0.get(err.span.0) {
+        if let Some((e, mut caller)) = self.0.get(err.span.0) {
             writeln!(&mut buf, "{}\n", e).unwrap();
+            while let Some((e, next_caller)) = self.0.get(caller.0) {
+                writeln!(&mut buf, "Called from:\n{}\n", e).unwrap();
+                caller = *next_caller;
+            }
         }
 
         let mut err: &(dyn std::error::Error + 'static) = &err.inner;
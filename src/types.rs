@@ -0,0 +1,234 @@
+//! Static types of values, for checking a template before generating data.
+
+use crate::{
+    error::Error,
+    span::{Span, SpanExt, S},
+    value::Value,
+};
+use std::{fmt, ops::BitOr};
+
+/// A set of value types, representing all possible types of an expression.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Types(u8);
+
+impl Types {
+    /// The NULL value.
+    pub const NULL: Self = Self(1);
+    /// Numbers (including booleans).
+    pub const NUMBER: Self = Self(2);
+    /// Strings and byte strings.
+    pub const BYTES: Self = Self(4);
+    /// Timestamps.
+    pub const TIMESTAMP: Self = Self(8);
+    /// Time intervals.
+    pub const INTERVAL: Self = Self(16);
+    /// Arrays.
+    pub const ARRAY: Self = Self(32);
+    /// JSON values.
+    pub const JSON: Self = Self(64);
+    /// All types.
+    pub const ANY: Self = Self(127);
+
+    /// Nullable numbers.
+    pub const NULLABLE_NUMBER: Self = Self::NULL.or(Self::NUMBER);
+    /// Nullable booleans, which are represented as numbers.
+    pub const NULLABLE_BOOL: Self = Self::NULLABLE_NUMBER;
+    /// Nullable strings.
+    pub const NULLABLE_BYTES: Self = Self::NULL.or(Self::BYTES);
+
+    /// Returns the union of two sets of types.
+    #[must_use]
+    pub const fn or(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns whether the two sets of types have any types in common.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns the type of a value.
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => Self::NULL,
+            Value::Number(_) => Self::NUMBER,
+            Value::Bytes(_) => Self::BYTES,
+            Value::Timestamp(..) => Self::TIMESTAMP,
+            Value::Interval(_) => Self::INTERVAL,
+            Value::Array(_) => Self::ARRAY,
+            Value::Json(_) => Self::JSON,
+        }
+    }
+}
+
+impl BitOr for Types {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        self.or(other)
+    }
+}
+
+impl fmt::Display for Types {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [&str; 7] = ["NULL", "number", "string", "timestamp", "interval", "array", "JSON"];
+
+        if *self == Self::ANY {
+            return f.write_str("any type");
+        }
+        let names = NAMES
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.0 & (1 << i) != 0)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        match names.split_last() {
+            None => f.write_str("no type"),
+            Some((last, [])) => f.write_str(last),
+            Some((last, init)) => write!(f, "{} or {}", init.join(", "), last),
+        }
+    }
+}
+
+/// The signature of a function, i.e. the types of its parameters and result.
+#[derive(Copy, Clone, Debug)]
+pub struct Signature {
+    /// The accepted types of each parameter.
+    params: &'static [Types],
+    /// The number of required parameters.
+    required: usize,
+    /// Whether the last parameter can be repeated any number of times.
+    variadic: bool,
+    /// The possible types of the result.
+    result: Types,
+}
+
+impl Signature {
+    /// The signature accepting any number of arguments of any type.
+    pub const ANY: Self = Self::new(&[Types::ANY], Types::ANY).optional_after(0).variadic();
+
+    /// Creates a new signature where all parameters are required.
+    pub const fn new(params: &'static [Types], result: Types) -> Self {
+        Self {
+            params,
+            required: params.len(),
+            variadic: false,
+            result,
+        }
+    }
+
+    /// Makes all parameters after the first `required` ones optional.
+    #[must_use]
+    pub const fn optional_after(mut self, required: usize) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Allows the last parameter to be repeated any number of times.
+    #[must_use]
+    pub const fn variadic(mut self) -> Self {
+        self.variadic = true;
+        self
+    }
+
+    /// Returns the possible types of the result.
+    pub fn result(&self) -> Types {
+        self.result
+    }
+
+    /// Checks whether the number and types of the arguments match this
+    /// signature.
+    pub fn check(&self, span: Span, args: &[S<Types>]) -> Result<(), S<Error>> {
+        if args.len() < self.required {
+            return Err(Error::NotEnoughArguments.span(span));
+        }
+        if !self.variadic && args.len() > self.params.len() {
+            return Err(Error::TooManyArguments {
+                expected: self.params.len(),
+                found: args.len(),
+            }
+            .span(span));
+        }
+        for (arg, expected) in args
+            .iter()
+            .zip(self.params.iter().chain(self.params.last().into_iter().cycle()))
+        {
+            if !arg.inner.intersects(*expected) {
+                return Err(Error::TypeMismatch {
+                    expected: *expected,
+                    found: arg.inner,
+                }
+                .span(arg.span));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_display_types() {
+    assert_eq!(Types::NUMBER.to_string(), "number");
+    assert_eq!(Types::NULLABLE_BOOL.to_string(), "NULL or number");
+    assert_eq!(
+        (Types::NUMBER | Types::TIMESTAMP | Types::INTERVAL).to_string(),
+        "number, timestamp or interval"
+    );
+    assert_eq!(Types::ANY.to_string(), "any type");
+}
+
+#[test]
+fn test_check_signature() {
+    let span = Span::default();
+    let signature = Signature::new(&[Types::BYTES, Types::NUMBER], Types::BYTES).optional_after(1);
+    let check = |args: &[Types]| {
+        let args = args.iter().map(|t| (*t).no_span()).collect::<Vec<_>>();
+        signature.check(span, &args).map_err(|e| e.inner.to_string())
+    };
+    assert_eq!(check(&[Types::BYTES]), Ok(()));
+    assert_eq!(check(&[Types::BYTES | Types::NULL, Types::ANY]), Ok(()));
+    assert_eq!(check(&[]), Err("not enough arguments".to_owned()));
+    assert_eq!(
+        check(&[Types::BYTES, Types::NUMBER, Types::NUMBER]),
+        Err("expected at most 2 arguments, found 3".to_owned())
+    );
+    assert_eq!(check(&[Types::NUMBER]), Err("expected string, found number".to_owned()));
+}
+
+#[test]
+fn test_compile_type_errors() {
+    use crate::{eval::CompileContext, parser::Template, span::Registry};
+
+    let test_cases = [
+        (
+            "rand.regex('[a-z]') + 1",
+            Some("expected number, timestamp or interval, found string"),
+        ),
+        (
+            "CASE WHEN rownum > 1 THEN upper(rownum) END",
+            Some("expected string, found number"),
+        ),
+        (
+            "CASE WHEN rand.regex('x') THEN 1 END",
+            Some("expected NULL or number, found string"),
+        ),
+        (
+            "rand.range(1, rownum, 3)",
+            Some("expected at most 2 arguments, found 3"),
+        ),
+        ("lower(rand.regex('[A-Z]')) || rownum", None),
+        ("coalesce(@a, 1) + rownum", None),
+        ("CASE WHEN rownum > 1 THEN 'a' END || rownum", None),
+    ];
+    for (expr, expected) in &test_cases {
+        let mut registry = Registry::default();
+        let input = format!("create table a ({{{{ {} }}}});", expr);
+        let template = Template::parse(&input, None, &[], None, &mut registry).unwrap();
+        let ctx = CompileContext::new(template.variables_count);
+        let result = ctx.compile(template.tables[0].exprs[0].clone());
+        assert_eq!(
+            result.map(|_| ()).map_err(|e| e.inner.to_string()).err().as_deref(),
+            *expected,
+            "case {}",
+            expr
+        );
+    }
+}
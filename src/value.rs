@@ -13,6 +13,7 @@ use tzfile::ArcTz;
 use crate::{
     bytes::ByteString,
     error::Error,
+    json::Json,
     number::{Number, NumberError},
 };
 
@@ -34,6 +35,8 @@ pub enum Value {
     Interval(i64),
     /// An array of values.
     Array(Arc<[Value]>),
+    /// A JSON value.
+    Json(Arc<Json>),
 }
 
 impl Default for Value {
@@ -114,6 +117,31 @@ impl Value {
         Self::Number(Number::from_finite_f64(v))
     }
 
+    /// Makes cloning this value cheap. This is used for values which are
+    /// cloned for every row, i.e. constants and variables.
+    pub(crate) fn share(&mut self) {
+        if let Self::Bytes(bytes) = self {
+            bytes.share();
+        }
+    }
+
+    /// Checks whether two values are exactly the same, so that any function
+    /// would produce the same result given either value.
+    ///
+    /// Unlike `==`, numbers of different representations are distinguished.
+    /// JSON values are only considered identical when they are shared.
+    pub(crate) fn is_identical(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.is_identical(*b),
+            (Self::Array(a), Self::Array(b)) => {
+                Arc::ptr_eq(a, b) || a.len() == b.len() && a.iter().zip(&**b).all(|(x, y)| x.is_identical(y))
+            }
+            (Self::Json(a), Self::Json(b)) => Arc::ptr_eq(a, b),
+            (Self::Number(_) | Self::Array(_) | Self::Json(_), _) => false,
+            _ => self == other,
+        }
+    }
+
     /// Compares two values using the rules common among SQL implementations.
     ///
     /// * Comparing with NULL always return `None`.
@@ -146,7 +174,7 @@ impl Value {
             Self::Null => Ordering::Equal,
             Self::Number(a) => a.sql_sign(),
             Self::Bytes(a) => true.cmp(&a.is_empty()),
-            Self::Timestamp(..) => Ordering::Greater,
+            Self::Timestamp(..) | Self::Json(_) => Ordering::Greater,
             Self::Interval(a) => a.cmp(&0),
             Self::Array(a) => true.cmp(&a.is_empty()),
         }
@@ -265,6 +293,7 @@ impl Value {
                     write!(res, "{}", tz.from_utc_datetime(timestamp).format(TIMESTAMP_FORMAT)).unwrap();
                 }
                 Self::Interval(interval) => write!(res, "INTERVAL {} MICROSECOND", interval).unwrap(),
+                Self::Json(json) => write!(res, "{}", json).unwrap(),
                 Self::Array(_) => {
                     return Err(Error::InvalidArguments(
                         "cannot concatenate arrays using || operator".to_owned(),
@@ -353,6 +382,18 @@ impl TryFrom<Value> for Number {
     }
 }
 
+impl TryFrom<Value> for Option<Number> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Null => Ok(None),
+            Value::Number(n) => Ok(Some(n)),
+            _ => Err(value.to_unexpected_value_type_error("nullable number")),
+        }
+    }
+}
+
 impl TryFrom<Value> for ByteString {
     type Error = Error;
 
@@ -442,6 +483,12 @@ impl From<EncodedString> for Value {
     }
 }
 
+impl From<Json> for Value {
+    fn from(json: Json) -> Self {
+        Self::Json(Arc::new(json))
+    }
+}
+
 impl<T: Into<Value>> From<Option<T>> for Value {
     fn from(value: Option<T>) -> Self {
         value.map_or(Self::Null, T::into)
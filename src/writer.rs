@@ -7,7 +7,7 @@ use crate::{
     span::{ResultExt, S},
     value::Value,
 };
-use std::{convert::TryInto, mem};
+use std::{collections::VecDeque, convert::TryInto, mem};
 
 /// A generic writer which could accept rows of values.
 pub trait Writer {
@@ -50,6 +50,11 @@ struct TableState<W: Writer> {
     /// needed to be written or not. This member will be reset to `true` after calling
     /// [`Env::write_trailer()`].
     empty: bool,
+    /// The previous rows written in this environment, the most recent first. At most
+    /// `table.lag_rows` rows are kept, for use by `lag()`.
+    history: VecDeque<Vec<Value>>,
+    /// The values of the current row. The vector is reused for every row.
+    values: Vec<Value>,
 }
 
 /// An environment for writing rows from multiple tables generated from a single template.
@@ -79,6 +84,8 @@ impl<W: Writer> Env<W> {
                         writer,
                         fresh: true,
                         empty: true,
+                        history: VecDeque::new(),
+                        values: Vec::new(),
                     })
                 })
                 .collect::<Result<_, _>>()?,
@@ -94,15 +101,21 @@ impl<W: Writer> Env<W> {
     fn write_one_row(&mut self, format: &dyn Format, table_index: usize) -> Result<(), S<Error>> {
         let table = &mut self.tables[table_index];
 
+        self.state.swap_history(&mut table.history);
+        let accepted = table.table.eval_row(&mut self.state, &mut table.values);
+        self.state.swap_history(&mut table.history);
+        if !accepted? {
+            // the row is dropped by WHERE, together with all its derived rows.
+            return Ok(());
+        }
+
         if mem::take(&mut table.empty) {
             table.writer.write_header(format, &table.schema)
         } else {
             table.writer.write_row_separator(format)
         }?;
 
-        let values = table.table.row.eval(&mut self.state)?;
-
-        for (col_index, (column, value)) in table.schema.column_names().zip(&values).enumerate() {
+        for (col_index, (column, value)) in table.schema.column_names().zip(&table.values).enumerate() {
             if col_index != 0 {
                 table.writer.write_value_separator(format)?;
             }
@@ -110,6 +123,16 @@ impl<W: Writer> Env<W> {
             table.writer.write_value(format, value)?;
         }
 
+        if table.table.lag_rows > 0 {
+            // recycle the oldest row as the buffer of the next row.
+            let buffer = if table.history.len() == table.table.lag_rows {
+                table.history.pop_back().unwrap_or_default()
+            } else {
+                Vec::with_capacity(table.values.len())
+            };
+            table.history.push_front(mem::replace(&mut table.values, buffer));
+        }
+
         if table.table.derived.is_empty() {
             return Ok(());
         }
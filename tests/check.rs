@@ -10,6 +10,7 @@ use std::{
     ffi::OsStr,
     fs::{read, read_dir, remove_file, File},
     path::Path,
+    process::Command,
     str::from_utf8,
 };
 use tempfile::tempdir;
@@ -19,6 +20,28 @@ fn run_test() {
     main().unwrap();
 }
 
+#[test]
+fn explain_test() -> Result<(), Box<dyn Error>> {
+    let data_dir = Path::new(file!()).with_file_name("data");
+    for child_dir in read_dir(data_dir)? {
+        let child_path = child_dir?.path();
+        let expected_path = child_path.join("explain.txt");
+        if !expected_path.exists() {
+            continue;
+        }
+
+        eprintln!("Explaining {}...", child_path.display());
+        let output = Command::new(env!("CARGO_BIN_EXE_dbgen"))
+            .arg("--explain")
+            .arg("-i")
+            .arg(child_path.join("template.sql"))
+            .output()?;
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(from_utf8(&output.stdout)?, from_utf8(&read(expected_path)?)?);
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let out_dir = tempdir()?;
 
@@ -0,0 +1,32 @@
+INSERT INTO result SET
+int_a = 3,
+int_b = -3,
+int_c = 42,
+int_d = 12,
+int_e = NULL,
+int_f = 1,
+int_g = -8388608,
+int_h = 7,
+unsigned_a = 18446744073709551615,
+unsigned_b = 4294967295,
+unsigned_c = 255,
+unsigned_d = 12,
+decimal_a = 3.14,
+decimal_b = 123.456,
+decimal_c = 2.33333333,
+decimal_d = 0.3,
+float_a = 2500.0,
+float_b = 7.0,
+char_a = '123',
+char_b = 'héll',
+char_c = '1.5',
+char_d = '2020-01-02 03:04:05',
+binary_a = X'68C3',
+timestamp_a = '2020-01-02 03:04:05',
+timestamp_b = '2020-01-02 00:00:00',
+date_a = '2020-01-02 00:00:00',
+bool_a = 1,
+bool_b = 0,
+json_a = '[1,2]',
+chain_a = '-5x';
+
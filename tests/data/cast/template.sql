@@ -0,0 +1,32 @@
+create table result (
+    int_a {{ CAST(3.7 AS INT) }}
+    int_b {{ CAST(-3.7 AS BIGINT) }}
+    int_c {{ CAST(' 42 ' AS INTEGER) }}
+    int_d {{ '12.9'::int }}
+    int_e {{ CAST(NULL AS INT) }}
+    int_f {{ CAST(TRUE AS SMALLINT) }}
+    int_g {{ CAST(-8388608 AS MEDIUMINT) }}
+    int_h {{ CAST('7' AS SIGNED INTEGER) }}
+    unsigned_a {{ CAST(18446744073709551615 AS UNSIGNED) }}
+    unsigned_b {{ CAST(4294967295 AS INT UNSIGNED) }}
+    unsigned_c {{ 255::tinyint unsigned }}
+    unsigned_d {{ CAST(12.9 AS UNSIGNED INTEGER) }}
+    decimal_a {{ CAST(3.14159 AS DECIMAL(10, 2)) }}
+    decimal_b {{ CAST('123.456' AS NUMERIC(6, 3)) }}
+    decimal_c {{ CAST(7 AS DECIMAL(5, 2)) / 3 }}
+    decimal_d {{ CAST('0.1' AS DECIMAL(3, 1)) + CAST('0.2' AS DECIMAL(3, 1)) }}
+    float_a {{ CAST('2.5e3' AS DOUBLE) }}
+    float_b {{ 7::float }}
+    char_a {{ CAST(123 AS VARCHAR) }}
+    char_b {{ CAST('héllo wörld' AS VARCHAR(4)) }}
+    char_c {{ CAST(1.5 AS CHAR(10)) }}
+    char_d {{ CAST(TIMESTAMP '2020-01-02 03:04:05' AS TEXT) }}
+    binary_a {{ CAST('héllo' AS BINARY(2)) }}
+    timestamp_a {{ CAST('2020-01-02 03:04:05' AS TIMESTAMP) }}
+    timestamp_b {{ '2020-01-02'::timestamp }}
+    date_a {{ CAST(TIMESTAMP '2020-01-02 03:04:05' AS DATE) }}
+    bool_a {{ CAST('yes' AS BOOLEAN) }}
+    bool_b {{ CAST(0.0 AS BOOL) }}
+    json_a {{ CAST(ARRAY[1, 2] AS JSON) }}
+    chain_a {{ (-'5'::int)::text || 'x' }}
+);
@@ -0,0 +1,35 @@
+INSERT INTO c VALUES
+(1, 1, 'gkf'),
+(1, 2, 'rpl'),
+(2, 1, 'fur'),
+(2, 2, 'waw'),
+(3, 1, 'jdq'),
+(3, 2, 'lyy');
+INSERT INTO c VALUES
+(4, 1, 'fxu'),
+(4, 2, 'dkn'),
+(5, 1, 'ekh'),
+(5, 2, 'pum'),
+(6, 1, 'ldj'),
+(6, 2, 'onu');
+INSERT INTO c VALUES
+(7, 1, 'wwj'),
+(7, 2, 'dwy'),
+(8, 1, 'wwr'),
+(8, 2, 'qcw'),
+(9, 1, 'dmi'),
+(9, 2, 'txa');
+INSERT INTO c VALUES
+(10, 1, 'qki'),
+(10, 2, 'jyf'),
+(11, 1, 'xgs'),
+(11, 2, 'par'),
+(12, 1, 'csu'),
+(12, 2, 'knp');
+INSERT INTO c VALUES
+(13, 1, 'ojd'),
+(13, 2, 'zbd'),
+(14, 1, 'bxz'),
+(14, 2, 'eeu'),
+(15, 1, 'wdh'),
+(15, 2, 'qsr');
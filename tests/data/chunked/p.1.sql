@@ -0,0 +1,20 @@
+INSERT INTO p VALUES
+(1, 744, 1),
+(2, 407, 2),
+(3, 425, 3);
+INSERT INTO p VALUES
+(4, 242, 4),
+(5, 225, 5),
+(6, 896, 6);
+INSERT INTO p VALUES
+(7, 269, 1),
+(8, 893, 2),
+(9, 508, 3);
+INSERT INTO p VALUES
+(10, 770, 4),
+(11, 36, 5),
+(12, 832, 6);
+INSERT INTO p VALUES
+(13, 356, 1),
+(14, 219, 2),
+(15, 989, 3);
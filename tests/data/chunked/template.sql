@@ -0,0 +1,4 @@
+{{ @x := 0 }}
+create table p (id int {{ rownum }}, v {{ rand.range(0, 1000) }}, s {{ @x := @x + 1 }});
+/*{{ for each row of p generate 2 rows of c }}*/
+create table c (pid {{ rownum }}, sub {{ subrownum }}, w {{ rand.regex('[a-z]{3}') }});
@@ -0,0 +1,5 @@
+INSERT INTO result VALUES
+(5, 22.66, 113.30, 'Vessqrm', 'Fiffyzj', 'Vessqrm Fiffyzj', 'VF'),
+(3, 21.36, 64.08, 'Nnzuvz', 'Tunfy', 'Nnzuvz Tunfy', 'NT'),
+(9, 84.49, 760.41, 'Gbvb', 'Utunmcm', 'Gbvb Utunmcm', 'GU'),
+(4, 46.13, 184.52, 'Fnmwy', 'Tfdhbik', 'Fnmwy Tfdhbik', 'FT');
@@ -0,0 +1,9 @@
+create table result (
+    qty integer {{ rand.range_inclusive(1, 9) }},
+    price decimal(6, 2) {{ rand.decimal(1, 100, 2) }},
+    total decimal(8, 2) {{ col(qty) * col(PRICE) }},
+    first_name text {{ rand.regex('[A-Z][a-z]{3,6}') }},
+    "Last Name" text {{ rand.regex('[A-Z][a-z]{3,6}') }},
+    full_name text {{ col(first_name) || ' ' || col("Last Name") }},
+    initials text {{ substring(col(first_name) from 1 for 1) || substring(col("Last Name") from 1 for 1) }}
+);
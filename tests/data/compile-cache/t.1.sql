@@ -0,0 +1,13 @@
+INSERT INTO t VALUES
+(1, 'bc', 7, 3),
+(2, 'd', 0, 1),
+(3, 'aaa', 2, 5),
+(4, 'cb', 0, 1),
+(5, 'd', 4, 1),
+(6, 'aaa', 0, 2),
+(7, 'cb', 8, 4),
+(8, 'd', 0, 6),
+(9, 'aaa', 7, 1),
+(10, 'bb', 0, 2),
+(11, 'd', 9, 2),
+(12, 'aaa', 0, 1);
@@ -0,0 +1,7 @@
+/*{{ @patterns := array['a{3}', '[b-c]{2}', 'd'] }}*/
+create table t (
+    id integer {{ rownum }},
+    word text {{ rand.regex(@patterns[1 + mod(rownum, 3)]) }},
+    small integer {{ rand.range(0, 1 + 9 * mod(rownum, 2)) }},
+    weighted integer {{ rand.zipf(@n := 10 + mod(rownum, 2), 0.8) }}
+);
@@ -0,0 +1,6 @@
+INSERT INTO result VALUES
+(297.24, 24.52, 321.76, 99.08000000, 1.0000000000),
+(118.07, 9.74, 127.81, 39.35666667, 1.0000000000),
+(460.95, 38.03, 498.98, 153.65000000, 1.0000000000),
+(204.87, 16.90, 221.77, 68.29000000, 1.0000000000),
+(358.88, 29.61, 388.49, 119.62666667, 1.0000000000);
@@ -0,0 +1,7 @@
+create table result (
+    price {{ @p := rand.decimal(0.01, 999.99, 2) }},
+    tax {{ round(@p * 0.0825, 2) }},
+    total {{ @p + round(@p * 0.0825, 2) }},
+    share {{ @p / 3 }},
+    exact {{ rand.decimal(1, 1, 10) }}
+);
@@ -0,0 +1,9 @@
+{{ @base := 100 }}
+create table result (
+    a {{ 2 * 3 }}
+    b {{ rand.range(0, 10) + rownum }}
+    c {{ lower(rand.regex('[A-Z]{3}')) || '-' || col(a) }}
+    d {{ CAST(col(b) AS VARCHAR(3)) }}
+    e {{ coalesce(lag(b), @base) }}
+    f {{ CASE WHEN rownum > 2 THEN -rownum ELSE @base := @base + 1 END }}
+);
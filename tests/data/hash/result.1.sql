@@ -0,0 +1,16 @@
+INSERT INTO result SET
+md5_a = 'D41D8CD98F00B204E9800998ECF8427E',
+md5_b = '9E107D9D372BB6826BD81D3542A419D6',
+sha1_a = 'A9993E364706816ABA3E25717850C26C9CD0D89D',
+sha256_a = 'BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD',
+sha512_a = '3a81oZNherrMQXNJriBBMRLm+k6JqX6iCp7u5ktV05ohkpkqJ0/BqDa6PCOj/uu9RU1EI2Q86A4qmslPpUyknw==',
+hmac_a = 'F7BC83F430538424B13298E6AA6FB143EF4D59A14946175997479DBC2D1A3CD8',
+hmac_b = '80070713463E7749B90C2DC24911E275',
+hmac_c = 'DE7C9B85B8B78AA6BC8A7A36F70A90701C9DB4D9',
+crc32_a = 1095738169,
+xxhash64_a = 17241709254077376921,
+xxhash64_b = 13738734796240226568,
+murmur3_a = 0,
+murmur3_b = 3142237357,
+octet_a = 64;
+
@@ -0,0 +1,16 @@
+create table result (
+    md5_a {{ to_hex(md5('')) }}
+    md5_b {{ to_hex(md5('The quick brown fox jumps over the lazy dog')) }}
+    sha1_a {{ to_hex(sha1('abc')) }}
+    sha256_a {{ to_hex(sha256('abc')) }}
+    sha512_a {{ to_base64(sha512('abc')) }}
+    hmac_a {{ to_hex(hmac('key', 'The quick brown fox jumps over the lazy dog')) }}
+    hmac_b {{ to_hex(hmac('key', 'The quick brown fox jumps over the lazy dog', 'md5')) }}
+    hmac_c {{ to_hex(hmac('key', 'The quick brown fox jumps over the lazy dog', 'SHA1')) }}
+    crc32_a {{ crc32('The quick brown fox jumps over the lazy dog') }}
+    xxhash64_a {{ xxhash64('') }}
+    xxhash64_b {{ xxhash64('abc', 1) }}
+    murmur3_a {{ murmur3('') }}
+    murmur3_b {{ murmur3('hello', 1) }}
+    octet_a {{ octet_length(sha512(X'00')) }}
+);
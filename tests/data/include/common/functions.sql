@@ -0,0 +1,3 @@
+-- shared helpers, resolved relative to this file
+{{ include '../names.sql' }}
+{{ function email(n) := user_name(n) || '@example.com' }}
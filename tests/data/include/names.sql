@@ -0,0 +1 @@
+{{ function user_name(n) := @prefix || '_' || n }}
@@ -0,0 +1,4 @@
+INSERT INTO result VALUES
+(1, 'user_1', 'user_1@example.com'),
+(2, 'user_2', 'user_2@example.com'),
+(3, 'user_3', 'user_3@example.com');
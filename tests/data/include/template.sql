@@ -0,0 +1,7 @@
+/*{{ include 'common/functions.sql' }}*/
+{{ @prefix := 'user' }}
+create table result (
+    id integer {{ rownum }},
+    name text {{ user_name(rownum) }},
+    email text {{ email(rownum) }}
+);
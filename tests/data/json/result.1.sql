@@ -0,0 +1,12 @@
+INSERT INTO result SET
+object_a = '{"a":1,"b":"x","c":null,"d":true,"e":2.5}',
+object_b = '{"k":3,"j":2}',
+object_c = '{}',
+array_a = '[1,"two",[3,4],{"five":5}]',
+build_a = '{"user":{"name":"bob","age":30},"tags":["x","y"]}',
+to_json_a = '"quote \" backslash \\ newline\ntab\t"',
+to_json_b = '[1,null,false]',
+to_json_c = NULL,
+to_json_d = '"2020-01-02 03:04:05"',
+concat_a = 'json: [1,2]';
+
@@ -0,0 +1,13 @@
+create table result (
+    object_a {{ json_object('a', 1, 'b', 'x', 'c', NULL, 'd', TRUE, 'e', 2.5) }}
+    object_b {{ json_object('k', 1, 'j', 2, 'k', 3) }}
+    object_c {{ json_object() }}
+    array_a {{ json_array(1, 'two', ARRAY[3, 4], json_object('five', 5)) }}
+    build_a {{ json_build('user.name', 'alice', 'user.age', 30, 'tags', ARRAY['x', 'y'], 'user.name', 'bob') }}
+    to_json_a {{ to_json('quote " backslash \ newline
+tab	') }}
+    to_json_b {{ to_json(ARRAY[1, NULL, FALSE]) }}
+    to_json_c {{ to_json(NULL) }}
+    to_json_d {{ to_json(TIMESTAMP '2020-01-02 03:04:05') }}
+    concat_a {{ 'json: ' || json_array(1, 2) }}
+);
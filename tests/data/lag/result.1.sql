@@ -0,0 +1,5 @@
+INSERT INTO result VALUES
+(1, 45, 45, NULL, -1, 0, 2, 1, -1),
+(2, 22, 67, 45, -1, 2, 3, 1, -1),
+(3, 64, 131, 22, 1, 3, 4, 3, -1),
+(4, 40, 171, 64, 2, 4, 5, 3, -1);
@@ -0,0 +1,5 @@
+INSERT INTO result VALUES
+(5, 8, 8, NULL, -1, 0, 6, 5, -1),
+(6, 75, 83, 8, -1, 6, 7, 5, -1),
+(7, 80, 163, 75, 5, 7, 8, 5, -1),
+(8, 23, 186, 80, 6, 8, 9, 5, -1);
@@ -0,0 +1,11 @@
+create table result (
+    id integer {{ rownum }},
+    amount integer {{ rand.range(1, 100) }},
+    balance integer {{ running_sum(col(amount)) }},
+    prev_amount integer {{ lag(amount) }},
+    prev_prev_id integer {{ lag(id, 2, -1) }},
+    prev_next integer {{ lag(next_id, 1, 0) }},
+    next_id integer {{ rownum + 1 }},
+    session integer {{ CASE WHEN rand.bool(0.3) THEN rownum ELSE coalesce(lag(session), rownum) END }},
+    far_id integer {{ lag(id, 65536, -1) }}
+);
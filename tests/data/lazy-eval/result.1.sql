@@ -0,0 +1,5 @@
+INSERT INTO result VALUES
+(1, 1, 1, 0, 0, 0, 1, 0, NULL, 1, 'odd', 'odd', 2),
+(2, 1, 1, 0, 0, 0, 10, 10, 2, 1, 'even', 'even', 2),
+(3, 1, 0, 1, 1, NULL, 1, 0, NULL, 0, 'odd', 'odd', 2),
+(4, 1, 0, 1, 1, NULL, 10, 10, NULL, 0, 'even', 'even', 2);
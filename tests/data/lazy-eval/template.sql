@@ -0,0 +1,15 @@
+create table result (
+    row_num {{ rownum }}
+    or_a {{ @or := 0; rownum > 2 OR (@or := 1) = 1 }}
+    or_b {{ @or }}
+    and_a {{ @and := 0; rownum > 2 AND (@and := 1) = 1 }}
+    and_b {{ @and }}
+    and_null {{ rownum > 2 AND NULL }}
+    coalesce_a {{ @co := 0; coalesce(nullif(mod(rownum, 2), 0), @co := 10) }}
+    coalesce_b {{ @co }}
+    nullif_a {{ @ni := 0; nullif(CASE WHEN rownum > 2 THEN NULL ELSE rownum END, @ni := 1) }}
+    nullif_b {{ @ni }}
+    if_a {{ @if := ''; if(mod(rownum, 2) = 0, @if := 'even', @if := 'odd') }}
+    if_b {{ @if }}
+    if_c {{ if(NULL, 1, 2) }}
+);
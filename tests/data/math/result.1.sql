@@ -0,0 +1,32 @@
+INSERT INTO result SET
+abs_a = 7,
+abs_b = 2.5,
+abs_c = NULL,
+floor_a = -3.0,
+ceil_a = -2.0,
+trunc_a = -2.0,
+floor_b = 7,
+sign_a = -1,
+sign_b = 0,
+sqrt_a = 4.0,
+power_a = 1024,
+power_b = 0.25,
+power_c = 1.4142135623730951,
+exp_a = 1.0,
+ln_a = 1.0,
+log10_a = 3.0,
+log2_a = 10.0,
+pi_a = 3.141592653589793,
+sin_a = 1.0,
+cos_a = 1.0,
+atan2_a = 1,
+degrees_a = 180.0,
+radians_a = 1,
+shl_a = 1024,
+shl_b = 32,
+shr_a = -128,
+shr_b = 0,
+shift_prec_a = 8,
+shift_prec_b = 2,
+shift_cmp_a = 1;
+
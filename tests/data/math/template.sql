@@ -0,0 +1,32 @@
+create table result (
+    abs_a {{ abs(-7) }}
+    abs_b {{ abs(-2.5) }}
+    abs_c {{ abs(NULL) }}
+    floor_a {{ floor(-2.5) }}
+    ceil_a {{ ceil(-2.5) }}
+    trunc_a {{ trunc(-2.5) }}
+    floor_b {{ floor(7) }}
+    sign_a {{ sign(-0.5) }}
+    sign_b {{ sign(0) }}
+    sqrt_a {{ sqrt(16) }}
+    power_a {{ power(2, 10) }}
+    power_b {{ power(2, -2) }}
+    power_c {{ power(2.0, 0.5) }}
+    exp_a {{ exp(0) }}
+    ln_a {{ ln(exp(1)) }}
+    log10_a {{ log10(1000) }}
+    log2_a {{ log2(1024) }}
+    pi_a {{ pi() }}
+    sin_a {{ sin(pi() / 2) }}
+    cos_a {{ cos(0) }}
+    atan2_a {{ atan2(1, 1) = pi() / 4 }}
+    degrees_a {{ degrees(pi()) }}
+    radians_a {{ radians(180) = pi() }}
+    shl_a {{ 1 << 10 }}
+    shl_b {{ 1 << 2 << 3 }}
+    shr_a {{ -1024 >> 3 }}
+    shr_b {{ 1024 >> 20 }}
+    shift_prec_a {{ 1 << 2 + 1 }}
+    shift_prec_b {{ 7 & 1 << 1 }}
+    shift_cmp_a {{ 1 << 4 < 100 }}
+);
@@ -0,0 +1,36 @@
+INSERT INTO result SET
+in_a = 1,
+in_b = 0,
+in_c = NULL,
+in_d = 1,
+in_e = NULL,
+in_f = 0,
+in_g = 1,
+in_h = NULL,
+in_i = 1,
+between_a = 1,
+between_b = 1,
+between_c = 0,
+between_d = NULL,
+between_e = 0,
+between_f = 1,
+between_g = 0,
+between_h = 1,
+like_a = 1,
+like_b = 1,
+like_c = 0,
+like_d = 1,
+like_e = 1,
+like_f = 0,
+like_g = 1,
+like_h = 1,
+like_i = 0,
+like_j = 0,
+like_k = 0,
+like_l = NULL,
+match_a = 1,
+match_b = 0,
+match_c = NULL,
+not_a = 1,
+case_a = 'mid';
+
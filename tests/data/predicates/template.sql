@@ -0,0 +1,36 @@
+create table result (
+    in_a {{ 3 IN (1, 2, 3) }}
+    in_b {{ 4 IN (1, 2, 3) }}
+    in_c {{ 4 IN (1, NULL, 3) }}
+    in_d {{ 1 IN (1, NULL, 3) }}
+    in_e {{ NULL IN (1, 2) }}
+    in_f {{ 'b' NOT IN ('a', 'b') }}
+    in_g {{ 'c' NOT IN ('a', 'b') }}
+    in_h {{ 4 NOT IN (1, NULL) }}
+    in_i {{ 2.0 IN (1, 1 + 1) }}
+    between_a {{ 5 BETWEEN 1 AND 10 }}
+    between_b {{ 10 BETWEEN 1 AND 10 }}
+    between_c {{ 11 BETWEEN 1 AND 10 }}
+    between_d {{ 5 BETWEEN NULL AND 10 }}
+    between_e {{ 11 BETWEEN NULL AND 10 }}
+    between_f {{ 0 NOT BETWEEN 1 AND 10 }}
+    between_g {{ 3 BETWEEN 1 AND 5 AND 2 < 1 }}
+    between_h {{ 'm' BETWEEN 'a' AND 'z' }}
+    like_a {{ 'hello' LIKE 'h%o' }}
+    like_b {{ 'hello' LIKE 'h_llo' }}
+    like_c {{ 'hello' LIKE 'h_lo' }}
+    like_d {{ 'héllo' LIKE 'h_llo' }}
+    like_e {{ '50%' LIKE '50\%' }}
+    like_f {{ '500' LIKE '50\%' }}
+    like_g {{ '5%' LIKE '5!%' ESCAPE '!' }}
+    like_h {{ 'a.c' LIKE 'a.c' }}
+    like_i {{ 'abc' LIKE 'a.c' }}
+    like_j {{ 'Hello' LIKE 'h%' }}
+    like_k {{ 'hello' NOT LIKE 'h%' }}
+    like_l {{ NULL LIKE '%' }}
+    match_a {{ 'abc123' ~ '^[a-z]+[0-9]+$' }}
+    match_b {{ 'abc' ~ '[0-9]' }}
+    match_c {{ NULL ~ 'a' }}
+    not_a {{ NOT 3 IN (1, 2) }}
+    case_a {{ CASE WHEN 7 BETWEEN 5 AND 9 THEN 'mid' ELSE 'out' END }}
+);
@@ -0,0 +1,7 @@
+INSERT INTO child VALUES
+(1, 1, 6),
+(2, 2, 4),
+(6, 1, 2),
+(6, 2, 8),
+(7, 1, 4),
+(8, 2, 4);
@@ -0,0 +1,9 @@
+INSERT INTO parent VALUES
+(1, 2, 8, 6),
+(2, 4, 6, 8),
+(3, 3, 9, 14),
+(4, 2, 5, 17),
+(5, 4, 8, 21),
+(6, 4, 8, 25),
+(7, 2, 9, 32),
+(8, 7, 8, 33);
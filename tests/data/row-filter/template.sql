@@ -0,0 +1,14 @@
+create table parent (
+    id integer {{ rownum }},
+    start_day integer {{ rand.range(1, 10) }},
+    end_day integer {{ rand.range(1, 10) }},
+    total integer {{ running_sum(col(end_day) - col(start_day)) }}
+    {{ RETRY UNTIL col(end_day) > col(start_day) MAX 100 }}
+);
+/*{{ for each row of parent generate 2 rows of child }}*/
+create table child (
+    parent_id integer {{ rownum }},
+    sub integer {{ subrownum }},
+    n integer {{ rand.range(0, 10) }}
+    /*{{ WHERE mod(col(n), 2) = 0 }}*/
+);
@@ -0,0 +1,56 @@
+INSERT INTO result SET
+case_a = 'SSTRASSE Ǆ',
+case_b = 'àéîõü ǆ',
+case_c = 'Hello World, Foo_Bar 3rd-Ёлка',
+case_d = X'41E942',
+trim_a = 'abc',
+trim_b = 'abc  ',
+trim_c = '  abc',
+trim_d = 'abc',
+trim_e = 'abc',
+trim_f = 'abc',
+trim_g = '',
+pad_a = '   abc',
+pad_b = 'abc🥰😘🥰',
+pad_c = 'abc',
+pad_d = '🥰😘',
+pad_e = '--🥰',
+pad_f = 'abc',
+pad_g = '',
+rep_a = 'ab🥰ab🥰ab🥰',
+rep_b = '',
+rep_c = '🥰c🥰c🥰',
+rep_d = 'abc',
+rev_a = '😘b🥰a',
+rev_b = X'B0A59FF06261',
+pos_a = 3,
+pos_b = 3,
+pos_c = 9,
+pos_d = 0,
+pos_e = 1,
+pos_f = 2,
+split_a = 'b',
+split_b = '',
+split_c = 'c',
+split_d = '',
+split_e = ARRAY['a', 'b', '', 'c'],
+split_f = ARRAY['a', 'b', NULL, 'c'],
+split_g = ARRAY['a', '🥰', 'b'],
+split_h = ARRAY[],
+split_i = 'a-b-3',
+split_j = 'a-*-b-3',
+re_a = 1,
+re_b = 0,
+re_c = 1,
+re_d = 'a#b22c333',
+re_e = 'a#b#c#',
+re_f = 'smith, john',
+fmt_a = 'a-12',
+fmt_b = '[   ab|🥰    ]',
+fmt_c = '00042|+42|ff|FF|10|101',
+fmt_d = '3.142|     -2.50|1.234568e+04|1.23E-04',
+fmt_e = 'b a b',
+fmt_f = '''it''''s'', NULL, 1.5, 100%',
+fmt_g = '🥰😘|xy',
+fmt_h = ' 🥰|';
+
@@ -0,0 +1,64 @@
+create table result (
+    case_a {{ upper('ßtraße ǆ') }}
+    case_b {{ lower('ÀÉÎÕÜ Ǆ') }}
+    case_c {{ initcap('hello wORLD, foo_bar 3rd-ёлка') }}
+    case_d {{ upper(X'61E962') }}
+
+    trim_a {{ trim('  abc  ') }}
+    trim_b {{ ltrim('  abc  ') }}
+    trim_c {{ rtrim('  abc  ') }}
+    trim_d {{ trim('xyxabcxy', 'xy') }}
+    trim_e {{ trim('🥰abc😘', '😘🥰') }}
+    trim_f {{ trim('🥰abc😘', '😘🥰' using octets) }}
+    trim_g {{ trim('xxx', 'x') }}
+
+    pad_a {{ lpad('abc', 6) }}
+    pad_b {{ rpad('abc', 6, '🥰😘') }}
+    pad_c {{ lpad('abcdef', 3) }}
+    pad_d {{ rpad('🥰😘😍', 2) }}
+    pad_e {{ lpad('🥰', 6, '-' using octets) }}
+    pad_f {{ lpad('abc', 6, '') }}
+    pad_g {{ rpad('abc', -1) }}
+
+    rep_a {{ repeat('ab🥰', 3) }}
+    rep_b {{ repeat('ab', 0) }}
+    rep_c {{ replace('abcabcab', 'ab', '🥰') }}
+    rep_d {{ replace('abc', '', 'x') }}
+
+    rev_a {{ reverse('a🥰b😘') }}
+    rev_b {{ reverse('ab🥰' using octets) }}
+
+    pos_a {{ position('c' in 'abc') }}
+    pos_b {{ position('c' in '🥰😘c') }}
+    pos_c {{ position('c' in '🥰😘c' using octets) }}
+    pos_d {{ position('z' in 'abc') }}
+    pos_e {{ position('' in 'abc') }}
+    pos_f {{ position('b' || 'c' in 'abc') }}
+
+    split_a {{ split_part('a,b,,c', ',', 2) }}
+    split_b {{ split_part('a,b,,c', ',', 3) }}
+    split_c {{ split_part('a,b,,c', ',', -1) }}
+    split_d {{ split_part('a,b,,c', ',', 9) }}
+    split_e {{ string_to_array('a,b,,c', ',') }}
+    split_f {{ string_to_array('a,b,,c', ',', '') }}
+    split_g {{ string_to_array('a🥰b', null) }}
+    split_h {{ string_to_array('', ',') }}
+    split_i {{ array_to_string(array['a', null, 'b', 3], '-') }}
+    split_j {{ array_to_string(array['a', null, array['b', 3]], '-', '*') }}
+
+    re_a {{ regexp_like('abc123', '[0-9]+$') }}
+    re_b {{ regexp_like('ABC', '^abc$') }}
+    re_c {{ regexp_like('ABC', '^abc$', 'i') }}
+    re_d {{ regexp_replace('a1b22c333', '[0-9]+', '#') }}
+    re_e {{ regexp_replace('a1b22c333', '[0-9]+', '#', 'g') }}
+    re_f {{ regexp_replace('john smith', '(\w+) (\w+)', '$2, $1') }}
+
+    fmt_a {{ format('%s-%s', 'a', 12) }}
+    fmt_b {{ format('[%5s|%-5s]', 'ab', '🥰') }}
+    fmt_c {{ format('%05d|%+d|%x|%X|%o|%b', 42, 42, 255, 255, 8, 5) }}
+    fmt_d {{ format('%.3f|%10.2f|%e|%.2E', 3.14159, -2.5, 12345.678, 0.000123) }}
+    fmt_e {{ format('%2$s %1$s %s', 'a', 'b') }}
+    fmt_f {{ format('%L, %L, %L, 100%%', 'it''s', null, 1.5) }}
+    fmt_g {{ printf('%.2s|%.2s', '🥰😘😍', 'xyz') }}
+    fmt_h {{ format('%5s|', '🥰' using octets) }}
+);
@@ -0,0 +1,5 @@
+INSERT INTO result VALUES
+('alice1@lhfvq.com', 4, '11', 1, 'row #11', 40, 6, 1112),
+('alice2@oflwt.com', 6, '33', 1, 'row #22', 20, 6, 1114),
+('alice3@fyzjg.com', 9, '00', 1, 'row #33', 20, 6, 1116),
+('alice4@ndnyo.com', 10, '33', 1, 'row #44', 43, 6, 1118);
@@ -0,0 +1,16 @@
+{{ FUNCTION mk_email(n) := lower(n) || '@' || rand.regex('[a-z]{5}\.com') }}
+{{ FUNCTION clamp(v, lo, hi) := greatest(lo, least(v, hi)) }}
+{{ FUNCTION Twice(x) := x || x }}
+{{ FUNCTION pick_one() := rand.range(0, 100) }}
+{{ FUNCTION add(a, b) := a + b }}
+/*{{ FUNCTION label(n) := 'row #' || Twice(n) }}*/
+create table result (
+    email {{ mk_email('Alice' || rownum) }}
+    clamped {{ clamp(rownum * 3, 4, 10) }}
+    twice {{ twice(rand.range(0, 10)) }}
+    nested {{ clamp(clamp(rownum, 1, 2), 0, 1) }}
+    label {{ label(rownum) }}
+    picked {{ pick_one() }}
+    nested_later {{ add(1, add(2, 3)) }}
+    nested_both {{ add(add(rownum, 10), add(add(100, rownum), 1000)) }}
+);